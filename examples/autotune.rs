@@ -0,0 +1,63 @@
+//! Autotune a controller and print the gains it settles on.
+//!
+//!     cargo run --example autotune --features simulator
+//!
+//! tunes unit 5 on the serial port below, which takes as long as the process
+//! needs to go through a few heating cycles. With `SYL2381_SIM=1` it tunes
+//! the in-crate simulator on a virtual clock instead, and finishes at once.
+
+use std::env;
+use std::time::Duration;
+
+extern crate syl2381;
+use syl2381::clock::{StdClock, VirtualClock};
+use syl2381::simulator::Simulator;
+use syl2381::{Clock, IoTransport, Syl2381};
+
+fn main() {
+    if env::var_os("SYL2381_SIM").is_some() {
+        let clock = VirtualClock::new();
+        let mut pid = Syl2381::new(5, Simulator::new(5, &clock));
+        autotune(&mut pid, &clock);
+        return;
+    }
+
+    let port_name = "/dev/tty.usbserial-A10MMQO2";
+
+    let port = serialport::new(port_name, 9600)
+        .data_bits(serialport::DataBits::Eight)
+        .parity(serialport::Parity::None)
+        .stop_bits(serialport::StopBits::One)
+        .flow_control(serialport::FlowControl::None)
+        .timeout(Duration::from_secs(3))
+        .open()
+        .expect("opening serial port");
+
+    let port = IoTransport::new(port);
+
+    let mut pid = Syl2381::new(5, port);
+
+    autotune(&mut pid, &StdClock::new());
+}
+
+fn autotune<S>(pid: &mut Syl2381<S>, clock: &impl Clock)
+where
+    S: syl2381::Transport,
+    S::Error: std::fmt::Debug,
+{
+    if let Err(err) = pid.start_autotune() {
+        eprintln!("{}", err);
+        return;
+    }
+    println!("autotuning");
+
+    let poll = Duration::from_secs(5);
+    let timeout = Duration::from_secs(2 * 60 * 60);
+    match pid.wait_autotune_complete(clock, poll, timeout) {
+        Ok(gains) => println!("P = {}, I = {}, D = {}", gains.p, gains.i, gains.d),
+        Err(err) => {
+            eprintln!("{}", err);
+            let _ = pid.abort_autotune();
+        }
+    }
+}
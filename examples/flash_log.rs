@@ -0,0 +1,147 @@
+//! Keep an audit log in a flash sector.
+//!
+//!     cargo run --example flash_log --features audit,simulator
+//!
+//! An MCU with no filesystem can still persist through
+//! [`Storage`](syl2381::storage::Storage). `Flash` below uses nothing but
+//! `core`: it appends `key, blob` entries to a NOR flash sector, which can only
+//! be erased as a whole and then programmed once, and reads back the newest
+//! entry for a key. Here the sector is an array, and the controller is the
+//! in-crate simulator; on a board both would be real.
+
+use core::time::Duration;
+
+extern crate syl2381;
+use syl2381::audit::{self, Audited, Log, Record};
+use syl2381::clock::VirtualClock;
+use syl2381::simulator::Simulator;
+use syl2381::storage::Storage;
+use syl2381::{Syl2381, TempRaw};
+
+const SECTOR_LEN: usize = 4096;
+
+/// Erased flash reads as all ones.
+const ERASED: u8 = 0xFF;
+
+#[derive(Debug)]
+enum FlashError {
+    /// The sector is full of live entries.
+    Full,
+}
+
+/// Entries are a key length byte, the key, a little-endian `u16` blob
+/// length and the blob, one after another from the start of the sector.
+struct Flash {
+    sector: [u8; SECTOR_LEN],
+    /// Where the next entry goes.
+    end: usize,
+}
+
+impl Flash {
+    fn erased() -> Self {
+        Flash {
+            sector: [ERASED; SECTOR_LEN],
+            end: 0,
+        }
+    }
+
+    /// `(key, blob)` for each entry, oldest first.
+    fn entries(sector: &[u8], end: usize) -> impl Iterator<Item = (&[u8], &[u8])> {
+        let mut at = 0;
+        core::iter::from_fn(move || {
+            if at >= end {
+                return None;
+            }
+            let key_len = sector[at] as usize;
+            let key = &sector[at + 1..at + 1 + key_len];
+            at += 1 + key_len;
+            let len = u16::from_le_bytes([sector[at], sector[at + 1]]) as usize;
+            let blob = &sector[at + 2..at + 2 + len];
+            at += 2 + len;
+            Some((key, blob))
+        })
+    }
+
+    fn program(&mut self, key: &[u8], blob: &[u8]) -> Result<(), FlashError> {
+        let len = 1 + key.len() + 2 + blob.len();
+        if self.end + len > SECTOR_LEN {
+            return Err(FlashError::Full);
+        }
+        let entry = &mut self.sector[self.end..self.end + len];
+        entry[0] = key.len() as u8;
+        entry[1..1 + key.len()].copy_from_slice(key);
+        entry[1 + key.len()..3 + key.len()].copy_from_slice(&(blob.len() as u16).to_le_bytes());
+        entry[3 + key.len()..].copy_from_slice(blob);
+        self.end += len;
+        Ok(())
+    }
+
+    /// Erase the sector and program back the newest entry for each key.
+    fn compact(&mut self) -> Result<(), FlashError> {
+        let old = self.sector;
+        let old_end = self.end;
+        *self = Flash::erased();
+        for (i, (key, blob)) in Flash::entries(&old, old_end).enumerate() {
+            let newer = Flash::entries(&old, old_end)
+                .skip(i + 1)
+                .any(|(k, _)| k == key);
+            if !newer {
+                self.program(key, blob)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Storage for Flash {
+    type Error = FlashError;
+
+    fn read(&mut self, key: &str, buf: &mut [u8]) -> Result<Option<usize>, FlashError> {
+        let newest = Flash::entries(&self.sector, self.end)
+            .filter(|(k, _)| *k == key.as_bytes())
+            .last();
+        Ok(newest.map(|(_, blob)| {
+            let n = blob.len().min(buf.len());
+            buf[..n].copy_from_slice(&blob[..n]);
+            blob.len()
+        }))
+    }
+
+    fn write(&mut self, key: &str, blob: &[u8]) -> Result<(), FlashError> {
+        match self.program(key.as_bytes(), blob) {
+            Err(FlashError::Full) => {
+                self.compact()?;
+                self.program(key.as_bytes(), blob)
+            }
+            done => done,
+        }
+    }
+}
+
+fn main() {
+    let clock = VirtualClock::new();
+    let mut log = Log::new(Flash::erased());
+
+    // Two power cycles, each resuming the chain from flash.
+    for sv in [65.0, 70.0] {
+        let chain = log.chain().expect("reading the log");
+        let sink = |rec: &Record| log.push(rec);
+        let sim = Simulator::new(5, &clock);
+        let mut pid = Syl2381::new(5, Audited::new(sim, &clock, chain, sink));
+        pid.set_sv(TempRaw(sv)).expect("setting SV");
+        clock.advance(Duration::from_secs(60));
+    }
+
+    let mut records = Vec::new();
+    while let Some(rec) = log.get(records.len() as u64).expect("reading the log") {
+        println!(
+            "#{} at {:?}: {:#06X} = {:?}",
+            rec.seq, rec.at, rec.addr, rec.value
+        );
+        records.push(rec);
+    }
+    match audit::verify(&records) {
+        Ok(()) => println!("chain intact"),
+        Err(i) => println!("chain broken at record {}", i),
+    }
+}
@@ -0,0 +1,42 @@
+//! Configure a SYL-2381 with a PT100 probe as an on/off freezer thermostat.
+//!
+//! The compressor is driven by the J1 relay, which turns on when the
+//! temperature rises above SV + Hy and off once it's back down to SV.
+
+use std::time::Duration;
+
+extern crate syl2381;
+use syl2381::{ControlDirection, InputType, IoTransport, OutputMode, Syl2381, TempRaw};
+
+fn main() {
+    let port_name = "/dev/tty.usbserial-A10MMQO2";
+
+    let port = serialport::new(port_name, 9600)
+        .data_bits(serialport::DataBits::Eight)
+        .parity(serialport::Parity::None)
+        .stop_bits(serialport::StopBits::One)
+        .flow_control(serialport::FlowControl::None)
+        .timeout(Duration::from_secs(3))
+        .open()
+        .expect("opening serial port");
+
+    let port = IoTransport::new(port);
+
+    let mut pid = Syl2381::new(5, port);
+
+    let configured = pid
+        .set_input_sensor_type(InputType::P10_0)
+        .and_then(|_| pid.set_control_direction(ControlDirection::Cooling))
+        .and_then(|_| pid.set_output_mode(OutputMode::J1RelayAsOnOffControlOutputSsrPortDisabled))
+        .and_then(|_| pid.set_hysteresis(TempRaw(2.0)))
+        .and_then(|_| pid.set_intput_offset(TempRaw(-0.5)))
+        .and_then(|_| pid.set_sv(TempRaw(-18.0)));
+    if configured.is_err() {
+        eprintln!("failed to configure controller");
+        return;
+    }
+
+    if let (Ok(pv), Ok(sv)) = (pid.get_pv(), pid.get_sv()) {
+        println!("PV = {:.1}, SV = {:.1}", pv, sv);
+    }
+}
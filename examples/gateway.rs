@@ -0,0 +1,43 @@
+//! A Modbus TCP gateway: serves a controller on a local serial port to SCADA
+//! systems on the network.
+//!
+//!     cargo run --example gateway --features gateway -- 0.0.0.0:502 /dev/ttyUSB0 5
+//!
+//! serves unit 5 on `/dev/ttyUSB0` to Modbus TCP clients on port 502. One
+//! client is served at a time.
+
+use std::env;
+use std::net::TcpListener;
+use std::time::Duration;
+
+extern crate syl2381;
+use syl2381::gateway::Gateway;
+use syl2381::{IoTransport, Syl2381};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let [listen, port_name, unit_id] = &args[..] else {
+        eprintln!("usage: gateway <listen addr> <serial port> <unit id>");
+        std::process::exit(2);
+    };
+    let unit_id = unit_id.parse().expect("unit id");
+
+    let port = serialport::new(port_name, 9600)
+        .data_bits(serialport::DataBits::Eight)
+        .parity(serialport::Parity::None)
+        .stop_bits(serialport::StopBits::One)
+        .flow_control(serialport::FlowControl::None)
+        .timeout(Duration::from_secs(1))
+        .open()
+        .expect("opening serial port");
+    let mut gateway = Gateway::new(Syl2381::new(unit_id, IoTransport::new(port)));
+
+    let listener = TcpListener::bind(listen).expect("binding listener");
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let peer_addr = stream.peer_addr().ok();
+        if let Err(err) = gateway.serve(stream) {
+            eprintln!("{:?}: {}", peer_addr, err);
+        }
+    }
+}
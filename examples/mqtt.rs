@@ -0,0 +1,85 @@
+//! Announce a controller to Home Assistant and bridge it to MQTT.
+//!
+//!     cargo run --example mqtt --features mqtt,simulator
+//!
+//! bridges unit 5 on the serial port below. With `SYL2381_SIM=1` it bridges
+//! the in-crate simulator instead. There's no broker here: every message the
+//! bridge would publish is printed, and a command setting SV is fed in as if
+//! it had arrived on the command topic. Implement `Publish` for a real MQTT
+//! client to go further.
+
+use std::convert::Infallible;
+use std::env;
+use std::time::Duration;
+
+extern crate syl2381;
+use syl2381::clock::StdClock;
+use syl2381::mqtt::{Bridge, Publish, Topics};
+use syl2381::simulator::Simulator;
+use syl2381::{IoTransport, Syl2381};
+
+/// Prints messages instead of sending them.
+struct Print;
+
+impl Publish for Print {
+    type Error = Infallible;
+
+    fn publish(&mut self, topic: &str, payload: &[u8], retain: bool) -> Result<(), Infallible> {
+        let retained = if retain { " (retained)" } else { "" };
+        println!(
+            "{}{}: {}",
+            topic,
+            retained,
+            String::from_utf8_lossy(payload)
+        );
+        Ok(())
+    }
+}
+
+fn main() {
+    if env::var_os("SYL2381_SIM").is_some() {
+        let mut pid = Syl2381::new(5, Simulator::new(5, StdClock::new()));
+        bridge(&mut pid);
+        return;
+    }
+
+    let port_name = "/dev/tty.usbserial-A10MMQO2";
+
+    let port = serialport::new(port_name, 9600)
+        .data_bits(serialport::DataBits::Eight)
+        .parity(serialport::Parity::None)
+        .stop_bits(serialport::StopBits::One)
+        .flow_control(serialport::FlowControl::None)
+        .timeout(Duration::from_secs(3))
+        .open()
+        .expect("opening serial port");
+
+    let port = IoTransport::new(port);
+
+    let mut pid = Syl2381::new(5, port);
+
+    bridge(&mut pid);
+}
+
+fn bridge<S>(pid: &mut Syl2381<S>)
+where
+    S: syl2381::Transport,
+    S::Error: std::fmt::Debug,
+{
+    let identity = match pid.probe() {
+        Ok(identity) => identity,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+
+    let mut bridge = Bridge::new(Print, Topics::new("kiln"));
+    let Ok(()) = bridge.publish_discovery("kiln", pid.labels(), &identity);
+    let Ok(_) = bridge.publish_state(pid);
+
+    let command = bridge.topics().command.clone();
+    let set_sv = br#"{"param": "Sv", "value": {"Temp": 65.0}}"#;
+    let Ok(_) = bridge.handle(pid, &command, set_sv);
+    let Ok(_) = bridge.publish_state(pid);
+}
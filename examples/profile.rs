@@ -0,0 +1,69 @@
+//! Push a configuration profile to a controller, or print the one it has.
+//!
+//!     cargo run --example profile --features profile,simulator -- kiln.toml
+//!
+//! applies `kiln.toml` to unit 5 on the serial port below and lists what
+//! changed. Without a path it prints the controller's configuration as TOML,
+//! ready to be saved as a profile. With `SYL2381_SIM=1` it talks to the
+//! in-crate simulator instead.
+
+use std::env;
+use std::time::Duration;
+
+extern crate syl2381;
+use syl2381::clock::StdClock;
+use syl2381::profile::{self, Format};
+use syl2381::simulator::Simulator;
+use syl2381::{Config, IoTransport, Syl2381};
+
+fn main() {
+    let path = env::args().nth(1);
+
+    if env::var_os("SYL2381_SIM").is_some() {
+        let mut pid = Syl2381::new(5, Simulator::new(5, StdClock::new()));
+        run(&mut pid, path.as_deref());
+        return;
+    }
+
+    let port_name = "/dev/tty.usbserial-A10MMQO2";
+
+    let port = serialport::new(port_name, 9600)
+        .data_bits(serialport::DataBits::Eight)
+        .parity(serialport::Parity::None)
+        .stop_bits(serialport::StopBits::One)
+        .flow_control(serialport::FlowControl::None)
+        .timeout(Duration::from_secs(3))
+        .open()
+        .expect("opening serial port");
+
+    let port = IoTransport::new(port);
+
+    let mut pid = Syl2381::new(5, port);
+
+    run(&mut pid, path.as_deref());
+}
+
+fn run<S>(pid: &mut Syl2381<S>, path: Option<&str>)
+where
+    S: syl2381::Transport,
+    S::Error: std::fmt::Debug,
+{
+    let Some(path) = path else {
+        match pid.read_config() {
+            Ok(cfg) => print!("{}", profile::to_string(Format::Toml, &cfg).unwrap()),
+            Err(err) => eprintln!("{}", err),
+        }
+        return;
+    };
+
+    let cfg: Config = profile::load(path).expect("reading profile");
+    match pid.apply(&cfg) {
+        Ok(changes) if changes.is_empty() => println!("nothing to change"),
+        Ok(changes) => {
+            for change in &changes {
+                println!("{}", change);
+            }
+        }
+        Err(err) => eprintln!("{}", err),
+    }
+}
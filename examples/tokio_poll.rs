@@ -0,0 +1,34 @@
+//! Poll PV from a tokio task, as a web dashboard would.
+
+use std::time::Duration;
+
+extern crate syl2381;
+use syl2381::{Syl2381Async, TokioPort};
+
+use tokio_serial::SerialPortBuilderExt;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let port_name = "/dev/tty.usbserial-A10MMQO2";
+
+    let port = tokio_serial::new(port_name, 9600)
+        .data_bits(tokio_serial::DataBits::Eight)
+        .parity(tokio_serial::Parity::None)
+        .stop_bits(tokio_serial::StopBits::One)
+        .flow_control(tokio_serial::FlowControl::None)
+        .open_native_async()
+        .expect("opening serial port");
+
+    let port = TokioPort::new(port, Duration::from_secs(1));
+
+    let mut pid = Syl2381Async::new(5, port);
+
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        interval.tick().await;
+        match pid.get_pv().await {
+            Ok(pv) => println!("PV = {:.1}", pv),
+            Err(_) => eprintln!("failed to read PV"),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! The remote end of a [`Tunnel`](syl2381::Tunnel): a serial bridge that
+//! relays tunnelled Modbus frames to a local RS-485 port.
+//!
+//!     cargo run --example tunnel_bridge --features tunnel -- keygen
+//!
+//! prints a fresh key pair (run it once for the bridge and once for the
+//! client). Then, on the machine with the serial adapter:
+//!
+//!     cargo run --example tunnel_bridge --features tunnel -- \
+//!         0.0.0.0:5020 /dev/ttyUSB0 <bridge private key> <client public key>
+//!
+//! Only the client holding the matching private key can connect. One client
+//! is served at a time.
+
+use std::env;
+use std::io::{self, Read, Write};
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+
+extern crate syl2381;
+use syl2381::tunnel::{Keypair, Tunnel};
+
+/// A response is complete once the line has been quiet this long.
+const QUIET: Duration = Duration::from_millis(50);
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("keygen") {
+        let keys = Keypair::generate().expect("generating keys");
+        println!("private {}", hex(&keys.private));
+        println!("public  {}", hex(&keys.public));
+        return;
+    }
+
+    let [listen, port_name, private, peer] = &args[..] else {
+        eprintln!("usage: tunnel_bridge <listen addr> <serial port> <private key> <client key>");
+        std::process::exit(2);
+    };
+    let private = unhex(private).expect("bridge private key");
+    let peer = unhex(peer).expect("client public key");
+
+    let mut port = serialport::new(port_name, 9600)
+        .data_bits(serialport::DataBits::Eight)
+        .parity(serialport::Parity::None)
+        .stop_bits(serialport::StopBits::One)
+        .flow_control(serialport::FlowControl::None)
+        .timeout(QUIET)
+        .open()
+        .expect("opening serial port");
+
+    let listener = TcpListener::bind(listen).expect("binding listener");
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let peer_addr = stream.peer_addr().ok();
+        let tunnel = match Tunnel::accept(stream, &private, &peer) {
+            Ok(tunnel) => tunnel,
+            Err(err) => {
+                eprintln!("{:?}: handshake failed: {}", peer_addr, err);
+                continue;
+            }
+        };
+        eprintln!("{:?}: connected", peer_addr);
+        if let Err(err) = relay(tunnel, &mut port) {
+            eprintln!("{:?}: {}", peer_addr, err);
+        }
+    }
+}
+
+/// Pass requests to the serial port and responses back until the client
+/// goes away.
+fn relay<S, P>(mut tunnel: Tunnel<S>, port: &mut P) -> io::Result<()>
+where
+    S: Read + Write,
+    P: Read + Write + ?Sized,
+{
+    let mut buf = [0; 256];
+    loop {
+        let request = tunnel.recv()?;
+        port.write_all(&request)?;
+
+        let started = Instant::now();
+        let mut response = Vec::new();
+        while started.elapsed() < RESPONSE_TIMEOUT {
+            match port.read(&mut buf) {
+                Ok(n) => response.extend_from_slice(&buf[..n]),
+                Err(err) if err.kind() == io::ErrorKind::TimedOut => {
+                    if !response.is_empty() {
+                        break;
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        if !response.is_empty() {
+            tunnel.send(&response)?;
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unhex(s: &str) -> Option<[u8; 32]> {
+    let mut key = [0; 32];
+    if s.len() != 64 {
+        return None;
+    }
+    for (i, b) in key.iter_mut().enumerate() {
+        *b = u8::from_str_radix(s.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(key)
+}
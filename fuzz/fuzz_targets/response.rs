@@ -0,0 +1,59 @@
+//! Arbitrary bytes as the controller's response.
+//!
+//! The first byte picks the request; the rest is what comes back, fed both
+//! through an [`Exchange`] (in chunks sized by the second byte) and through
+//! the blocking driver. Neither may panic, whatever arrives.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use syl2381::exchange::{Exchange, Op};
+use syl2381::{regs, Syl2381, Transport};
+
+/// Serves `rx` and then runs dry.
+struct Canned<'a> {
+    rx: &'a [u8],
+}
+
+impl Transport for Canned<'_> {
+    type Error = ();
+
+    fn write_frame(&mut self, _frame: &[u8]) -> Result<(), ()> {
+        Ok(())
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), ()> {
+        if buf.len() > self.rx.len() {
+            return Err(());
+        }
+        let (head, rest) = self.rx.split_at(buf.len());
+        buf.copy_from_slice(head);
+        self.rx = rest;
+        Ok(())
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let [pick, chunk, rx @ ..] = data else {
+        return;
+    };
+    let op = match pick % 3 {
+        0 => Op::Get(regs::PV),
+        1 => Op::Set(regs::SV, 65.0),
+        _ => Op::GetCoils(regs::AT, pick / 3 % 8 + 1),
+    };
+
+    let mut ex = Exchange::new(1, op).unwrap();
+    for chunk in rx.chunks(*chunk as usize + 1) {
+        if ex.feed(chunk).is_some() {
+            break;
+        }
+    }
+
+    let mut pid = Syl2381::new(1, Canned { rx });
+    let _ = match op {
+        Op::Get(reg) => pid.read_holding_raw(reg.addr, 2).map(drop),
+        Op::Set(reg, _) => pid.write_holding_raw(reg.addr, &[0x4282, 0]),
+        Op::GetCoils(reg, count) => pid.read_coils_raw(reg.addr, count).map(drop),
+    };
+});
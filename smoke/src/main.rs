@@ -0,0 +1,181 @@
+//! Links every public driver operation into a `no_std` binary.
+
+#![no_std]
+#![no_main]
+
+use core::panic::PanicInfo;
+use core::time::Duration;
+
+use embedded_io::{ErrorKind, ErrorType, Read, ReadReady, Write, WriteReady};
+use syl2381::audit::{Audited, Chain, Record};
+use syl2381::breaker::Breaker;
+use syl2381::clock::VirtualClock;
+use syl2381::exchange::Exchange;
+use syl2381::machine::{Machine, Op};
+use syl2381::plausibility::{JumpGuard, Plausibility};
+use syl2381::logger::{Field, Logger};
+use syl2381::regs;
+use syl2381::sample::Sampler;
+use syl2381::watch::StatusWatcher;
+use syl2381::{
+    AlarmConfig, Bus, Config, DisplayUnit, EmbeddedIo, InputType, Labels, Lockout, OutputType, Param, ParamValue, Percent, Pid, Seconds, SsrWithJ1Alarm, StaticCache, Syl2381, Temperature, WriteGuard, WriteStrategy,
+    TempRaw,
+};
+
+/// A UART that never has anything to say.
+struct NullUart;
+
+impl ErrorType for NullUart {
+    type Error = ErrorKind;
+}
+
+impl Read for NullUart {
+    fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Self::Error> {
+        Err(ErrorKind::Other)
+    }
+}
+
+impl Write for NullUart {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl ReadReady for NullUart {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+}
+
+impl WriteReady for NullUart {
+    fn write_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn _start() -> ! {
+    let clock = VirtualClock::new();
+    let mut pid = Syl2381::new(1, EmbeddedIo::new(NullUart)).with_clock(&clock);
+
+    let _ = pid.get_pv();
+    let _ = pid.get_status();
+    let _ = pid.set_sv(TempRaw(65.0));
+    if let Ok(mut manual) = pid.enter_manual_output() {
+        let _ = manual.set_out(Percent(0.5));
+        let _ = manual.exit();
+    }
+    let _ = pid.set_i(Seconds(120));
+    let _ = pid.get_input_sensor_type();
+    pid.set_labels(Labels::new("Kiln").with_probe("K"));
+    let _ = pid.read_snapshot();
+    let _ = pid.probe();
+    pid.set_lockout(Lockout::Fail);
+    pid.set_verify_writes(true);
+    pid.set_write_strategy(WriteStrategy::Fallback);
+    pid.set_write_guard(Some(WriteGuard {
+        min_interval: Duration::from_secs(60),
+    }));
+    pid.set_static_cache(Some(StaticCache { max_age: None }));
+    pid.invalidate_static_cache();
+    let _ = pid.read_pv_checked();
+    let mut watcher = StatusWatcher::new();
+    watcher.on_event(|_, _| {});
+    let _ = watcher.poll(&mut pid);
+    if let Ok(gains) = pid.get_pid() {
+        let _ = pid.set_pid(Pid { p: gains.p * 2.0, ..gains });
+    }
+    let _ = pid.poll();
+    let _ = pid.set_sv(Temperature::celsius(75.0));
+    let _ = pid.set_temperature(160.0, DisplayUnit::Fahrenheit);
+    let _ = pid.configure_onoff_control(TempRaw(4.0), TempRaw(2.0));
+    let _ = pid.configure_alarm(AlarmConfig::Absolute {
+        on: TempRaw(100.0),
+        off: TempRaw(95.0),
+    });
+    if let Ok(pv) = pid.get_pv() {
+        let _ = pid.temperature(pv);
+    }
+    let _ = pid.read_holding_raw(0x3000, 2);
+    let _ = pid.write_holding_raw(0x3000, &[0, 1]);
+    let _ = pid.read_coil_raw(0x0006);
+    for param in Param::ALL {
+        if param.writable() && param.meta().min < 0.0 {
+            let _ = pid.get_param(param);
+        }
+    }
+    let _ = pid.set_param(Param::Sv, ParamValue::Temp(TempRaw(65.0)));
+
+    let mut flash = [0; syl2381::stored::CONFIG_LEN];
+    if let Ok(cfg) = syl2381::stored::from_bytes::<Config>(&flash) {
+        let _ = pid.apply(&cfg);
+        let _ = pid.diff(&cfg);
+        let _ = syl2381::stored::to_slice(&cfg, &mut flash);
+    }
+
+    let _ = pid.get_pv_cached(&clock, Duration::from_secs(1));
+    let mut sampler = Sampler::new(&clock, Duration::from_secs(1)).with_out();
+    for sample in sampler.samples(&mut pid).take(3) {
+        let _ = sample;
+    }
+    let mut log: Logger<64> = Logger::new();
+    let _ = log.log(&mut pid, &clock);
+    let _ = log.summary(Field::Pv, Duration::from_secs(60)..);
+    let _ = pid.start_autotune();
+    let _ = pid.get_autotune();
+    let _ = pid.get_manual_mode();
+    let _ = pid.set_manual_mode(false);
+    let _ = pid.get_cooling_mode();
+    let _ = pid.get_setting_mode();
+    let _ = pid.get_anomaly();
+    let _ = pid.abort_autotune();
+    let _ = pid.wait_autotune_complete(&clock, Duration::from_secs(5), Duration::from_secs(60));
+    let _ = pid.get_pv_plausible(&Plausibility::new(InputType::K, DisplayUnit::Celsius));
+    let _ = pid.get_pv_confirmed(&mut JumpGuard::new(5.0));
+    let _ = pid.configure_ssr_with_j1_alarm(&SsrWithJ1Alarm {
+        output_type: OutputType::SSR,
+        alarm_on: TempRaw(100.0),
+        alarm_off: TempRaw(95.0),
+    });
+
+    let mut breaker = Breaker::new(pid, 3, Duration::from_secs(10));
+    let _ = breaker.call(|pid| pid.get_pv());
+
+    let audited = Audited::new(
+        EmbeddedIo::new(NullUart),
+        &clock,
+        Chain::new(),
+        |_: &Record| Ok::<_, ()>(()),
+    );
+    let mut pid = Syl2381::new(1, audited);
+    let _ = pid.set_sv(TempRaw(65.0));
+
+    let bus = Bus::new(EmbeddedIo::new(NullUart));
+    let mut kiln = bus.unit(1);
+    let mut smoker = bus.unit(2);
+    let _ = kiln.get_pv();
+    let _ = smoker.get_pv();
+    let _ = smoker.provision(&Config::default(), &[3, 4]).count();
+
+    let mut machine = Machine::new(1, NullUart);
+    let _ = machine.enqueue(Op::Get(regs::PV));
+    let _ = machine.step();
+    let _ = machine.take();
+
+    if let Ok(mut exchange) = Exchange::new(1, Op::Set(regs::SV, 65.0)) {
+        let _ = NullUart.write(exchange.request());
+        let _ = exchange.wanted();
+        let _ = exchange.feed(&[0x01, 0x10]);
+    }
+
+    loop {}
+}
+
+#[panic_handler]
+fn panic(_info: &PanicInfo) -> ! {
+    loop {}
+}
@@ -0,0 +1,597 @@
+//! Async driver built on the `embedded-io-async` traits.
+//!
+//! [`Syl2381Async`] mirrors every getter and setter of the blocking
+//! [`Syl2381`](crate::Syl2381) driver, sharing its framing and validation, so it can run on an
+//! async executor (e.g. embassy) without stalling it on `nb::block!`.
+//!
+//! It talks through a [`ModbusClient`]: an `embedded-io-async` port, or an
+//! existing Modbus stack (see [`client`](crate::client)).
+//!
+//! OUT is the exception: writing it is only safe behind the CV guard
+//! ([`ManualOutput`](crate::ManualOutput)), which relies on `Drop` to hand
+//! control back and so can't be done across an `.await`.
+//!
+//! Nothing here needs a UART, a clock or `std`, so with
+//! `default-features = false, features = ["async"]` it also builds for
+//! `wasm32-unknown-unknown`: a browser commissioning tool implements the
+//! `embedded-io-async` traits over WebSerial, or over a WebSocket to an RTU
+//! proxy, and gets the same register handling as everything else.
+
+use crate::client::ModbusClient;
+use crate::plausibility::Plausibility;
+use crate::{limits, regs, try_from_f32};
+use crate::{
+    AnyTemp, BaudRate, Context, ControlDirection, DisplayUnit, Error, Filter, InputType, Operation,
+    OutputMode, OutputType, Percent, Seconds, Status, TempRaw, Temperature,
+};
+
+pub type AsyncResult<T, UART> = core::result::Result<T, Error<<UART as ModbusClient>::Error>>;
+
+pub struct Syl2381Async<UART> {
+    unit_id: u8,
+    port: UART,
+    display_unit: Option<DisplayUnit>,
+    input_type: Option<InputType>,
+}
+
+impl<UART> Syl2381Async<UART>
+where
+    UART: ModbusClient,
+{
+    pub fn new(unit_id: u8, port: UART) -> Self {
+        Syl2381Async {
+            unit_id,
+            port,
+            display_unit: None,
+            input_type: None,
+        }
+    }
+
+    /// Take `input` and `unit` as already read, for tests that aren't about
+    /// the sensor range check.
+    #[cfg(test)]
+    pub(crate) fn assume_sensor(&mut self, input: InputType, unit: DisplayUnit) {
+        self.input_type = Some(input);
+        self.display_unit = Some(unit);
+    }
+
+    /// Get the process value (PV).
+    pub async fn get_pv(&mut self) -> AsyncResult<TempRaw, UART> {
+        let val = self.get_holding(regs::PV).await?;
+        Ok(TempRaw(val))
+    }
+
+    /// Get the power output percentage (OUT).
+    pub async fn get_out(&mut self) -> AsyncResult<Percent, UART> {
+        let val = self.get_holding(regs::OUT).await?;
+        Ok(Percent(val))
+    }
+
+    /// Get J1 status flag (AL1_STA).
+    pub async fn get_j1_status(&mut self) -> AsyncResult<bool, UART> {
+        self.get_coil(regs::AL1_STA).await
+    }
+
+    /// Get the autotuning flag (AT).
+    pub async fn get_autotune(&mut self) -> AsyncResult<bool, UART> {
+        self.get_coil(regs::AT).await
+    }
+
+    /// Start autotuning (set AT).
+    pub async fn start_autotune(&mut self) -> AsyncResult<(), UART> {
+        self.set_coil(regs::AT, true).await
+    }
+
+    /// Abort autotuning (clear AT), leaving P, I and D as they were.
+    pub async fn abort_autotune(&mut self) -> AsyncResult<(), UART> {
+        self.set_coil(regs::AT, false).await
+    }
+
+    /// Get the manual mode flag (A/M).
+    pub async fn get_manual_mode(&mut self) -> AsyncResult<bool, UART> {
+        self.get_coil(regs::AM).await
+    }
+
+    /// Switch between manual mode (`true`) and PID control (A/M).
+    pub async fn set_manual_mode(&mut self, val: bool) -> AsyncResult<(), UART> {
+        self.set_coil(regs::AM, val).await
+    }
+
+    /// Get the cooling mode flag.
+    pub async fn get_cooling_mode(&mut self) -> AsyncResult<bool, UART> {
+        self.get_coil(regs::COOL).await
+    }
+
+    /// Get the setting mode flag, set while the front panel is in a menu.
+    pub async fn get_setting_mode(&mut self) -> AsyncResult<bool, UART> {
+        self.get_coil(regs::SETTING).await
+    }
+
+    /// Get the anomaly flag, set on a sensor fault.
+    pub async fn get_anomaly(&mut self) -> AsyncResult<bool, UART> {
+        self.get_coil(regs::ANOMALY).await
+    }
+
+    /// Get the control flag for OUT (CV).
+    ///
+    /// See [`Syl2381::get_cv`](crate::Syl2381::get_cv).
+    pub async fn get_cv(&mut self) -> AsyncResult<bool, UART> {
+        let val = self.get_holding(regs::CV).await?;
+        Ok(val == 1.0)
+    }
+
+    /// Set the control flag for OUT (CV).
+    ///
+    /// See [`Syl2381::set_cv`](crate::Syl2381::set_cv).
+    pub async fn set_cv(&mut self, val: bool) -> AsyncResult<(), UART> {
+        let val = if val { 1.0 } else { 0.0 };
+        self.set_holding(regs::CV, val).await
+    }
+
+    /// Get flag status (AT).
+    pub async fn get_status(&mut self) -> AsyncResult<Status, UART> {
+        let val = self.get_coils(regs::AT, 8).await?;
+        Ok(Status(val))
+    }
+
+    /// Get the set value (SV).
+    pub async fn get_sv(&mut self) -> AsyncResult<TempRaw, UART> {
+        let val = self.get_holding(regs::SV).await?;
+        Ok(TempRaw(val))
+    }
+
+    /// Set the set value (SV).
+    ///
+    /// See [`Syl2381::set_sv`](crate::Syl2381::set_sv).
+    pub async fn set_sv(&mut self, val: impl Into<AnyTemp>) -> AsyncResult<(), UART> {
+        let val = self.raw_temp(val.into()).await?;
+        if !limits::SV.contains(&val) {
+            return Err(Error::UnexpectedValue(val));
+        }
+        self.check_sensor_range(val).await?;
+        self.set_holding(regs::SV, val).await
+    }
+
+    /// Attach the controller's display unit (CorF) to a reading.
+    pub async fn temperature(&mut self, val: TempRaw) -> AsyncResult<Temperature, UART> {
+        let unit = self.get_display_unit_cached().await?;
+        Ok(Temperature::new(val.0, unit))
+    }
+
+    /// Set the set value (SV) to `value` in `unit`.
+    ///
+    /// See [`Syl2381::set_temperature`](crate::Syl2381::set_temperature).
+    pub async fn set_temperature(
+        &mut self,
+        value: f32,
+        unit: DisplayUnit,
+    ) -> AsyncResult<(), UART> {
+        if self.input_type.is_none() {
+            self.get_input_sensor_type().await?;
+        }
+        self.set_sv(Temperature::new(value, unit)).await
+    }
+
+    /// Get J1 ON temperature (AH1).
+    pub async fn get_j1_on_temp(&mut self) -> AsyncResult<TempRaw, UART> {
+        let val = self.get_holding(regs::AH1).await?;
+        Ok(TempRaw(val))
+    }
+
+    /// Set J1 ON temperature (AH1).
+    ///
+    /// See [`Syl2381::set_j1_on_temp`](crate::Syl2381::set_j1_on_temp).
+    pub async fn set_j1_on_temp(&mut self, val: impl Into<AnyTemp>) -> AsyncResult<(), UART> {
+        let val = self.raw_temp(val.into()).await?;
+        if !limits::AH1.contains(&val) {
+            return Err(Error::UnexpectedValue(val));
+        }
+        self.check_sensor_range(val).await?;
+        self.set_holding(regs::AH1, val).await
+    }
+
+    /// Get J1 OFF temperature (AL1).
+    pub async fn get_j1_off_temp(&mut self) -> AsyncResult<TempRaw, UART> {
+        let val = self.get_holding(regs::AL1).await?;
+        Ok(TempRaw(val))
+    }
+
+    /// Set J1 OFF temperature (AL1).
+    ///
+    /// See [`Syl2381::set_j1_off_temp`](crate::Syl2381::set_j1_off_temp).
+    pub async fn set_j1_off_temp(&mut self, val: impl Into<AnyTemp>) -> AsyncResult<(), UART> {
+        let val = self.raw_temp(val.into()).await?;
+        if !limits::AL1.contains(&val) {
+            return Err(Error::UnexpectedValue(val));
+        }
+        self.check_sensor_range(val).await?;
+        self.set_holding(regs::AL1, val).await
+    }
+
+    /// Get proportional constant (P).
+    pub async fn get_p(&mut self) -> AsyncResult<f32, UART> {
+        self.get_holding(regs::P).await
+    }
+
+    /// Get proportional constant (P).
+    pub async fn set_p(&mut self, val: f32) -> AsyncResult<(), UART> {
+        if !limits::P.contains(&val) {
+            return Err(Error::UnexpectedValue(val));
+        }
+        self.set_holding(regs::P, val).await
+    }
+
+    /// Get integral time (I).
+    pub async fn get_i(&mut self) -> AsyncResult<Seconds, UART> {
+        let val = self.get_holding(regs::I).await?;
+        try_from_f32(val)
+    }
+
+    /// Set integral time (I).
+    pub async fn set_i(&mut self, val: Seconds) -> AsyncResult<(), UART> {
+        let Seconds(val) = val;
+        if !limits::I.contains(&val) {
+            return Err(Error::UnexpectedValue(val as f32));
+        }
+        let val = val as f32;
+        self.set_holding(regs::I, val).await
+    }
+
+    /// Set derivative time (D).
+    pub async fn get_d(&mut self) -> AsyncResult<Seconds, UART> {
+        let val = self.get_holding(regs::D).await?;
+        try_from_f32(val)
+    }
+
+    /// Set derivative time (D).
+    pub async fn set_d(&mut self, val: Seconds) -> AsyncResult<(), UART> {
+        let Seconds(val) = val;
+        if !limits::D.contains(&val) {
+            return Err(Error::UnexpectedValue(val as f32));
+        }
+        let val = val as f32;
+        self.set_holding(regs::D, val).await
+    }
+
+    /// Get proportional band range limit (BB).
+    pub async fn get_bb(&mut self) -> AsyncResult<TempRaw, UART> {
+        let val = self.get_holding(regs::BB).await?;
+        Ok(TempRaw(val))
+    }
+
+    /// Set proportional band range limit (BB).
+    pub async fn set_bb(&mut self, val: TempRaw) -> AsyncResult<(), UART> {
+        let TempRaw(val) = val;
+        if !limits::BB.contains(&val) {
+            return Err(Error::UnexpectedValue(val));
+        }
+        self.set_holding(regs::BB, val).await
+    }
+
+    /// Get the Damp Constant (SouF).
+    ///
+    /// See [`Syl2381::get_souf`](crate::Syl2381::get_souf).
+    pub async fn get_souf(&mut self) -> AsyncResult<Percent, UART> {
+        let val = self.get_holding(regs::SOUF).await?;
+        Ok(Percent(val))
+    }
+
+    /// Set the Damp Constant (SouF).
+    ///
+    /// See [`Syl2381::set_souf`](crate::Syl2381::set_souf).
+    pub async fn set_souf(&mut self, val: Percent) -> AsyncResult<(), UART> {
+        let Percent(val) = val;
+        if !limits::SOUF.contains(&val) {
+            return Err(Error::UnexpectedValue(val));
+        }
+        self.set_holding(regs::SOUF, val).await
+    }
+
+    /// Get control cycle (OT).
+    ///
+    /// See [`Syl2381::get_control_cycle`](crate::Syl2381::get_control_cycle).
+    pub async fn get_control_cycle(&mut self) -> AsyncResult<Seconds, UART> {
+        let val = self.get_holding(regs::OT).await?;
+        try_from_f32(val)
+    }
+
+    /// Set control cycle (OT).
+    ///
+    /// See [`Syl2381::set_control_cycle`](crate::Syl2381::set_control_cycle).
+    pub async fn set_control_cycle(&mut self, val: Seconds) -> AsyncResult<(), UART> {
+        let Seconds(val) = val;
+        if !limits::OT.contains(&val) {
+            return Err(Error::UnexpectedValue(val as f32));
+        }
+        let val = val as f32;
+        self.set_holding(regs::OT, val).await
+    }
+
+    /// Get digital filter (FILT).
+    ///
+    /// See [`Syl2381::get_filter`](crate::Syl2381::get_filter).
+    pub async fn get_filter(&mut self) -> AsyncResult<Filter, UART> {
+        let val = self.get_holding(regs::FILT).await?;
+        try_from_f32(val)
+    }
+
+    /// Set digital filter (FILT).
+    ///
+    /// See [`Syl2381::set_filter`](crate::Syl2381::set_filter).
+    pub async fn set_filter(&mut self, val: Filter) -> AsyncResult<(), UART> {
+        let val = val.into();
+        self.set_holding(regs::FILT, val).await
+    }
+
+    /// Get input sensor type (INTY).
+    pub async fn get_input_sensor_type(&mut self) -> AsyncResult<InputType, UART> {
+        let val = self.get_holding(regs::INTY).await?;
+        let input = try_from_f32(val)?;
+        self.input_type = Some(input);
+        Ok(input)
+    }
+
+    /// Set input sensor type (INTY).
+    pub async fn set_input_sensor_type(&mut self, val: InputType) -> AsyncResult<(), UART> {
+        self.input_type = None;
+        self.set_holding(regs::INTY, val.into()).await?;
+        self.input_type = Some(val);
+        Ok(())
+    }
+
+    /// Get output control mode (OUTY).
+    pub async fn get_output_mode(&mut self) -> AsyncResult<OutputMode, UART> {
+        let val = self.get_holding(regs::OUTY).await?;
+        try_from_f32(val)
+    }
+
+    /// Set output control mode (OUTY).
+    pub async fn set_output_mode(&mut self, val: OutputMode) -> AsyncResult<(), UART> {
+        let val = val.into();
+        self.set_holding(regs::OUTY, val).await
+    }
+
+    /// Get main output mode (COTY).
+    pub async fn get_output_type(&mut self) -> AsyncResult<OutputType, UART> {
+        let val = self.get_holding(regs::COTY).await?;
+        try_from_f32(val)
+    }
+
+    /// Set main output mode (COTY).
+    pub async fn set_output_type(&mut self, val: OutputType) -> AsyncResult<(), UART> {
+        let val = val.into();
+        self.set_holding(regs::COTY, val).await
+    }
+
+    /// Get hysteresis band (Hy).
+    pub async fn get_hysteresis(&mut self) -> AsyncResult<TempRaw, UART> {
+        let val = self.get_holding(regs::HY).await?;
+        Ok(TempRaw(val))
+    }
+
+    /// Set hysteresis band (Hy).
+    pub async fn set_hysteresis(&mut self, val: TempRaw) -> AsyncResult<(), UART> {
+        let TempRaw(val) = val;
+        if !limits::HY.contains(&val) {
+            return Err(Error::UnexpectedValue(val));
+        }
+        self.set_holding(regs::HY, val).await
+    }
+
+    /// Get input offset (PSb).
+    pub async fn get_input_offset(&mut self) -> AsyncResult<TempRaw, UART> {
+        let val = self.get_holding(regs::PSB).await?;
+        Ok(TempRaw(val))
+    }
+
+    /// Set input offset (PSb).
+    ///
+    /// See [`Syl2381::set_intput_offset`](crate::Syl2381::set_intput_offset).
+    pub async fn set_intput_offset(&mut self, val: TempRaw) -> AsyncResult<(), UART> {
+        let TempRaw(val) = val;
+        if !limits::PSB.contains(&val) {
+            return Err(Error::UnexpectedValue(val));
+        }
+        self.set_holding(regs::PSB, val).await
+    }
+
+    /// Get control function (rd).
+    pub async fn get_control_direction(&mut self) -> AsyncResult<ControlDirection, UART> {
+        let val = self.get_holding(regs::RD).await?;
+        try_from_f32(val)
+    }
+
+    /// Set control function (rd).
+    ///
+    /// See [`Syl2381::set_control_direction`](crate::Syl2381::set_control_direction).
+    pub async fn set_control_direction(&mut self, val: ControlDirection) -> AsyncResult<(), UART> {
+        let val = val.into();
+        self.set_holding(regs::RD, val).await
+    }
+
+    /// Get display unit (CorF).
+    pub async fn get_display_unit(&mut self) -> AsyncResult<DisplayUnit, UART> {
+        let val = self.get_holding(regs::CORF).await?;
+        let unit = try_from_f32(val)?;
+        self.display_unit = Some(unit);
+        Ok(unit)
+    }
+
+    /// Set display unit (CorF).
+    pub async fn set_display_unit(&mut self, val: DisplayUnit) -> AsyncResult<(), UART> {
+        self.display_unit = None;
+        self.set_holding(regs::CORF, val.into()).await?;
+        self.display_unit = Some(val);
+        Ok(())
+    }
+
+    /// Get unit ID (Id).
+    pub async fn get_unit_id(&mut self) -> AsyncResult<u8, UART> {
+        let val = self.get_holding(regs::ID).await?;
+        limits::unit_id(val).ok_or(Error::UnexpectedValue(val))
+    }
+
+    /// Set unit ID (Id).
+    ///
+    /// See [`Syl2381::set_unit_id`](crate::Syl2381::set_unit_id).
+    pub async fn set_unit_id(&mut self, val: u8) -> AsyncResult<(), UART> {
+        if !limits::ID.contains(&val) {
+            return Err(Error::UnexpectedValue(val as f32));
+        }
+        let val = val as f32;
+        self.set_holding(regs::ID, val).await
+    }
+
+    /// Get baud rate (bAud).
+    pub async fn get_baud_rate(&mut self) -> AsyncResult<BaudRate, UART> {
+        let val = self.get_holding(regs::BAUD).await?;
+        try_from_f32(val)
+    }
+
+    /// Set baud rate (bAud).
+    pub async fn set_baud_rate(&mut self, val: BaudRate) -> AsyncResult<(), UART> {
+        let val = val.into();
+        self.set_holding(regs::BAUD, val).await
+    }
+
+    // ---------------------------
+
+    /// The display unit, read the first time it's needed.
+    async fn get_display_unit_cached(&mut self) -> AsyncResult<DisplayUnit, UART> {
+        match self.display_unit {
+            Some(unit) => Ok(unit),
+            None => self.get_display_unit().await,
+        }
+    }
+
+    /// `val` in the controller's display unit.
+    async fn raw_temp(&mut self, val: AnyTemp) -> AsyncResult<f32, UART> {
+        match val {
+            AnyTemp::Raw(TempRaw(val)) => Ok(val),
+            AnyTemp::Unit(val) => Ok(val.to(self.get_display_unit_cached().await?)),
+        }
+    }
+
+    /// Reject a temperature setting the sensor can't measure, reading INTY
+    /// and CorF the first time they're needed.
+    async fn check_sensor_range(&mut self, val: f32) -> AsyncResult<(), UART> {
+        let input = match self.input_type {
+            Some(input) => input,
+            None => self.get_input_sensor_type().await?,
+        };
+        let unit = self.get_display_unit_cached().await?;
+        Plausibility::new(input, unit).check(val)
+    }
+
+    /// Set holding param.
+    async fn set_holding(&mut self, reg: regs::Reg, val: f32) -> AsyncResult<(), UART> {
+        let ctx = Context::new(Operation::WriteHolding, reg);
+        self.port
+            .write_holding(self.unit_id, reg, val)
+            .await
+            .map_err(|err| err.with(ctx))
+    }
+
+    /// Get holding param.
+    async fn get_holding(&mut self, reg: regs::Reg) -> AsyncResult<f32, UART> {
+        let ctx = Context::new(Operation::ReadHolding, reg);
+        self.port
+            .read_holding(self.unit_id, reg)
+            .await
+            .map_err(|err| err.with(ctx))
+    }
+
+    /// Read a single coil.
+    async fn get_coil(&mut self, reg: regs::Reg) -> AsyncResult<bool, UART> {
+        let val = self.get_coils(reg, 1).await?;
+        Ok(val & 1 == 1)
+    }
+
+    /// Set a single coil.
+    async fn set_coil(&mut self, reg: regs::Reg, on: bool) -> AsyncResult<(), UART> {
+        let ctx = Context::new(Operation::WriteCoil, reg);
+        self.port
+            .write_coil(self.unit_id, reg, on)
+            .await
+            .map_err(|err| err.with(ctx))
+    }
+
+    /// Get `count` coils.
+    async fn get_coils(&mut self, reg: regs::Reg, count: u8) -> AsyncResult<u8, UART> {
+        let ctx = Context::new(Operation::ReadCoils, reg);
+        self.port
+            .read_coils(self.unit_id, reg, count)
+            .await
+            .map_err(|err| err.with(ctx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{block_on, frame, MockSerial};
+
+    #[test]
+    fn shares_framing_with_blocking_driver() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 62.5);
+        port.ack_f32(1, regs::SV.addr);
+        let mut pid = Syl2381Async::new(1, port);
+        pid.assume_sensor(crate::InputType::K, crate::DisplayUnit::Celsius);
+
+        assert!(matches!(block_on(pid.get_pv()), Ok(TempRaw(v)) if v == 62.5));
+        assert!(block_on(pid.set_sv(TempRaw(-40.0))).is_ok());
+        assert!(matches!(
+            block_on(pid.set_sv(TempRaw(10000.0))),
+            Err(Error::UnexpectedValue(_))
+        ));
+
+        let mut expected = frame(&[0x01, 0x03, 0x01, 0x64, 0x00, 0x02]);
+        expected.extend(frame(&[
+            0x01, 0x10, 0x00, 0x00, 0x00, 0x02, 0x04, 0xC2, 0x20, 0x00, 0x00,
+        ]));
+        assert_eq!(pid.port.tx, expected);
+    }
+
+    #[test]
+    fn truncated_response_is_broken_frame() {
+        let mut port = MockSerial::new();
+        port.rx.extend([0x01, 0x03, 0x04, 0x42]);
+        let mut pid = Syl2381Async::new(1, port);
+
+        assert!(matches!(
+            block_on(pid.get_pv()),
+            Err(Error::ModbusError(_, rmodbus::ErrorKind::FrameBroken))
+        ));
+    }
+
+    #[test]
+    fn writes_coils() {
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x05, 0x00, 0x00, 0xFF, 0x00]);
+        port.respond(&[1, 0x01, 1, 0x01]);
+        let mut pid = Syl2381Async::new(1, port);
+
+        assert!(block_on(pid.start_autotune()).is_ok());
+        assert!(matches!(block_on(pid.get_autotune()), Ok(true)));
+        assert_eq!(pid.port.tx[..8], frame(&[1, 0x05, 0x00, 0x00, 0xFF, 0x00]));
+        assert_eq!(pid.port.tx[8..], frame(&[1, 0x01, 0x00, 0x00, 0x00, 0x01]));
+    }
+
+    #[test]
+    fn converts_and_checks_temperatures() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 6.0); // INTY: K
+        port.respond_f32(1, 1.0); // CorF: °F
+        port.ack_f32(1, regs::SV.addr);
+        let mut pid = Syl2381Async::new(1, port);
+
+        assert!(block_on(pid.set_temperature(65.0, DisplayUnit::Celsius)).is_ok());
+        assert!(matches!(
+            block_on(pid.set_j1_on_temp(Temperature::celsius(2000.0))),
+            Err(Error::OutOfRange { .. })
+        ));
+        assert!(pid.port.rx.is_empty());
+        assert_eq!(pid.port.tx[16 + 7..16 + 11], 149f32.to_be_bytes());
+    }
+}
@@ -0,0 +1,593 @@
+//! Tamper-evident log of writes to the controller.
+//!
+//! Food-safety (HACCP) record keeping has to show that process history hasn't
+//! been edited after the fact. [`Audited`] wraps a transport and turns every
+//! write the controller acknowledges (holding parameters, raw registers and
+//! coils alike, however they were sent) into a [`Record`] carrying the SHA-256
+//! hash of the record before it, so editing, dropping or reordering records
+//! breaks the chain, which [`verify`] detects. Storing the records is up to
+//! the sink; a [`Log`] keeps them in any [`Storage`] and picks the chain back
+//! up after a restart. A write whose record the sink couldn't keep holds back
+//! the writes after it until it can, so the log never has a gap.
+//!
+//! The driver itself says which writes were acknowledged, through
+//! [`Transport::acknowledged`]; nothing is inferred from the frames. The
+//! SYL-2381 has no alarm acknowledgement command, so writes are the only
+//! operator actions there are to log.
+
+use core::fmt;
+use core::time::Duration;
+
+use sha2::{Digest, Sha256};
+
+use crate::clock::Clock;
+use crate::storage::Storage;
+use crate::transport::{Ack, AckValue, Transport};
+
+/// One acknowledged write.
+#[derive(Clone, Copy, fmt::Debug, PartialEq)]
+pub struct Record {
+    /// Position in the chain, starting at 0.
+    pub seq: u64,
+
+    /// When the controller acknowledged the write.
+    pub at: Duration,
+
+    pub unit_id: u8,
+
+    /// Holding register or coil address.
+    pub addr: u16,
+
+    pub value: AckValue,
+
+    /// Hash of the previous record; all zeros for the first.
+    pub prev: [u8; 32],
+}
+
+impl Record {
+    /// Length of [`Record::to_bytes`].
+    pub const LEN: usize = 60;
+
+    /// The record in a fixed big-endian layout, for storage.
+    pub fn to_bytes(&self) -> [u8; Record::LEN] {
+        let mut buf = [0; Record::LEN];
+        buf[0..8].copy_from_slice(&self.seq.to_be_bytes());
+        buf[8..16].copy_from_slice(&self.at.as_secs().to_be_bytes());
+        buf[16..20].copy_from_slice(&self.at.subsec_nanos().to_be_bytes());
+        buf[20] = self.unit_id;
+        buf[21..23].copy_from_slice(&self.addr.to_be_bytes());
+        let (tag, value) = match self.value {
+            AckValue::Holding(val) => (0, val.to_bits()),
+            AckValue::Word(word) => (1, word.into()),
+            AckValue::Coil(on) => (2, on.into()),
+        };
+        buf[23] = tag;
+        buf[24..28].copy_from_slice(&value.to_be_bytes());
+        buf[28..].copy_from_slice(&self.prev);
+        buf
+    }
+
+    /// Read back a record written by [`Record::to_bytes`].
+    pub fn from_bytes(buf: &[u8; Record::LEN]) -> Option<Self> {
+        let be64 = |at: usize| u64::from_be_bytes(buf[at..at + 8].try_into().unwrap());
+        let be32 = |at: usize| u32::from_be_bytes(buf[at..at + 4].try_into().unwrap());
+        let value = match (buf[23], be32(24)) {
+            (0, bits) => AckValue::Holding(f32::from_bits(bits)),
+            (1, word) => AckValue::Word(word.try_into().ok()?),
+            (2, 0) => AckValue::Coil(false),
+            (2, 1) => AckValue::Coil(true),
+            _ => return None,
+        };
+        Some(Record {
+            seq: be64(0),
+            at: Duration::new(be64(8), be32(16)),
+            unit_id: buf[20],
+            addr: u16::from_be_bytes([buf[21], buf[22]]),
+            value,
+            prev: buf[28..].try_into().unwrap(),
+        })
+    }
+
+    /// SHA-256 over the record's fields, in a fixed big-endian layout.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut h = Sha256::new();
+        h.update(self.seq.to_be_bytes());
+        h.update(self.at.as_secs().to_be_bytes());
+        h.update(self.at.subsec_nanos().to_be_bytes());
+        h.update([self.unit_id]);
+        h.update(self.addr.to_be_bytes());
+        match self.value {
+            AckValue::Holding(val) => {
+                h.update([0]);
+                h.update(val.to_be_bytes());
+            }
+            AckValue::Word(word) => {
+                h.update([1]);
+                h.update(word.to_be_bytes());
+            }
+            AckValue::Coil(on) => h.update([2, on as u8]),
+        }
+        h.update(self.prev);
+        h.finalize().into()
+    }
+}
+
+/// The running end of a hash chain.
+#[derive(Clone, Copy, fmt::Debug)]
+pub struct Chain {
+    seq: u64,
+    head: [u8; 32],
+}
+
+impl Chain {
+    /// Start a new chain.
+    pub fn new() -> Self {
+        Chain {
+            seq: 0,
+            head: [0; 32],
+        }
+    }
+
+    /// Continue an existing chain after its last record.
+    pub fn resume(last: &Record) -> Self {
+        Chain {
+            seq: last.seq + 1,
+            head: last.hash(),
+        }
+    }
+
+    /// Append a record for the acknowledged write `ack`.
+    pub fn next(&mut self, at: Duration, ack: &Ack) -> Record {
+        let rec = Record {
+            seq: self.seq,
+            at,
+            unit_id: ack.unit_id,
+            addr: ack.addr,
+            value: ack.value,
+            prev: self.head,
+        };
+        self.seq += 1;
+        self.head = rec.hash();
+        rec
+    }
+}
+
+impl Default for Chain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Check that `records` form an unbroken chain from the first record.
+///
+/// Returns the position of the first record that doesn't follow on from the
+/// one before it.
+pub fn verify<'a>(records: impl IntoIterator<Item = &'a Record>) -> Result<(), usize> {
+    let mut expected = Chain::new();
+    for (i, rec) in records.into_iter().enumerate() {
+        if rec.seq != expected.seq || rec.prev != expected.head {
+            return Err(i);
+        }
+        expected = Chain::resume(rec);
+    }
+    Ok(())
+}
+
+/// Why a [`Log`] couldn't be read.
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq)]
+pub enum LogError<E> {
+    Storage(E),
+
+    /// What's stored under the key isn't a record.
+    Corrupt,
+}
+
+impl<E> From<E> for LogError<E> {
+    fn from(err: E) -> Self {
+        LogError::Storage(err)
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for LogError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogError::Storage(err) => err.fmt(f),
+            LogError::Corrupt => f.write_str("stored audit record is corrupt"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Debug + fmt::Display> std::error::Error for LogError<E> {}
+
+/// Audit records kept in a [`Storage`].
+///
+/// Record `n` is stored under `audit/<n>`, and the newest again under
+/// `audit/last`, so that [`Log::chain`] can continue the chain after a
+/// restart without reading the whole log.
+pub struct Log<S> {
+    storage: S,
+}
+
+impl<S> Log<S>
+where
+    S: Storage,
+{
+    pub fn new(storage: S) -> Self {
+        Log { storage }
+    }
+
+    /// The chain to append to: after the newest stored record, or a new one if
+    /// there are none.
+    pub fn chain(&mut self) -> Result<Chain, LogError<S::Error>> {
+        Ok(match self.read("audit/last")? {
+            Some(last) => Chain::resume(&last),
+            None => Chain::new(),
+        })
+    }
+
+    /// Store `rec`, as a sink for [`Audited`] would.
+    pub fn push(&mut self, rec: &Record) -> Result<(), S::Error> {
+        let bytes = rec.to_bytes();
+        self.storage.write(&key(rec.seq), &bytes)?;
+        self.storage.write("audit/last", &bytes)
+    }
+
+    /// Record number `seq`, if it's stored.
+    pub fn get(&mut self, seq: u64) -> Result<Option<Record>, LogError<S::Error>> {
+        self.read(&key(seq))
+    }
+
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+
+    fn read(&mut self, key: &str) -> Result<Option<Record>, LogError<S::Error>> {
+        let mut buf = [0; Record::LEN];
+        match self.storage.read(key, &mut buf)? {
+            None => Ok(None),
+            Some(Record::LEN) => Record::from_bytes(&buf).map(Some).ok_or(LogError::Corrupt),
+            Some(_) => Err(LogError::Corrupt),
+        }
+    }
+}
+
+/// `audit/<seq>`.
+fn key(seq: u64) -> heapless::String<32> {
+    let mut key = heapless::String::new();
+    // "audit/" and at most 20 digits always fit.
+    let _ = fmt::Write::write_fmt(&mut key, format_args!("audit/{}", seq));
+    key
+}
+
+/// Modbus function codes that write: single coil, single register, multiple
+/// coils and multiple registers.
+const WRITES: [u8; 4] = [0x05, 0x06, 0x0F, 0x10];
+
+/// A transport error, or a write held back because the record of an earlier
+/// one couldn't be kept.
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq)]
+pub enum AuditError<T, S> {
+    Transport(T),
+
+    /// The sink still can't keep [`Audited::unlogged`].
+    Sink(S),
+}
+
+impl<T: fmt::Display, S: fmt::Display> fmt::Display for AuditError<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditError::Transport(err) => err.fmt(f),
+            AuditError::Sink(err) => write!(f, "audit record not kept: {}", err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, S> std::error::Error for AuditError<T, S>
+where
+    T: fmt::Debug + fmt::Display,
+    S: fmt::Debug + fmt::Display,
+{
+}
+
+/// A transport that logs every acknowledged write.
+///
+/// Records go to `sink` as soon as the driver has checked the controller's
+/// acknowledgement. A write the controller rejects with an exception isn't
+/// logged.
+///
+/// If the sink fails, the record is kept back and the chain doesn't move on.
+/// The next write hands the record to the sink again before it is sent, and
+/// fails with [`AuditError::Sink`] if the sink still can't keep it; reads go
+/// through regardless.
+pub struct Audited<T, C, F> {
+    inner: T,
+    clock: C,
+    chain: Chain,
+    sink: F,
+    unlogged: Option<Record>,
+}
+
+impl<T, C, F, E> Audited<T, C, F>
+where
+    T: Transport,
+    C: Clock,
+    F: FnMut(&Record) -> Result<(), E>,
+{
+    pub fn new(inner: T, clock: C, chain: Chain, sink: F) -> Self {
+        Audited {
+            inner,
+            clock,
+            chain,
+            sink,
+            unlogged: None,
+        }
+    }
+
+    /// The record of an acknowledged write that the sink hasn't kept yet.
+    pub fn unlogged(&self) -> Option<&Record> {
+        self.unlogged.as_ref()
+    }
+
+    /// Hand [`Audited::unlogged`] to the sink again, without waiting for the
+    /// next write.
+    pub fn retry(&mut self) -> Result<(), E> {
+        if let Some(rec) = self.unlogged {
+            (self.sink)(&rec)?;
+            self.chain = Chain::resume(&rec);
+            self.unlogged = None;
+        }
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T, C, F, E> Transport for Audited<T, C, F>
+where
+    T: Transport,
+    C: Clock,
+    F: FnMut(&Record) -> Result<(), E>,
+{
+    type Error = AuditError<T::Error, E>;
+
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        if frame.get(1).is_some_and(|fc| WRITES.contains(fc)) {
+            self.retry().map_err(AuditError::Sink)?;
+        }
+        self.inner.write_frame(frame).map_err(AuditError::Transport)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.inner.read_exact(buf).map_err(AuditError::Transport)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner.flush().map_err(AuditError::Transport)
+    }
+
+    fn is_timeout(err: &Self::Error) -> bool {
+        matches!(err, AuditError::Transport(err) if T::is_timeout(err))
+    }
+
+    fn acknowledged(&mut self, ack: &Ack) {
+        let mut next = self.chain;
+        self.unlogged = Some(next.next(self.clock.now(), ack));
+        // A failure is reported by the next write, which tries again.
+        let _ = self.retry();
+        self.inner.acknowledged(ack);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    use crate::clock::VirtualClock;
+    use crate::mock::MockSerial;
+    use crate::simulator::Simulator;
+    use crate::storage::FileStorage;
+    use crate::{regs, Error, Pid, Seconds, Syl2381, TempRaw, WriteStrategy};
+
+    fn ack(addr: u16, value: AckValue) -> Ack {
+        Ack {
+            unit_id: 1,
+            addr,
+            value,
+        }
+    }
+
+    #[test]
+    fn verify_detects_edits() {
+        let mut chain = Chain::new();
+        let mut log = [
+            chain.next(
+                Duration::from_secs(1),
+                &ack(regs::SV.addr, AckValue::Holding(65.0)),
+            ),
+            chain.next(
+                Duration::from_secs(2),
+                &ack(regs::SV.addr, AckValue::Holding(70.0)),
+            ),
+            chain.next(
+                Duration::from_secs(3),
+                &ack(regs::AT.addr, AckValue::Coil(true)),
+            ),
+        ];
+        assert_eq!(verify(&log), Ok(()));
+
+        log[1].value = AckValue::Holding(75.0);
+        assert_eq!(verify(&log), Err(2));
+        assert_eq!(verify([&log[0], &log[2]]), Err(1));
+    }
+
+    #[test]
+    fn records_round_trip_through_bytes() {
+        let mut chain = Chain::new();
+        for value in [
+            AckValue::Holding(-12.5),
+            AckValue::Word(0xBEEF),
+            AckValue::Coil(true),
+        ] {
+            let rec = chain.next(Duration::new(7, 250), &ack(regs::SV.addr, value));
+            assert_eq!(Record::from_bytes(&rec.to_bytes()), Some(rec));
+        }
+
+        let mut bytes = chain
+            .next(Duration::ZERO, &ack(0, AckValue::Coil(false)))
+            .to_bytes();
+        bytes[27] = 2;
+        assert_eq!(Record::from_bytes(&bytes), None);
+    }
+
+    #[test]
+    fn log_resumes_the_chain_from_storage() {
+        let root = std::env::temp_dir().join(std::format!("syl2381-audit-{}", std::process::id()));
+        let clock = VirtualClock::new();
+        let mut log = Log::new(FileStorage::new(&root));
+
+        for sv in [65.0, 70.0] {
+            let mut port = MockSerial::new();
+            port.ack_f32(1, regs::SV.addr);
+            let chain = log.chain().unwrap();
+            let sink = |r: &Record| log.push(r);
+            let mut pid = Syl2381::new(1, Audited::new(port, &clock, chain, sink));
+            pid.live
+                .assume_sensor(crate::InputType::K, crate::DisplayUnit::Celsius);
+            assert!(pid.set_sv(TempRaw(sv)).is_ok());
+        }
+
+        let mut log = Log::new(FileStorage::new(&root));
+        let stored = [log.get(0).unwrap().unwrap(), log.get(1).unwrap().unwrap()];
+        assert_eq!(log.get(2).unwrap(), None);
+        assert_eq!(stored[1].value, AckValue::Holding(70.0));
+        assert_eq!(verify(&stored), Ok(()));
+
+        std::fs::write(root.join("audit/last"), b"short").unwrap();
+        assert!(matches!(log.chain(), Err(LogError::Corrupt)));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn logs_acknowledged_writes_only() {
+        let clock = VirtualClock::new();
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 25.0);
+        port.ack_f32(1, regs::SV.addr);
+        port.respond(&[1, 0x90, 0x02]); // illegal data address
+
+        let mut log = Vec::new();
+        let audited = Audited::new(port, &clock, Chain::new(), |r: &Record| {
+            log.push(*r);
+            Ok::<_, ()>(())
+        });
+        let mut pid = Syl2381::new(1, audited);
+        pid.live
+            .assume_sensor(crate::InputType::K, crate::DisplayUnit::Celsius);
+
+        assert!(pid.get_pv().is_ok());
+        clock.advance(Duration::from_secs(5));
+        assert!(pid.set_sv(TempRaw(65.0)).is_ok());
+        assert!(matches!(
+            pid.set_sv(TempRaw(70.0)),
+            Err(Error::IllegalDataAddress(_))
+        ));
+        drop(pid);
+
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].at, Duration::from_secs(5));
+        assert_eq!(
+            (log[0].addr, log[0].value),
+            (regs::SV.addr, AckValue::Holding(65.0))
+        );
+        assert_eq!(verify(&log), Ok(()));
+    }
+
+    #[test]
+    fn logs_every_kind_of_write() {
+        let clock = VirtualClock::new();
+        let mut log = Vec::new();
+        let sim = Simulator::new(1, &clock);
+        let audited = Audited::new(sim, &clock, Chain::new(), |r: &Record| {
+            log.push(*r);
+            Ok::<_, ()>(())
+        });
+        let mut pid = Syl2381::new(1, audited);
+
+        let tuned = Pid {
+            p: 5.0,
+            i: Seconds(100),
+            d: Seconds(20),
+        };
+        assert!(pid.set_pid(tuned).is_ok());
+        pid.set_write_strategy(WriteStrategy::Single);
+        assert!(pid.set_sv(TempRaw(65.0)).is_ok());
+        assert!(pid.start_autotune().is_ok());
+        assert!(pid
+            .write_holding_raw(regs::HY.addr, &[0x4000, 0x0000])
+            .is_ok());
+        drop(pid);
+
+        let written: Vec<_> = log.iter().map(|r| (r.addr, r.value)).collect();
+        assert_eq!(
+            written,
+            [
+                (regs::P.addr, AckValue::Holding(5.0)),
+                (regs::I.addr, AckValue::Holding(100.0)),
+                (regs::D.addr, AckValue::Holding(20.0)),
+                (regs::SV.addr, AckValue::Holding(65.0)),
+                (regs::AT.addr, AckValue::Coil(true)),
+                (regs::HY.addr, AckValue::Word(0x4000)),
+                (regs::HY.addr + 1, AckValue::Word(0x0000)),
+            ]
+        );
+        assert_eq!(verify(&log), Ok(()));
+    }
+
+    #[test]
+    fn holds_back_writes_until_the_record_is_kept() {
+        let clock = VirtualClock::new();
+        let full = Cell::new(true);
+        let mut log = Vec::new();
+        let sink = |r: &Record| {
+            if full.get() {
+                return Err("full");
+            }
+            log.push(*r);
+            Ok(())
+        };
+        let sim = Simulator::new(1, &clock);
+        let mut pid = Syl2381::new(1, Audited::new(sim, &clock, Chain::new(), sink));
+
+        assert!(pid.set_sv(TempRaw(65.0)).is_ok());
+        assert!(matches!(
+            pid.set_sv(TempRaw(70.0)),
+            Err(Error::SerialError(_, AuditError::Sink("full")))
+        ));
+        assert!(pid.get_pv().is_ok());
+
+        full.set(false);
+        assert!(pid.set_sv(TempRaw(70.0)).is_ok());
+        drop(pid);
+
+        let written: Vec<_> = log.iter().map(|r| r.value).collect();
+        assert_eq!(written, [AckValue::Holding(65.0), AckValue::Holding(70.0)]);
+        assert_eq!(verify(&log), Ok(()));
+    }
+
+    #[test]
+    fn passes_timeouts_through() {
+        let clock = VirtualClock::new();
+        let sim = Simulator::new(1, &clock);
+        let mut pid = Syl2381::new(
+            2,
+            Audited::new(sim, &clock, Chain::new(), |_: &Record| Ok::<_, ()>(())),
+        );
+
+        assert!(matches!(pid.get_pv(), Err(Error::Timeout(_))));
+    }
+}
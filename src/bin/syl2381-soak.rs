@@ -0,0 +1,135 @@
+//! Soak test: poll a controller continuously for hours and tally failures.
+//!
+//! Meant for qualifying a new transport or USB adapter before a release.
+//!
+//! ```text
+//! syl2381-soak [port] [unit id] [hours]
+//! ```
+//!
+//! e.g. `cargo run --features soak --bin syl2381-soak -- /dev/ttyUSB0 5 8`
+//! runs for 8 hours against unit 5. The defaults are `/dev/ttyUSB0`, unit 5
+//! and 1 hour.
+//!
+//! PV, OUT and status are read every 250 ms. Every 10 minutes SV is written
+//! back with the value it already holds, which exercises the write path.
+//! Those writes still go to EEPROM, so don't leave this running for days. A
+//! summary is printed every minute and once more at the end.
+
+use std::env;
+use std::time::{Duration, Instant};
+
+use syl2381::{Error, IoTransport, Syl2381};
+
+const POLL_EVERY: Duration = Duration::from_millis(250);
+const WRITE_EVERY: Duration = Duration::from_secs(10 * 60);
+const REPORT_EVERY: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+struct Stats {
+    ok: u64,
+    serial: u64,
+    modbus: u64,
+    timeout: u64,
+    other: u64,
+    slowest: Duration,
+}
+
+impl Stats {
+    fn record<T, E>(&mut self, started: Instant, res: Result<T, Error<E>>) -> Option<T> {
+        self.slowest = self.slowest.max(started.elapsed());
+        match res {
+            Ok(val) => {
+                self.ok += 1;
+                return Some(val);
+            }
+            Err(Error::SerialError(..)) => self.serial += 1,
+            Err(Error::ModbusError(..))
+            | Err(Error::IllegalFunction(_))
+            | Err(Error::IllegalDataAddress(_))
+            | Err(Error::IllegalDataValue(_))
+            | Err(Error::DeviceFailure(_))
+            | Err(Error::DeviceBusy(_)) => self.modbus += 1,
+            Err(Error::Timeout(_)) => self.timeout += 1,
+            Err(_) => self.other += 1,
+        }
+        None
+    }
+
+    fn report(&self, elapsed: Duration) {
+        println!(
+            "{:>6}s  ok {}  serial {}  modbus {}  timeout {}  other {}  slowest {:?}  peak rss {}",
+            elapsed.as_secs(),
+            self.ok,
+            self.serial,
+            self.modbus,
+            self.timeout,
+            self.other,
+            self.slowest,
+            peak_rss().unwrap_or_else(|| "n/a".into()),
+        );
+    }
+}
+
+/// Peak resident set size, where the OS reports it (Linux only).
+fn peak_rss() -> Option<String> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    Some(line["VmHWM:".len()..].trim().to_string())
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let port_name = args.next().unwrap_or_else(|| "/dev/ttyUSB0".into());
+    let unit_id: u8 = args.next().and_then(|s| s.parse().ok()).unwrap_or(5);
+    let hours: u64 = args.next().and_then(|s| s.parse().ok()).unwrap_or(1);
+
+    let port = serialport::new(&port_name, 9600)
+        .data_bits(serialport::DataBits::Eight)
+        .parity(serialport::Parity::None)
+        .stop_bits(serialport::StopBits::One)
+        .flow_control(serialport::FlowControl::None)
+        .timeout(Duration::from_secs(3))
+        .open()
+        .expect("opening serial port");
+
+    let mut pid = Syl2381::new(unit_id, IoTransport::new(port));
+    let mut stats = Stats::default();
+
+    let start = Instant::now();
+    let end = Duration::from_secs(hours * 60 * 60);
+    let mut last_write = start;
+    let mut last_report = start;
+
+    while start.elapsed() < end {
+        let tick = Instant::now();
+        let t = Instant::now();
+        let res = pid.get_pv();
+        stats.record(t, res);
+        let t = Instant::now();
+        let res = pid.get_out();
+        stats.record(t, res);
+        let t = Instant::now();
+        let res = pid.get_status();
+        stats.record(t, res);
+
+        if last_write.elapsed() >= WRITE_EVERY {
+            last_write = Instant::now();
+            let t = Instant::now();
+            let res = pid.get_sv();
+            if let Some(sv) = stats.record(t, res) {
+                let t = Instant::now();
+                let res = pid.set_sv(sv);
+                stats.record(t, res);
+            }
+        }
+
+        if last_report.elapsed() >= REPORT_EVERY {
+            last_report = Instant::now();
+            stats.report(start.elapsed());
+        }
+
+        std::thread::sleep(POLL_EVERY.saturating_sub(tick.elapsed()));
+    }
+
+    stats.report(start.elapsed());
+}
@@ -0,0 +1,215 @@
+//! Command line access to a SYL-2381 on a serial port.
+//!
+//! ```text
+//! syl2381 [options] dump
+//! syl2381 [options] get <param>
+//! syl2381 [options] set <param> <value>
+//! syl2381 [options] monitor
+//! syl2381 [options] apply <profile.toml>
+//! syl2381 [options] tui
+//! ```
+//!
+//! Parameters go by their front panel code (`sv`, `p`, `inty`, ...).
+
+use std::env;
+use std::fmt;
+use std::process::ExitCode;
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "tui")]
+mod tui;
+
+use syl2381::{profile, Config, IoTransport, Param, ParamValue, Percent, Syl2381};
+
+const USAGE: &str = "\
+usage: syl2381 [options] <command>
+
+commands:
+    dump                    print every parameter
+    get <param>             print one parameter, by its front panel code
+    set <param> <value>     write one parameter
+    monitor                 print PV, OUT and the status flags every second
+    apply <profile>         write the parameters that differ from a profile
+    tui                     full screen live view (needs the tui feature)
+
+options:
+    -p, --port <path>       serial port (default: $SYL2381_PORT)
+    -u, --unit <id>         unit ID (default: 1)
+    -b, --baud <rate>       baud rate (default: 9600)
+    -t, --timeout <ms>      response timeout (default: 1000)
+";
+
+type Port = IoTransport<Box<dyn serialport::SerialPort>>;
+
+struct Options {
+    port: Option<String>,
+    unit: u8,
+    baud: u32,
+    timeout: Duration,
+}
+
+enum Command {
+    Dump,
+    Get(Param),
+    Set(Param, ParamValue),
+    Monitor,
+    Apply(String),
+    #[cfg(feature = "tui")]
+    Tui,
+}
+
+fn main() -> ExitCode {
+    let (opts, cmd) = match parse_args(env::args().skip(1)) {
+        Ok(parsed) => parsed,
+        Err(msg) => {
+            eprint!("{msg}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match run(&opts, cmd) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(msg) => {
+            eprintln!("syl2381: {msg}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<(Options, Command), String> {
+    let mut opts = Options {
+        port: env::var("SYL2381_PORT").ok(),
+        unit: 1,
+        baud: 9600,
+        timeout: Duration::from_millis(1000),
+    };
+    let mut words = Vec::new();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            "-p" | "--port" => opts.port = Some(value()?),
+            "-u" | "--unit" => opts.unit = number(&value()?)?,
+            "-b" | "--baud" => opts.baud = number(&value()?)?,
+            "-t" | "--timeout" => opts.timeout = Duration::from_millis(number(&value()?)?),
+            "-h" | "--help" => {
+                print!("{USAGE}");
+                std::process::exit(0);
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option {arg}")),
+            _ => words.push(arg.clone()),
+        }
+    }
+
+    let cmd = match &words.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["dump"] => Command::Dump,
+        ["get", param] => Command::Get(param_named(param)?),
+        ["set", param, value] => {
+            let param = param_named(param)?;
+            Command::Set(param, parse_value(param, value)?)
+        }
+        ["monitor"] => Command::Monitor,
+        ["apply", path] => Command::Apply(path.to_string()),
+        #[cfg(feature = "tui")]
+        ["tui"] => Command::Tui,
+        [] => return Err("no command given".into()),
+        [cmd, ..] => return Err(format!("bad arguments to {cmd}")),
+    };
+    Ok((opts, cmd))
+}
+
+fn number<T: std::str::FromStr>(s: &str) -> Result<T, String> {
+    s.parse().map_err(|_| format!("{s} isn't a valid number"))
+}
+
+fn param_named(code: &str) -> Result<Param, String> {
+    code.parse()
+        .map_err(|()| format!("no parameter {code}; try one of {}", codes()))
+}
+
+fn codes() -> String {
+    let codes: Vec<_> = Param::ALL.iter().map(|p| p.meta().code).collect();
+    codes.join(", ")
+}
+
+/// A number as stored, `on`/`off` for flags, `40%` for OUT and SouF, or an
+/// option's name (`K`, `Weak`, `Cooling`).
+fn parse_value(param: Param, s: &str) -> Result<ParamValue, String> {
+    let meta = param.meta();
+    let stored = match s.to_ascii_lowercase().as_str() {
+        "on" | "true" => Some(1.0),
+        "off" | "false" => Some(0.0),
+        pct if pct.ends_with('%') => pct[..pct.len() - 1]
+            .parse()
+            .ok()
+            .map(|val| Percent::from_percent(val).0),
+        num => num.parse().ok(),
+    };
+    let val = match stored {
+        Some(val) => ParamValue::from_stored(param, val),
+        None => (0..=meta.max as u8)
+            .filter_map(|i| ParamValue::from_stored(param, i as f32))
+            .find(|val| val.to_string().eq_ignore_ascii_case(s)),
+    };
+    val.ok_or_else(|| format!("{s} isn't a value for {}", meta.code))
+}
+
+fn run(opts: &Options, cmd: Command) -> Result<(), String> {
+    let mut pid = open(opts)?;
+    match cmd {
+        Command::Dump => print!("{}", pid.read_snapshot().map_err(failed)?),
+        Command::Get(param) => println!("{}", pid.get_param(param).map_err(failed)?),
+        Command::Set(param, val) => {
+            pid.set_param(param, val).map_err(failed)?;
+            let val = pid.get_param(param).map_err(failed)?;
+            println!("{} = {}", param.meta().code, val);
+        }
+        Command::Monitor => loop {
+            let vals = pid.poll().map_err(failed)?;
+            let status = vals.status;
+            let flags = [
+                (status.alarm1(), "AL1"),
+                (status.anomaly(), "ANOMALY"),
+                (status.manual_mode(), "MANUAL"),
+                (status.autotune_mode(), "AT"),
+                (status.cooling_mode(), "COOL"),
+                (status.setting_mode(), "SETTING"),
+            ];
+            let flags: Vec<_> = flags.iter().filter(|f| f.0).map(|f| f.1).collect();
+            println!("{vals} {}", flags.join(" "));
+            thread::sleep(Duration::from_secs(1));
+        },
+        Command::Apply(path) => {
+            let cfg: Config = profile::load(&path).map_err(|err| format!("{path}: {err}"))?;
+            let changes = pid.apply(&cfg).map_err(failed)?;
+            if changes.is_empty() {
+                println!("nothing to change");
+            }
+            for change in &changes {
+                println!("{change}");
+            }
+        }
+        #[cfg(feature = "tui")]
+        Command::Tui => tui::run(&mut pid, Duration::from_secs(1))?,
+    }
+    Ok(())
+}
+
+fn open(opts: &Options) -> Result<Syl2381<Port>, String> {
+    let path = opts
+        .port
+        .as_deref()
+        .ok_or("no serial port; pass --port or set SYL2381_PORT")?;
+    let port = serialport::new(path, opts.baud)
+        .data_bits(serialport::DataBits::Eight)
+        .parity(serialport::Parity::None)
+        .stop_bits(serialport::StopBits::One)
+        .flow_control(serialport::FlowControl::None)
+        .timeout(opts.timeout)
+        .open()
+        .map_err(|err| format!("{path}: {err}"))?;
+    Ok(Syl2381::new(opts.unit, IoTransport::new(port)))
+}
+
+fn failed<E: fmt::Debug>(err: syl2381::Error<E>) -> String {
+    err.to_string()
+}
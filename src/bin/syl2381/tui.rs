@@ -0,0 +1,181 @@
+//! `syl2381 tui`: a full screen live view for bench tuning.
+//!
+//! PV and SV up top, OUT as a bar, the status flags, and a sparkline of
+//! recent PV readings. A failed read is shown at the bottom and the view
+//! carries on polling, so a loose cable doesn't end the session.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Gauge, Paragraph, Sparkline};
+use ratatui::{DefaultTerminal, Frame};
+use syl2381::{ProcessValues, Syl2381, TempRaw, Transport};
+
+/// PV readings kept for the sparkline; wider than any terminal.
+const HISTORY: usize = 512;
+
+#[derive(Default)]
+struct View {
+    latest: Option<ProcessValues>,
+    sv: Option<TempRaw>,
+    history: VecDeque<f32>,
+    error: Option<String>,
+}
+
+/// Poll every `period` until `q` or Esc is pressed.
+pub fn run<S>(pid: &mut Syl2381<S>, period: Duration) -> Result<(), String>
+where
+    S: Transport,
+    S::Error: fmt::Debug,
+{
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, pid, period);
+    ratatui::restore();
+    result
+}
+
+fn event_loop<S>(
+    terminal: &mut DefaultTerminal,
+    pid: &mut Syl2381<S>,
+    period: Duration,
+) -> Result<(), String>
+where
+    S: Transport,
+    S::Error: fmt::Debug,
+{
+    let mut view = View::default();
+    loop {
+        view.update(pid);
+        terminal
+            .draw(|frame| view.draw(frame))
+            .map_err(|err| err.to_string())?;
+        if event::poll(period).map_err(|err| err.to_string())? {
+            if let Event::Key(key) = event::read().map_err(|err| err.to_string())? {
+                let quit = matches!(key.code, KeyCode::Char('q') | KeyCode::Esc);
+                if quit && key.kind == KeyEventKind::Press {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+impl View {
+    fn update<S>(&mut self, pid: &mut Syl2381<S>)
+    where
+        S: Transport,
+        S::Error: fmt::Debug,
+    {
+        let read = pid.poll().and_then(|vals| Ok((vals, pid.get_sv()?)));
+        match read {
+            Ok((vals, sv)) => {
+                if self.history.len() == HISTORY {
+                    self.history.pop_front();
+                }
+                self.history.push_back(vals.pv.0);
+                self.latest = Some(vals);
+                self.sv = Some(sv);
+                self.error = None;
+            }
+            Err(err) => self.error = Some(err.to_string()),
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [readout, out, flags, trend, footer] = Layout::vertical([
+            Constraint::Length(5),
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Min(4),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [pv, sv] = Layout::horizontal([Constraint::Ratio(1, 2); 2]).areas(readout);
+
+        let pv_val = self.latest.map(|vals| vals.pv);
+        frame.render_widget(big("PV", pv_val, Color::Green), pv);
+        frame.render_widget(big("SV", self.sv, Color::Cyan), sv);
+
+        let ratio = self.latest.map_or(0.0, |vals| vals.out.0.clamp(0.0, 1.0));
+        let label = self
+            .latest
+            .map_or_else(|| "--".to_string(), |vals| vals.out.to_string());
+        let gauge = Gauge::default()
+            .block(Block::bordered().title("OUT"))
+            .gauge_style(Style::new().fg(Color::Yellow))
+            .ratio(ratio as f64)
+            .label(label);
+        frame.render_widget(gauge, out);
+
+        frame.render_widget(self.flags(), flags);
+        self.draw_trend(frame, trend);
+
+        let footer_line = match &self.error {
+            Some(err) => Line::from(err.as_str()).red(),
+            None => Line::from("q to quit").dim(),
+        };
+        frame.render_widget(footer_line, footer);
+    }
+
+    fn flags(&self) -> Paragraph<'static> {
+        let mut spans = Vec::new();
+        if let Some(vals) = self.latest {
+            let status = vals.status;
+            for (on, name, color) in [
+                (status.alarm1(), "AL1", Color::Red),
+                (status.anomaly(), "ANOMALY", Color::Red),
+                (status.manual_mode(), "MANUAL", Color::Yellow),
+                (status.autotune_mode(), "AT", Color::Yellow),
+                (status.cooling_mode(), "COOL", Color::Cyan),
+                (status.setting_mode(), "SETTING", Color::Magenta),
+            ] {
+                let span = Span::raw(format!(" {name} "));
+                spans.push(if on {
+                    span.bg(color).black().bold()
+                } else {
+                    span.dim()
+                });
+                spans.push(Span::raw(" "));
+            }
+        }
+        Paragraph::new(Line::from(spans)).block(Block::bordered().title("Status"))
+    }
+
+    /// The newest readings that fit, scaled to tenths of a degree above the
+    /// lowest one shown.
+    fn draw_trend(&self, frame: &mut Frame, area: Rect) {
+        let width = area.width.saturating_sub(2) as usize;
+        let shown = self
+            .history
+            .iter()
+            .skip(self.history.len().saturating_sub(width));
+        let (min, max) = shown
+            .clone()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &pv| {
+                (lo.min(pv), hi.max(pv))
+            });
+        let data: Vec<u64> = shown.map(|&pv| ((pv - min) * 10.0) as u64).collect();
+        let title = if data.is_empty() {
+            "PV".to_string()
+        } else {
+            format!("PV {} to {}", TempRaw(min), TempRaw(max))
+        };
+        let sparkline = Sparkline::default()
+            .block(Block::bordered().title(title))
+            .style(Style::new().fg(Color::Green))
+            .data(&data);
+        frame.render_widget(sparkline, area);
+    }
+}
+
+fn big(title: &'static str, val: Option<TempRaw>, color: Color) -> Paragraph<'static> {
+    let text = val.map_or_else(|| "--".to_string(), |TempRaw(val)| format!("{val:.1}°"));
+    Paragraph::new(vec![Line::default(), Line::from(text).bold().fg(color)])
+        .centered()
+        .block(Block::bordered().title(title))
+}
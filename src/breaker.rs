@@ -0,0 +1,246 @@
+//! Circuit breaker for a controller that has stopped answering.
+//!
+//! Every request to a dead controller costs a full read timeout, during which
+//! nothing else on the RS-485 segment can be talked to. After `threshold`
+//! consecutive communication failures the breaker opens and fails calls
+//! immediately with [`Error::CircuitOpen`] until `cooldown` has passed, as
+//! told by the driver's clock. It then lets a single trial call through
+//! (half-open): an answer from the controller closes the breaker, anything
+//! else opens it again for another `cooldown`.
+
+use core::fmt;
+use core::time::Duration;
+
+use crate::clock::Clock;
+use crate::transport::Transport;
+use crate::{Error, Labels, Syl2381};
+
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BreakerState {
+    /// Calls go through normally.
+    Closed,
+
+    /// Calls fail immediately.
+    Open,
+
+    /// The cool-down has passed; the next call is a trial.
+    HalfOpen,
+}
+
+impl fmt::Display for BreakerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <Self as fmt::Debug>::fmt(self, f)
+    }
+}
+
+pub struct Breaker<UART, CLOCK> {
+    inner: Syl2381<UART, CLOCK>,
+    threshold: u16,
+    cooldown: Duration,
+    state: BreakerState,
+    failures: u16,
+    opened_at: Duration,
+    on_transition: Option<fn(&Labels, BreakerState)>,
+}
+
+impl<UART, CLOCK> Breaker<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: Clock,
+{
+    /// Wrap `inner`, opening after `threshold` consecutive failures and
+    /// staying open for `cooldown` by `inner`'s clock.
+    pub fn new(inner: Syl2381<UART, CLOCK>, threshold: u16, cooldown: Duration) -> Self {
+        Breaker {
+            inner,
+            threshold: threshold.max(1),
+            cooldown,
+            state: BreakerState::Closed,
+            failures: 0,
+            opened_at: Duration::ZERO,
+            on_transition: None,
+        }
+    }
+
+    /// Call `f` with the controller's labels and the new state whenever the
+    /// breaker changes state.
+    pub fn on_transition(&mut self, f: fn(&Labels, BreakerState)) {
+        self.on_transition = Some(f);
+    }
+
+    pub fn state(&self) -> BreakerState {
+        self.state
+    }
+
+    /// Run `op` against the controller, unless the breaker is open.
+    ///
+    /// Only communication failures (serial and Modbus errors, timeouts, and a
+    /// failed or busy controller) count towards opening the breaker. Success,
+    /// or an exception saying the controller doesn't take the function,
+    /// address or value, shows the controller answered, and closes it again.
+    /// Any other error, such as a value rejected before anything was sent,
+    /// leaves a closed breaker as it was, and opens a half-open one again,
+    /// since the trial showed nothing.
+    pub fn call<T>(
+        &mut self,
+        op: impl FnOnce(&mut Syl2381<UART, CLOCK>) -> crate::Result<T, UART>,
+    ) -> crate::Result<T, UART> {
+        if self.state == BreakerState::Open {
+            if self.now().saturating_sub(self.opened_at) < self.cooldown {
+                return Err(Error::CircuitOpen);
+            }
+            self.transition(BreakerState::HalfOpen);
+        }
+
+        let res = op(&mut self.inner);
+        match &res {
+            Err(Error::SerialError(..))
+            | Err(Error::ModbusError(..))
+            | Err(Error::Timeout(_))
+            | Err(Error::DeviceFailure(_))
+            | Err(Error::DeviceBusy(_)) => {
+                self.failures = self.failures.saturating_add(1);
+                if self.state == BreakerState::HalfOpen || self.failures >= self.threshold {
+                    self.open();
+                }
+            }
+            Ok(_)
+            | Err(Error::IllegalFunction(_))
+            | Err(Error::IllegalDataAddress(_))
+            | Err(Error::IllegalDataValue(_)) => {
+                self.failures = 0;
+                if self.state != BreakerState::Closed {
+                    self.transition(BreakerState::Closed);
+                }
+            }
+            Err(_) if self.state == BreakerState::HalfOpen => self.open(),
+            Err(_) => {}
+        }
+
+        res
+    }
+
+    /// Unwrap the breaker, returning the driver.
+    pub fn into_inner(self) -> Syl2381<UART, CLOCK> {
+        self.inner
+    }
+
+    fn now(&self) -> Duration {
+        self.inner.clock().now()
+    }
+
+    fn open(&mut self) {
+        self.opened_at = self.now();
+        self.transition(BreakerState::Open);
+    }
+
+    fn transition(&mut self, state: BreakerState) {
+        self.state = state;
+        if let Some(f) = self.on_transition {
+            f(self.inner.labels(), state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::VirtualClock;
+    use crate::mock::MockSerial;
+
+    #[test]
+    fn opens_after_threshold_and_recovers() {
+        let clock = VirtualClock::new();
+        let pid = Syl2381::new(1, MockSerial::new()).with_clock(&clock);
+        let mut breaker = Breaker::new(pid, 3, Duration::from_secs(10));
+
+        // Nothing queued on the mock, so every read fails.
+        for _ in 0..3 {
+            assert!(matches!(
+                breaker.call(|pid| pid.get_pv()),
+                Err(Error::SerialError(..))
+            ));
+        }
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(matches!(
+            breaker.call(|pid| pid.get_pv()),
+            Err(Error::CircuitOpen)
+        ));
+
+        clock.advance(Duration::from_secs(10));
+        breaker.inner.port.respond_f32(1, 25.0);
+        assert!(breaker.call(|pid| pid.get_pv()).is_ok());
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[test]
+    fn failed_trial_reopens() {
+        let clock = VirtualClock::new();
+        let pid = Syl2381::new(1, MockSerial::new()).with_clock(&clock);
+        let mut breaker = Breaker::new(pid, 1, Duration::from_secs(10));
+
+        assert!(breaker.call(|pid| pid.get_pv()).is_err());
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        clock.advance(Duration::from_secs(10));
+        assert!(matches!(
+            breaker.call(|pid| pid.get_pv()),
+            Err(Error::SerialError(..))
+        ));
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(matches!(
+            breaker.call(|pid| pid.get_pv()),
+            Err(Error::CircuitOpen)
+        ));
+    }
+
+    #[test]
+    fn rejected_calls_prove_nothing() {
+        let clock = VirtualClock::new();
+        let pid = Syl2381::new(1, MockSerial::new()).with_clock(&clock);
+        let mut breaker = Breaker::new(pid, 2, Duration::from_secs(10));
+
+        assert!(breaker.call(|pid| pid.get_pv()).is_err());
+        assert!(matches!(
+            breaker.call(|pid| pid.set_sv(crate::TempRaw(1e6))),
+            Err(Error::UnexpectedValue(_))
+        ));
+        assert!(breaker.call(|pid| pid.get_pv()).is_err());
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        // A trial that never reaches the controller doesn't close the
+        // breaker, and doesn't leave it open to more trials either.
+        clock.advance(Duration::from_secs(10));
+        assert!(breaker.call(|pid| pid.set_sv(crate::TempRaw(1e6))).is_err());
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(matches!(
+            breaker.call(|pid| pid.get_pv()),
+            Err(Error::CircuitOpen)
+        ));
+        assert_eq!(breaker.inner.port.tx.len(), 2 * 8);
+    }
+
+    #[test]
+    fn exceptions_show_the_controller_answered() {
+        let clock = VirtualClock::new();
+        let pid = Syl2381::new(1, MockSerial::new()).with_clock(&clock);
+        let mut breaker = Breaker::new(pid, 2, Duration::from_secs(10));
+
+        assert!(breaker.call(|pid| pid.get_pv()).is_err());
+        breaker.inner.port.respond(&[1, 0x83, 0x02]); // illegal data address
+        assert!(matches!(
+            breaker.call(|pid| pid.get_pv()),
+            Err(Error::IllegalDataAddress(_))
+        ));
+        assert!(breaker.call(|pid| pid.get_pv()).is_err());
+        assert_eq!(breaker.state(), BreakerState::Closed);
+
+        assert!(breaker.call(|pid| pid.get_pv()).is_err());
+        assert_eq!(breaker.state(), BreakerState::Open);
+        clock.advance(Duration::from_secs(10));
+        breaker.inner.port.respond(&[1, 0x83, 0x02]);
+        assert!(breaker.call(|pid| pid.get_pv()).is_err());
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+}
@@ -0,0 +1,99 @@
+//! Several controllers on one port.
+//!
+//! RS-485 is multi-drop, but [`Syl2381::new`] takes the port by value. A
+//! [`Bus`] owns the port instead and hands out a driver per unit ID, each
+//! talking through a [`BusPort`] that borrows the shared port for the length
+//! of a call. A driver call runs to completion before the next one starts,
+//! so requests to different units never interleave on the wire.
+
+use core::cell::RefCell;
+
+use crate::transport::{Ack, Transport};
+use crate::Syl2381;
+
+pub struct Bus<UART> {
+    port: RefCell<UART>,
+}
+
+impl<UART> Bus<UART>
+where
+    UART: Transport,
+{
+    pub fn new(port: UART) -> Self {
+        Bus {
+            port: RefCell::new(port),
+        }
+    }
+
+    /// A driver for the controller at `unit_id`.
+    pub fn unit(&self, unit_id: u8) -> Syl2381<BusPort<'_, UART>> {
+        Syl2381::new(unit_id, BusPort { port: &self.port })
+    }
+
+    pub fn get_mut(&mut self) -> &mut UART {
+        self.port.get_mut()
+    }
+
+    pub fn into_inner(self) -> UART {
+        self.port.into_inner()
+    }
+}
+
+/// A [`Bus`]'s port, as seen by one driver.
+pub struct BusPort<'a, UART> {
+    port: &'a RefCell<UART>,
+}
+
+impl<UART> Transport for BusPort<'_, UART>
+where
+    UART: Transport,
+{
+    type Error = UART::Error;
+
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        self.port.borrow_mut().write_frame(frame)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.port.borrow_mut().read_exact(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.port.borrow_mut().flush()
+    }
+
+    fn is_timeout(err: &Self::Error) -> bool {
+        UART::is_timeout(err)
+    }
+
+    fn acknowledged(&mut self, ack: &Ack) {
+        self.port.borrow_mut().acknowledged(ack)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{frame, MockSerial};
+    use crate::TempRaw;
+
+    #[test]
+    fn addresses_each_unit() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 20.0);
+        port.respond_f32(2, 30.0);
+        port.respond_f32(1, 21.0);
+        let bus = Bus::new(port);
+
+        let mut kiln = bus.unit(1);
+        let mut smoker = bus.unit(2);
+        assert!(matches!(kiln.get_pv(), Ok(TempRaw(v)) if v == 20.0));
+        assert!(matches!(smoker.get_pv(), Ok(TempRaw(v)) if v == 30.0));
+        assert!(matches!(kiln.get_pv(), Ok(TempRaw(v)) if v == 21.0));
+
+        let port = bus.into_inner();
+        assert!(port.rx.is_empty());
+        assert_eq!(port.tx[..8], frame(&[0x01, 0x03, 0x01, 0x64, 0x00, 0x02]));
+        assert_eq!(port.tx[8..16], frame(&[0x02, 0x03, 0x01, 0x64, 0x00, 0x02]));
+    }
+}
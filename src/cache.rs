@@ -0,0 +1,219 @@
+//! Bounded-staleness reads of live values.
+//!
+//! UI code tends to ask for PV far more often than it changes. The `*_cached`
+//! getters serve a recent enough reading from memory and only go to the bus
+//! when the last one is older than the caller's `max_age`.
+
+use core::time::Duration;
+
+use crate::clock::{Clock, DriverClock};
+use crate::regs;
+use crate::transport::Transport;
+use crate::{DisplayUnit, InputType, Percent, Status, Syl2381, TempRaw};
+
+/// Timestamped copies of recently read live values, and the display unit and
+/// sensor type.
+#[derive(Default)]
+pub(crate) struct LiveCache {
+    holdings: heapless::LinearMap<u16, (Duration, f32), 4>,
+    status: Option<(Duration, u8)>,
+    pub(crate) display_unit: Option<DisplayUnit>,
+    pub(crate) input_type: Option<InputType>,
+}
+
+impl LiveCache {
+    /// Drop any cached copy of `reg`, e.g. after writing it.
+    pub(crate) fn invalidate(&mut self, reg: regs::Reg) {
+        if reg.bank == regs::Bank::Status {
+            self.status = None;
+            return;
+        }
+        self.holdings.remove(&reg.addr);
+        if reg.addr == regs::CORF.addr {
+            // Every cached temperature is in the old unit.
+            self.holdings.clear();
+            self.display_unit = None;
+        }
+        if reg.addr == regs::INTY.addr {
+            self.input_type = None;
+        }
+    }
+
+    /// Drop everything cached.
+    pub(crate) fn clear(&mut self) {
+        *self = LiveCache::default();
+    }
+
+    /// Take `input` and `unit` as already read, for tests that aren't about
+    /// the sensor range check.
+    #[cfg(test)]
+    pub(crate) fn assume_sensor(&mut self, input: InputType, unit: DisplayUnit) {
+        self.input_type = Some(input);
+        self.display_unit = Some(unit);
+    }
+
+    fn fresh_holding(&self, reg: regs::Reg, now: Duration, max_age: Duration) -> Option<f32> {
+        match self.holdings.get(&reg.addr) {
+            Some(&(at, val)) if now.saturating_sub(at) <= max_age => Some(val),
+            _ => None,
+        }
+    }
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    /// Get the process value (PV), reusing a reading taken within `max_age`.
+    pub fn get_pv_cached(
+        &mut self,
+        clock: &impl Clock,
+        max_age: Duration,
+    ) -> crate::Result<TempRaw, UART> {
+        let val = self.get_holding_cached(regs::PV, clock, max_age)?;
+        Ok(TempRaw(val))
+    }
+
+    /// Get the power output percentage (OUT), reusing a reading taken within
+    /// `max_age`.
+    pub fn get_out_cached(
+        &mut self,
+        clock: &impl Clock,
+        max_age: Duration,
+    ) -> crate::Result<Percent, UART> {
+        let val = self.get_holding_cached(regs::OUT, clock, max_age)?;
+        Ok(Percent(val))
+    }
+
+    /// Get the set value (SV), reusing a reading taken within `max_age`.
+    pub fn get_sv_cached(
+        &mut self,
+        clock: &impl Clock,
+        max_age: Duration,
+    ) -> crate::Result<TempRaw, UART> {
+        let val = self.get_holding_cached(regs::SV, clock, max_age)?;
+        Ok(TempRaw(val))
+    }
+
+    /// Get flag status (AT), reusing a reading taken within `max_age`.
+    pub fn get_status_cached(
+        &mut self,
+        clock: &impl Clock,
+        max_age: Duration,
+    ) -> crate::Result<Status, UART> {
+        let now = clock.now();
+        if let Some((at, val)) = self.live.status {
+            if now.saturating_sub(at) <= max_age {
+                return Ok(Status(val));
+            }
+        }
+
+        let status = self.get_status()?;
+        self.live.status = Some((now, status.0));
+        Ok(status)
+    }
+
+    fn get_holding_cached(
+        &mut self,
+        reg: regs::Reg,
+        clock: &impl Clock,
+        max_age: Duration,
+    ) -> crate::Result<f32, UART> {
+        let now = clock.now();
+        if let Some(val) = self.live.fresh_holding(reg, now, max_age) {
+            return Ok(val);
+        }
+
+        let val = self.get_holding(reg)?;
+        // Only a handful of registers are ever cached, so this can't overflow.
+        let _ = self.live.holdings.insert(reg.addr, (now, val));
+        Ok(val)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::VirtualClock;
+    use crate::mock::MockSerial;
+
+    #[test]
+    fn serves_fresh_readings_from_memory() {
+        let clock = VirtualClock::new();
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 60.0);
+        port.respond_f32(1, 61.0);
+        let mut pid = Syl2381::new(1, port);
+        let max_age = Duration::from_secs(1);
+
+        assert!(matches!(pid.get_pv_cached(&clock, max_age), Ok(TempRaw(v)) if v == 60.0));
+        clock.advance(Duration::from_millis(500));
+        assert!(matches!(pid.get_pv_cached(&clock, max_age), Ok(TempRaw(v)) if v == 60.0));
+        clock.advance(Duration::from_millis(600));
+        assert!(matches!(pid.get_pv_cached(&clock, max_age), Ok(TempRaw(v)) if v == 61.0));
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn writes_invalidate() {
+        let clock = VirtualClock::new();
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 60.0);
+        port.ack_f32(1, regs::SV.addr);
+        port.respond_f32(1, 65.0);
+        let mut pid = Syl2381::new(1, port);
+        pid.live
+            .assume_sensor(crate::InputType::K, crate::DisplayUnit::Celsius);
+        let max_age = Duration::from_secs(60);
+
+        assert!(matches!(pid.get_sv_cached(&clock, max_age), Ok(TempRaw(v)) if v == 60.0));
+        assert!(pid.set_sv(TempRaw(65.0)).is_ok());
+        assert!(matches!(pid.get_sv_cached(&clock, max_age), Ok(TempRaw(v)) if v == 65.0));
+    }
+
+    #[test]
+    fn unit_changes_invalidate_temperatures() {
+        let clock = VirtualClock::new();
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 60.0);
+        port.ack_f32(1, regs::CORF.addr);
+        port.respond_f32(1, 140.0);
+        let mut pid = Syl2381::new(1, port);
+        let max_age = Duration::from_secs(60);
+
+        assert!(matches!(pid.get_pv_cached(&clock, max_age), Ok(TempRaw(v)) if v == 60.0));
+        assert!(pid.set_display_unit(DisplayUnit::Fahrenheit).is_ok());
+        assert!(matches!(pid.get_pv_cached(&clock, max_age), Ok(TempRaw(v)) if v == 140.0));
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn coil_writes_invalidate_status() {
+        let clock = VirtualClock::new();
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x01, 1, 0x00]);
+        port.respond(&[1, 0x05, 0x00, 0x00, 0xFF, 0x00]);
+        port.respond(&[1, 0x01, 1, 0x01]);
+        port.respond(&[1, 0x05, 0x00, 0x00, 0x00, 0x00]);
+        port.respond(&[1, 0x01, 1, 0x00]);
+        let mut pid = Syl2381::new(1, port);
+        let max_age = Duration::from_secs(60);
+
+        assert!(!pid
+            .get_status_cached(&clock, max_age)
+            .unwrap()
+            .autotune_mode());
+        assert!(pid.start_autotune().is_ok());
+        assert!(pid
+            .get_status_cached(&clock, max_age)
+            .unwrap()
+            .autotune_mode());
+        assert!(pid.write_coil_raw(regs::AT.addr, false).is_ok());
+        assert!(!pid
+            .get_status_cached(&clock, max_age)
+            .unwrap()
+            .autotune_mode());
+        assert!(pid.port.rx.is_empty());
+    }
+}
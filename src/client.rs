@@ -0,0 +1,391 @@
+//! Running the driver on someone else's Modbus client.
+//!
+//! [`Syl2381Async`](crate::Syl2381Async) contributes the register semantics
+//! (scaling, validation, typed parameters) and leaves the wire to a
+//! [`ModbusClient`]. Any `embedded-io-async` port is one, speaking RTU itself;
+//! an application that already has a Modbus stack shared with other devices
+//! implements the trait for it instead (or, with the `tokio-modbus` feature,
+//! wraps it in `TokioModbus`).
+
+use core::future::Future;
+
+use embedded_io_async::{Read, ReadExactError, Write};
+use rmodbus::ErrorKind;
+
+use crate::frame::{Frame, RESYNC_WINDOW};
+use crate::regs::Reg;
+#[cfg(feature = "tokio-modbus")]
+use crate::values_to_f32;
+use crate::{Context, Error};
+
+/// Why a request failed, before the driver adds its [`Context`].
+#[derive(Debug)]
+pub enum ClientError<E> {
+    /// The underlying transport failed.
+    Io(E),
+
+    /// No response in time.
+    Timeout,
+
+    /// A malformed response, or a Modbus exception.
+    Modbus(ErrorKind),
+}
+
+impl<E> From<ErrorKind> for ClientError<E> {
+    fn from(kind: ErrorKind) -> Self {
+        ClientError::Modbus(kind)
+    }
+}
+
+impl<E> ClientError<E> {
+    pub(crate) fn with(self, ctx: Context) -> Error<E> {
+        match self {
+            ClientError::Io(err) => Error::SerialError(ctx, err),
+            ClientError::Timeout => Error::Timeout(ctx),
+            ClientError::Modbus(kind) => Error::modbus(ctx, kind),
+        }
+    }
+}
+
+/// The four requests the SYL-2381 needs.
+///
+/// Holding parameters are f32s spanning two registers, high word first.
+pub trait ModbusClient {
+    type Error;
+
+    /// Read the holding parameter at `reg`.
+    fn read_holding(
+        &mut self,
+        unit_id: u8,
+        reg: Reg,
+    ) -> impl Future<Output = Result<f32, ClientError<Self::Error>>>;
+
+    /// Write the holding parameter at `reg`.
+    fn write_holding(
+        &mut self,
+        unit_id: u8,
+        reg: Reg,
+        val: f32,
+    ) -> impl Future<Output = Result<(), ClientError<Self::Error>>>;
+
+    /// Read `count` (at most 8) coils starting at `reg`, first coil in bit 0.
+    fn read_coils(
+        &mut self,
+        unit_id: u8,
+        reg: Reg,
+        count: u8,
+    ) -> impl Future<Output = Result<u8, ClientError<Self::Error>>>;
+
+    /// Switch the coil at `reg` on or off.
+    fn write_coil(
+        &mut self,
+        unit_id: u8,
+        reg: Reg,
+        on: bool,
+    ) -> impl Future<Output = Result<(), ClientError<Self::Error>>>;
+}
+
+/// Modbus RTU over the port.
+impl<T> ModbusClient for T
+where
+    T: Read + Write,
+{
+    type Error = T::Error;
+
+    async fn read_holding(&mut self, unit_id: u8, reg: Reg) -> Result<f32, ClientError<T::Error>> {
+        let mut frame = Frame::get_holding(unit_id, reg)?;
+        transact(self, &mut frame).await?;
+        Ok(frame.parse_f32()?)
+    }
+
+    async fn write_holding(
+        &mut self,
+        unit_id: u8,
+        reg: Reg,
+        val: f32,
+    ) -> Result<(), ClientError<T::Error>> {
+        let mut frame = Frame::set_holding(unit_id, reg, val)?;
+        transact(self, &mut frame).await?;
+        Ok(frame.parse_ok()?)
+    }
+
+    async fn read_coils(
+        &mut self,
+        unit_id: u8,
+        reg: Reg,
+        count: u8,
+    ) -> Result<u8, ClientError<T::Error>> {
+        let mut frame = Frame::get_coils(unit_id, reg.addr, count)?;
+        transact(self, &mut frame).await?;
+        Ok(frame.parse_coils()?)
+    }
+
+    async fn write_coil(
+        &mut self,
+        unit_id: u8,
+        reg: Reg,
+        on: bool,
+    ) -> Result<(), ClientError<T::Error>> {
+        let mut frame = Frame::set_coil(unit_id, reg.addr, on)?;
+        transact(self, &mut frame).await?;
+        Ok(frame.parse_ok()?)
+    }
+}
+
+/// A `tokio-modbus` client context, owned or borrowed.
+///
+/// Selects the unit for each request, so the context can be shared with other
+/// devices between calls.
+#[cfg(feature = "tokio-modbus")]
+pub struct TokioModbus<C>(pub C);
+
+#[cfg(feature = "tokio-modbus")]
+impl<C> ModbusClient for TokioModbus<C>
+where
+    C: core::borrow::BorrowMut<tokio_modbus::client::Context>,
+{
+    type Error = tokio_modbus::Error;
+
+    async fn read_holding(
+        &mut self,
+        unit_id: u8,
+        reg: Reg,
+    ) -> Result<f32, ClientError<Self::Error>> {
+        use tokio_modbus::prelude::*;
+
+        let ctx = self.0.borrow_mut();
+        ctx.set_slave(Slave(unit_id));
+        let words = tokio_reply(ctx.read_holding_registers(reg.addr, 2).await)?;
+        match words[..] {
+            [d0, d1] => Ok(values_to_f32(d0, d1)),
+            _ => Err(ClientError::Modbus(ErrorKind::FrameBroken)),
+        }
+    }
+
+    async fn write_holding(
+        &mut self,
+        unit_id: u8,
+        reg: Reg,
+        val: f32,
+    ) -> Result<(), ClientError<Self::Error>> {
+        use tokio_modbus::prelude::*;
+
+        let ctx = self.0.borrow_mut();
+        ctx.set_slave(Slave(unit_id));
+        let words = crate::f32_to_values(val);
+        tokio_reply(ctx.write_multiple_registers(reg.addr, &words).await)
+    }
+
+    async fn read_coils(
+        &mut self,
+        unit_id: u8,
+        reg: Reg,
+        count: u8,
+    ) -> Result<u8, ClientError<Self::Error>> {
+        use tokio_modbus::prelude::*;
+
+        let ctx = self.0.borrow_mut();
+        ctx.set_slave(Slave(unit_id));
+        let coils = tokio_reply(ctx.read_coils(reg.addr, count.into()).await)?;
+        Ok(coils
+            .iter()
+            .take(8)
+            .enumerate()
+            .fold(0, |acc, (i, &on)| acc | (on as u8) << i))
+    }
+
+    async fn write_coil(
+        &mut self,
+        unit_id: u8,
+        reg: Reg,
+        on: bool,
+    ) -> Result<(), ClientError<Self::Error>> {
+        use tokio_modbus::prelude::*;
+
+        let ctx = self.0.borrow_mut();
+        ctx.set_slave(Slave(unit_id));
+        tokio_reply(ctx.write_single_coil(reg.addr, on).await)
+    }
+}
+
+#[cfg(feature = "tokio-modbus")]
+fn tokio_reply<T>(reply: tokio_modbus::Result<T>) -> Result<T, ClientError<tokio_modbus::Error>> {
+    match reply {
+        Ok(Ok(val)) => Ok(val),
+        Ok(Err(code)) => Err(ClientError::Modbus(ErrorKind::from_modbus_error(
+            code.into(),
+        ))),
+        Err(tokio_modbus::Error::Transport(err)) if err.kind() == std::io::ErrorKind::TimedOut => {
+            Err(ClientError::Timeout)
+        }
+        Err(err) => Err(ClientError::Io(err)),
+    }
+}
+
+/// Send the request and receive the response.
+async fn transact<T>(port: &mut T, frame: &mut Frame) -> Result<(), ClientError<T::Error>>
+where
+    T: Read + Write,
+{
+    port.write_all(frame.request()).await.map_err(io_error)?;
+    port.flush().await.map_err(io_error)?;
+    read_exact(port, frame.response_header()).await?;
+    let mut skipped = 0;
+    while let Some(next) = frame.resync_header() {
+        if skipped == RESYNC_WINDOW {
+            return Err(ClientError::Modbus(ErrorKind::FrameBroken));
+        }
+        skipped += 1;
+        read_exact(port, next).await?;
+    }
+    let body = frame.response_body()?;
+    read_exact(port, body).await
+}
+
+async fn read_exact<T>(port: &mut T, buf: &mut [u8]) -> Result<(), ClientError<T::Error>>
+where
+    T: Read,
+{
+    port.read_exact(buf).await.map_err(|err| match err {
+        // The port ran dry part way through a response.
+        ReadExactError::UnexpectedEof => ClientError::Modbus(ErrorKind::FrameBroken),
+        ReadExactError::Other(err) => io_error(err),
+    })
+}
+
+/// A port error, telling timeouts apart.
+fn io_error<E>(err: E) -> ClientError<E>
+where
+    E: embedded_io_async::Error,
+{
+    match err.kind() {
+        embedded_io_async::ErrorKind::TimedOut => ClientError::Timeout,
+        _ => ClientError::Io(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{block_on, MockSerial};
+    use crate::{regs, Operation, Syl2381Async, TempRaw};
+
+    /// A client that already knows PV and nothing else.
+    struct PvOnly;
+
+    impl ModbusClient for PvOnly {
+        type Error = ();
+
+        async fn read_holding(&mut self, _unit_id: u8, reg: Reg) -> Result<f32, ClientError<()>> {
+            if reg.addr == regs::PV.addr {
+                Ok(21.5)
+            } else {
+                Err(ClientError::Modbus(ErrorKind::IllegalDataAddress))
+            }
+        }
+
+        async fn write_holding(&mut self, _: u8, _: Reg, _: f32) -> Result<(), ClientError<()>> {
+            Err(ClientError::Timeout)
+        }
+
+        async fn read_coils(&mut self, _: u8, _: Reg, _: u8) -> Result<u8, ClientError<()>> {
+            Err(ClientError::Io(()))
+        }
+
+        async fn write_coil(&mut self, _: u8, _: Reg, _: bool) -> Result<(), ClientError<()>> {
+            Err(ClientError::Io(()))
+        }
+    }
+
+    /// A port that holds on to what's written until it's flushed, as a UART
+    /// with a transmit buffer does. The reply only comes once the request has
+    /// gone out.
+    #[derive(Default)]
+    struct Buffered {
+        port: MockSerial,
+        pending: Vec<u8>,
+    }
+
+    impl embedded_io_async::ErrorType for Buffered {
+        type Error = embedded_io_async::ErrorKind;
+    }
+
+    impl Read for Buffered {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            if !self.pending.is_empty() {
+                return Err(embedded_io_async::ErrorKind::TimedOut);
+            }
+            self.port.read(buf).await
+        }
+    }
+
+    impl Write for Buffered {
+        async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.pending.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        async fn flush(&mut self) -> Result<(), Self::Error> {
+            self.port.tx.append(&mut self.pending);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn runs_on_a_custom_client() {
+        let mut pid = Syl2381Async::new(1, PvOnly);
+        pid.assume_sensor(crate::InputType::K, crate::DisplayUnit::Celsius);
+
+        assert!(matches!(block_on(pid.get_pv()), Ok(TempRaw(v)) if v == 21.5));
+        assert!(matches!(
+            block_on(pid.get_sv()),
+            Err(Error::IllegalDataAddress(ctx)) if ctx.addr == regs::SV.addr
+        ));
+        assert!(matches!(
+            block_on(pid.set_sv(TempRaw(65.0))),
+            Err(Error::Timeout(ctx)) if ctx.op == Operation::WriteHolding
+        ));
+        assert!(matches!(
+            block_on(pid.get_status()),
+            Err(Error::SerialError(..))
+        ));
+    }
+
+    #[test]
+    fn flushes_before_reading() {
+        let mut port = Buffered::default();
+        port.port.respond_f32(1, 62.5);
+        let mut pid = Syl2381Async::new(1, port);
+
+        assert!(matches!(block_on(pid.get_pv()), Ok(TempRaw(v)) if v == 62.5));
+    }
+
+    #[cfg(feature = "tokio-modbus")]
+    #[tokio::test]
+    async fn runs_on_tokio_modbus() {
+        use crate::mock::frame;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (ours, mut theirs) = tokio::io::duplex(64);
+        let ctx = tokio_modbus::client::rtu::attach(ours);
+        let mut pid = Syl2381Async::new(3, TokioModbus(ctx));
+
+        let device = tokio::spawn(async move {
+            let mut req = [0; 8];
+            theirs.read_exact(&mut req).await.unwrap();
+            let resp = frame(&[0x03, 0x03, 0x04, 0x42, 0x48, 0x00, 0x00]);
+            theirs.write_all(&resp).await.unwrap();
+
+            theirs.read_exact(&mut req).await.unwrap();
+            theirs.write_all(&frame(&[0x03, 0x81, 0x02])).await.unwrap();
+            theirs
+        });
+
+        assert!(matches!(pid.get_sv().await, Ok(TempRaw(v)) if v == 50.0));
+        assert!(matches!(
+            pid.get_j1_status().await,
+            Err(Error::IllegalDataAddress(_))
+        ));
+        device.await.unwrap();
+    }
+}
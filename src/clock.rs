@@ -0,0 +1,180 @@
+//! Time sources.
+//!
+//! Anything in the crate that needs to know the time or wait takes a
+//! [`Clock`], so that it can run against a [`VirtualClock`] in tests, where an
+//! hour-long wait completes immediately.
+
+use core::cell::Cell;
+use core::fmt;
+#[cfg(feature = "async")]
+use core::future::Future;
+use core::time::Duration;
+
+/// A monotonic time source.
+pub trait Clock {
+    /// Time elapsed since an arbitrary, fixed epoch.
+    fn now(&self) -> Duration;
+
+    /// Wait for `duration` to pass.
+    ///
+    /// The default implementation spins on [`Clock::now`].
+    fn sleep(&self, duration: Duration) {
+        let start = self.now();
+        while self.now().saturating_sub(start) < duration {}
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> Duration {
+        C::now(self)
+    }
+
+    fn sleep(&self, duration: Duration) {
+        C::sleep(self, duration)
+    }
+}
+
+/// The clock of a driver that hasn't been given one.
+///
+/// It can't tell the time, so a [`Syl2381`](crate::Syl2381) with it takes no
+/// [`StaticCache`](crate::StaticCache) or [`WriteGuard`](crate::WriteGuard),
+/// and gives up at once under [`Lockout::Wait`](crate::Lockout::Wait). See
+/// [`Syl2381::with_clock`](crate::Syl2381::with_clock).
+#[derive(Clone, Copy, Default, fmt::Debug)]
+pub struct NoClock;
+
+/// What a [`Syl2381`](crate::Syl2381), or a recording in `fixture`, can
+/// keep as its clock: any [`Clock`], or [`NoClock`].
+///
+/// This trait is sealed.
+pub trait DriverClock: sealed::Sealed {
+    /// The clock, if there is one.
+    fn as_clock(&self) -> Option<&dyn Clock>;
+}
+
+impl DriverClock for NoClock {
+    fn as_clock(&self) -> Option<&dyn Clock> {
+        None
+    }
+}
+
+impl<C: Clock> DriverClock for C {
+    fn as_clock(&self) -> Option<&dyn Clock> {
+        Some(self)
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for super::NoClock {}
+
+    impl<C: super::Clock> Sealed for C {}
+}
+
+/// A [`Clock`] that can also wait without blocking, for async code.
+#[cfg(feature = "async")]
+pub trait AsyncClock: Clock {
+    /// Wait for `duration` to pass, letting other tasks run meanwhile.
+    fn sleep_async(&self, duration: Duration) -> impl Future<Output = ()>;
+}
+
+#[cfg(feature = "async")]
+impl<C: AsyncClock + ?Sized> AsyncClock for &C {
+    fn sleep_async(&self, duration: Duration) -> impl Future<Output = ()> {
+        C::sleep_async(self, duration)
+    }
+}
+
+/// A clock that only moves when told to.
+///
+/// Sleeping advances the clock instead of waiting, so code driven by a
+/// `VirtualClock` runs as fast as the CPU allows.
+#[derive(Default, fmt::Debug)]
+pub struct VirtualClock {
+    now: Cell<Duration>,
+}
+
+impl VirtualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration)
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncClock for VirtualClock {
+    async fn sleep_async(&self, duration: Duration) {
+        self.advance(duration)
+    }
+}
+
+/// The host's monotonic clock.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, fmt::Debug)]
+pub struct StdClock {
+    epoch: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl StdClock {
+    pub fn new() -> Self {
+        StdClock {
+            epoch: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for StdClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for StdClock {
+    fn now(&self) -> Duration {
+        self.epoch.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration)
+    }
+}
+
+/// Sleeps on the tokio timer.
+#[cfg(feature = "tokio")]
+impl AsyncClock for StdClock {
+    async fn sleep_async(&self, duration: Duration) {
+        tokio::time::sleep(duration).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn virtual_sleep_advances() {
+        let clock = VirtualClock::new();
+        clock.sleep(Duration::from_secs(3600));
+        let by_ref: &dyn Clock = &clock;
+        by_ref.sleep(Duration::from_millis(500));
+        assert_eq!(clock.now(), Duration::from_millis(3_600_500));
+    }
+}
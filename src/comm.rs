@@ -0,0 +1,210 @@
+//! Changing the controller's communication settings.
+//!
+//! Writing Id or bAud with the plain setters leaves the driver addressing a
+//! controller that has moved on. [`Syl2381::change_unit_id`] and
+//! [`Syl2381::change_baud_rate`] move the driver along with it, and can check
+//! that the controller answers at the new settings.
+
+use core::fmt;
+
+use crate::clock::DriverClock;
+use crate::regs;
+use crate::transport::Transport;
+use crate::{BaudRate, Context, Error, Operation, Syl2381};
+
+/// How a communication setting change went.
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CommChange {
+    /// Written, but not checked.
+    Unverified,
+
+    /// The controller answered at the new setting.
+    Verified,
+
+    /// The controller still answers at the old setting only; it will switch
+    /// once power cycled.
+    RebootRequired,
+}
+
+impl fmt::Display for CommChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <Self as fmt::Debug>::fmt(self, f)
+    }
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    /// Set the controller's unit ID (Id) and address it by the new ID from
+    /// then on.
+    ///
+    /// With `verify`, reads Id back at the new ID and, if that goes
+    /// unanswered, at the old one. Either way the driver is left on the new
+    /// ID.
+    pub fn change_unit_id(&mut self, val: u8, verify: bool) -> crate::Result<CommChange, UART> {
+        let old = self.unit_id;
+        self.set_unit_id(val)?;
+        self.readdress(val);
+        if !verify {
+            return Ok(CommChange::Unverified);
+        }
+
+        let err = match self.get_unit_id() {
+            Ok(id) if id == val => return Ok(CommChange::Verified),
+            Ok(id) => return Err(mismatch(val as f32, id as f32)),
+            Err(err) => err,
+        };
+        self.readdress(old);
+        let still_old = self.get_unit_id();
+        self.readdress(val);
+        match still_old {
+            Ok(_) => Ok(CommChange::RebootRequired),
+            Err(_) => Err(err),
+        }
+    }
+
+    /// Set the controller's baud rate (bAud), then switch the port over with
+    /// `reconfigure`.
+    ///
+    /// With `verify`, reads bAud back at the new rate and, if that goes
+    /// unanswered, at the old one. Either way the port is left at the new
+    /// rate, even if switching it back to the old one fails.
+    pub fn change_baud_rate(
+        &mut self,
+        val: BaudRate,
+        verify: bool,
+        mut reconfigure: impl FnMut(&mut UART, BaudRate) -> core::result::Result<(), UART::Error>,
+    ) -> crate::Result<CommChange, UART> {
+        let old = self.get_baud_rate()?;
+        self.set_baud_rate(val)?;
+        self.reconfigure(&mut reconfigure, val)?;
+        if !verify {
+            return Ok(CommChange::Unverified);
+        }
+
+        let err = match self.get_baud_rate() {
+            Ok(baud) if baud == val => return Ok(CommChange::Verified),
+            Ok(baud) => return Err(mismatch(val.into(), baud.into())),
+            Err(err) => err,
+        };
+        let still_old = match self.reconfigure(&mut reconfigure, old) {
+            Ok(()) => self.get_baud_rate(),
+            Err(err) => Err(err),
+        };
+        self.reconfigure(&mut reconfigure, val)?;
+        match still_old {
+            Ok(_) => Ok(CommChange::RebootRequired),
+            Err(_) => Err(err),
+        }
+    }
+
+    fn reconfigure(
+        &mut self,
+        reconfigure: &mut impl FnMut(&mut UART, BaudRate) -> core::result::Result<(), UART::Error>,
+        baud: BaudRate,
+    ) -> crate::Result<(), UART> {
+        let ctx = Context::new(Operation::WriteHolding, regs::BAUD);
+        reconfigure(&mut self.port, baud).map_err(|err| Error::SerialError(ctx, err))
+    }
+}
+
+fn mismatch<E>(expected: f32, actual: f32) -> Error<E> {
+    Error::VerificationFailed { expected, actual }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{frame, MockSerial};
+
+    #[test]
+    fn follows_the_new_unit_id() {
+        let mut port = MockSerial::new();
+        port.ack_f32(1, regs::ID.addr);
+        port.respond_f32(7, 7.0);
+        let mut pid = Syl2381::new(1, port);
+
+        assert_eq!(pid.change_unit_id(7, true).unwrap(), CommChange::Verified);
+        assert_eq!(pid.port.tx[13..], frame(&[7, 0x03, 0x20, 0x0E, 0, 2]));
+    }
+
+    /// A controller at `id` that ignores requests for anyone else.
+    struct Addressed {
+        id: u8,
+        to: u8,
+        port: MockSerial,
+    }
+
+    impl Transport for Addressed {
+        type Error = ();
+
+        fn write_frame(&mut self, frame: &[u8]) -> Result<(), ()> {
+            self.to = frame[0];
+            self.port.write_frame(frame)
+        }
+
+        fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), ()> {
+            if self.to != self.id {
+                return Err(());
+            }
+            self.port.read_exact(buf)
+        }
+    }
+
+    #[test]
+    fn reports_a_pending_reboot() {
+        let mut port = MockSerial::new();
+        port.ack_f32(1, regs::ID.addr);
+        port.respond_f32(1, 7.0);
+        let port = Addressed { id: 1, to: 0, port };
+        let mut pid = Syl2381::new(1, port);
+
+        assert_eq!(
+            pid.change_unit_id(7, true).unwrap(),
+            CommChange::RebootRequired
+        );
+        assert!(pid.port.port.rx.is_empty());
+        assert_eq!(pid.unit_id, 7);
+    }
+
+    #[test]
+    fn switches_the_port_rate() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 3.0); // 9600
+        port.ack_f32(1, regs::BAUD.addr);
+        port.respond_f32(1, 1.0); // 2400
+        let mut pid = Syl2381::new(1, port);
+
+        let change = pid.change_baud_rate(BaudRate::Baud2400, true, |port, baud| {
+            port.tx.extend_from_slice(&baud.bps().to_be_bytes());
+            Ok(())
+        });
+        assert_eq!(change.unwrap(), CommChange::Verified);
+        assert_eq!(pid.port.tx[8 + 13..8 + 13 + 4], 2400u32.to_be_bytes());
+    }
+
+    #[test]
+    fn stays_at_the_new_rate_when_switching_back_fails() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 3.0); // 9600
+        port.ack_f32(1, regs::BAUD.addr);
+        let mut pid = Syl2381::new(1, port);
+
+        let mut rates = Vec::new();
+        let change = pid.change_baud_rate(BaudRate::Baud2400, true, |_, baud| {
+            rates.push(baud);
+            match baud {
+                BaudRate::Baud9600 => Err(()),
+                _ => Ok(()),
+            }
+        });
+        assert!(matches!(change, Err(Error::SerialError(..))));
+        assert_eq!(
+            rates,
+            [BaudRate::Baud2400, BaudRate::Baud9600, BaudRate::Baud2400]
+        );
+    }
+}
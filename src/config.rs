@@ -0,0 +1,409 @@
+//! Declarative configuration.
+//!
+//! A [`Config`] lists the static parameters a controller should have; fields
+//! left as `None` are not touched. [`Syl2381::diff`] reports the registers
+//! that differ from it and [`Syl2381::apply`] writes only those, so
+//! commissioning the same controller twice costs no EEPROM writes.
+//!
+//! [`clone_config`] copies one controller's configuration onto another, for
+//! commissioning a batch of identical units, and [`Syl2381::provision`]
+//! applies a configuration to every unit on a multi-drop bus.
+
+use core::fmt;
+
+use crate::clock::{DriverClock, NoClock};
+use crate::limits;
+use crate::regs;
+use crate::transport::Transport;
+use crate::{
+    ControlDirection, DeviceSnapshot, DisplayUnit, Error, Filter, InputType, OutputMode,
+    OutputType, Percent, Seconds, Syl2381, TempRaw,
+};
+
+/// Number of parameters a [`Config`] covers.
+pub const PARAMS: usize = 17;
+
+/// Desired values for the static parameters.
+///
+/// The unit ID and baud rate aren't included, since changing them cuts off
+/// whatever comes after.
+#[derive(Clone, Copy, Default, fmt::Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Config {
+    pub input_sensor_type: Option<InputType>,
+    pub display_unit: Option<DisplayUnit>,
+    pub output_mode: Option<OutputMode>,
+    pub output_type: Option<OutputType>,
+    pub control_direction: Option<ControlDirection>,
+    pub hysteresis: Option<TempRaw>,
+    pub input_offset: Option<TempRaw>,
+    pub p: Option<f32>,
+    pub i: Option<Seconds>,
+    pub d: Option<Seconds>,
+    pub bb: Option<TempRaw>,
+    pub souf: Option<Percent>,
+    pub control_cycle: Option<Seconds>,
+    pub filter: Option<Filter>,
+    pub sv: Option<TempRaw>,
+    pub j1_on_temp: Option<TempRaw>,
+    pub j1_off_temp: Option<TempRaw>,
+}
+
+/// A register that differed from the [`Config`].
+#[derive(Clone, Copy, fmt::Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Change {
+    pub reg: regs::Reg,
+    pub from: f32,
+    pub to: f32,
+}
+
+/// `0xADDR: from -> to`
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:04X}: {} -> {}", self.reg.addr, self.from, self.to)
+    }
+}
+
+pub type Changes = heapless::Vec<Change, PARAMS>;
+
+/// Every static parameter in the snapshot.
+impl From<&DeviceSnapshot> for Config {
+    fn from(snap: &DeviceSnapshot) -> Self {
+        Config {
+            input_sensor_type: Some(snap.input_sensor_type),
+            display_unit: Some(snap.display_unit),
+            output_mode: Some(snap.output_mode),
+            output_type: Some(snap.output_type),
+            control_direction: Some(snap.control_direction),
+            hysteresis: Some(snap.hysteresis),
+            input_offset: Some(snap.input_offset),
+            p: Some(snap.p),
+            i: Some(snap.i),
+            d: Some(snap.d),
+            bb: Some(snap.bb),
+            souf: Some(snap.souf),
+            control_cycle: Some(snap.control_cycle),
+            filter: Some(snap.filter),
+            sv: Some(snap.sv),
+            j1_on_temp: Some(snap.j1_on_temp),
+            j1_off_temp: Some(snap.j1_off_temp),
+        }
+    }
+}
+
+impl Config {
+    /// The registers set, in the order they're written.
+    ///
+    /// The sensor type and display unit come first, since they change how
+    /// the temperatures after them are interpreted.
+    fn entries(&self) -> impl Iterator<Item = (regs::Reg, f32)> {
+        let secs = |val: Option<Seconds>| val.map(|Seconds(val)| val as f32);
+        let temp = |val: Option<TempRaw>| val.map(|TempRaw(val)| val);
+        [
+            (regs::INTY, self.input_sensor_type.map(f32::from)),
+            (regs::CORF, self.display_unit.map(f32::from)),
+            (regs::OUTY, self.output_mode.map(f32::from)),
+            (regs::COTY, self.output_type.map(f32::from)),
+            (regs::RD, self.control_direction.map(f32::from)),
+            (regs::HY, temp(self.hysteresis)),
+            (regs::PSB, temp(self.input_offset)),
+            (regs::P, self.p),
+            (regs::I, secs(self.i)),
+            (regs::D, secs(self.d)),
+            (regs::BB, temp(self.bb)),
+            (regs::SOUF, self.souf.map(|Percent(val)| val)),
+            (regs::OT, secs(self.control_cycle)),
+            (regs::FILT, self.filter.map(f32::from)),
+            (regs::SV, temp(self.sv)),
+            (regs::AH1, temp(self.j1_on_temp)),
+            (regs::AL1, temp(self.j1_off_temp)),
+        ]
+        .into_iter()
+        .filter_map(|(reg, val)| Some((reg, val?)))
+    }
+
+    /// The first value outside the range its setter accepts.
+    fn invalid(&self) -> Option<f32> {
+        let floats = [
+            (self.hysteresis.map(|t| t.0), limits::HY),
+            (self.input_offset.map(|t| t.0), limits::PSB),
+            (self.p, limits::P),
+            (self.bb.map(|t| t.0), limits::BB),
+            (self.souf.map(|p| p.0), limits::SOUF),
+            (self.sv.map(|t| t.0), limits::SV),
+            (self.j1_on_temp.map(|t| t.0), limits::AH1),
+            (self.j1_off_temp.map(|t| t.0), limits::AL1),
+        ];
+        for (val, range) in floats {
+            match val {
+                Some(val) if !range.contains(&val) => return Some(val),
+                _ => {}
+            }
+        }
+
+        let secs = [
+            (self.i, limits::I),
+            (self.d, limits::D),
+            (self.control_cycle, limits::OT),
+        ];
+        for (val, range) in secs {
+            match val {
+                Some(Seconds(val)) if !range.contains(&val) => return Some(val as f32),
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    /// Read every static parameter.
+    pub fn read_config(&mut self) -> crate::Result<Config, UART> {
+        Ok(Config {
+            input_sensor_type: Some(self.get_input_sensor_type()?),
+            display_unit: Some(self.get_display_unit()?),
+            output_mode: Some(self.get_output_mode()?),
+            output_type: Some(self.get_output_type()?),
+            control_direction: Some(self.get_control_direction()?),
+            hysteresis: Some(self.get_hysteresis()?),
+            input_offset: Some(self.get_input_offset()?),
+            p: Some(self.get_p()?),
+            i: Some(self.get_i()?),
+            d: Some(self.get_d()?),
+            bb: Some(self.get_bb()?),
+            souf: Some(self.get_souf()?),
+            control_cycle: Some(self.get_control_cycle()?),
+            filter: Some(self.get_filter()?),
+            sv: Some(self.get_sv()?),
+            j1_on_temp: Some(self.get_j1_on_temp()?),
+            j1_off_temp: Some(self.get_j1_off_temp()?),
+        })
+    }
+
+    /// Read the parameters set in `cfg` and report those that differ.
+    pub fn diff(&mut self, cfg: &Config) -> crate::Result<Changes, UART> {
+        let mut changes = Changes::new();
+        for (reg, to) in cfg.entries() {
+            let from = self.get_holding(reg)?;
+            if from != to {
+                // One entry per field, so this can't overflow.
+                let _ = changes.push(Change { reg, from, to });
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Apply `cfg` to each unit in `ids` in turn, through this driver's port.
+    ///
+    /// The units are only contacted as the returned iterator is advanced, and
+    /// one failing doesn't stop the rest. This driver keeps its own unit ID
+    /// for everything else, but starts over with nothing cached.
+    pub fn provision<'a>(
+        &'a mut self,
+        cfg: &'a Config,
+        ids: &'a [u8],
+    ) -> Provision<'a, UART, CLOCK> {
+        Provision {
+            pid: self,
+            cfg,
+            ids,
+        }
+    }
+
+    /// Write the parameters that differ from `cfg`, returning what changed.
+    ///
+    /// Every value is range checked before anything is read or written. If a
+    /// write fails part way, calling `apply` again picks up where it stopped.
+    pub fn apply(&mut self, cfg: &Config) -> crate::Result<Changes, UART> {
+        if let Some(val) = cfg.invalid() {
+            return Err(Error::UnexpectedValue(val));
+        }
+        let changes = self.diff(cfg)?;
+        for change in &changes {
+            self.set_holding(change.reg, change.to)?;
+        }
+        Ok(changes)
+    }
+}
+
+/// Per-unit results of [`Syl2381::provision`], as `(unit ID, result)`.
+pub struct Provision<'a, UART, CLOCK = NoClock> {
+    pid: &'a mut Syl2381<UART, CLOCK>,
+    cfg: &'a Config,
+    ids: &'a [u8],
+}
+
+impl<UART, CLOCK> Iterator for Provision<'_, UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    type Item = (u8, crate::Result<Changes, UART>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&id, rest) = self.ids.split_first()?;
+        self.ids = rest;
+
+        let home = self.pid.unit_id;
+        self.pid.readdress(id);
+        let result = self.pid.apply(self.cfg);
+        self.pid.readdress(home);
+        Some((id, result))
+    }
+}
+
+/// Copy every static parameter from `src` to `dst`, writing only those that
+/// differ. The unit ID and baud rate stay as they are.
+///
+/// Both controllers need the same kind of transport; otherwise pass
+/// [`Syl2381::read_config`] to [`Syl2381::apply`] yourself.
+pub fn clone_config<UART, SRC, DST>(
+    src: &mut Syl2381<UART, SRC>,
+    dst: &mut Syl2381<UART, DST>,
+) -> crate::Result<Changes, UART>
+where
+    UART: Transport,
+    SRC: DriverClock,
+    DST: DriverClock,
+{
+    let cfg = src.read_config()?;
+    dst.apply(&cfg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockSerial;
+
+    fn config() -> Config {
+        Config {
+            input_sensor_type: Some(InputType::K),
+            p: Some(10.0),
+            i: Some(Seconds(120)),
+            sv: Some(TempRaw(65.0)),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn writes_only_what_differs() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 6.0); // INTY: K
+        port.respond_f32(1, 5.0); // P
+        port.respond_f32(1, 120.0); // I
+        port.respond_f32(1, 60.0); // SV
+        port.ack_f32(1, regs::P.addr);
+        port.ack_f32(1, regs::SV.addr);
+        let mut pid = Syl2381::new(1, port);
+
+        let changes = pid.apply(&config()).unwrap();
+        assert!(pid.port.rx.is_empty());
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].to_string(), "0x1000: 5 -> 10");
+        assert_eq!(changes[1].to_string(), "0x0000: 60 -> 65");
+    }
+
+    #[test]
+    fn diff_only_reads() {
+        let mut port = MockSerial::new();
+        for val in [6.0, 10.0, 120.0, 65.0] {
+            port.respond_f32(1, val);
+        }
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(pid.diff(&config()).unwrap().is_empty());
+        assert_eq!(pid.port.tx.len(), 4 * 8);
+    }
+
+    #[test]
+    fn clones_between_controllers() {
+        // INTY ..= AL1, in write order
+        let values = [
+            6.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 10.0, 120.0, 30.0, 20.0, 0.2, 2.0, 1.0, 107.0,
+            115.0, 110.0,
+        ];
+        let mut port = MockSerial::new();
+        for val in values {
+            port.respond_f32(1, val);
+        }
+        let mut src = Syl2381::new(1, port);
+
+        let mut port = MockSerial::new();
+        for val in values {
+            port.respond_f32(2, if val == 107.0 { 100.0 } else { val });
+        }
+        port.ack_f32(2, regs::SV.addr);
+        let mut dst = Syl2381::new(2, port);
+
+        let changes = clone_config(&mut src, &mut dst).unwrap();
+        assert!(src.port.rx.is_empty() && dst.port.rx.is_empty());
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].to_string(), "0x0000: 100 -> 107");
+    }
+
+    #[test]
+    fn provisions_every_unit() {
+        let mut port = MockSerial::new();
+        for val in [6.0, 10.0, 120.0, 65.0] {
+            port.respond_f32(3, val);
+        }
+        port.respond_f32(4, 6.0);
+        // unit 4 stops answering
+        let mut pid = Syl2381::new(1, port);
+
+        let cfg = config();
+        let results: Vec<_> = pid.provision(&cfg, &[3, 4]).collect();
+        assert!(matches!(results[0], (3, Ok(ref changes)) if changes.is_empty()));
+        assert!(matches!(results[1], (4, Err(Error::SerialError(..)))));
+
+        pid.port.tx.clear();
+        pid.port.respond_f32(1, 65.0);
+        assert!(pid.get_sv().is_ok());
+        assert_eq!(pid.port.tx[0], 1);
+    }
+
+    #[test]
+    fn provisions_each_unit_afresh() {
+        let mut port = MockSerial::new();
+        for unit in [3, 4] {
+            for val in [6.0, 10.0, 60.0, 65.0] {
+                port.respond_f32(unit, val);
+            }
+            port.ack_f32(unit, regs::I.addr);
+        }
+        let mut pid = Syl2381::new(1, port).with_clock(crate::clock::VirtualClock::new());
+        pid.set_write_guard(Some(crate::WriteGuard {
+            min_interval: core::time::Duration::from_secs(60),
+        }));
+
+        // What unit 3 was told says nothing about unit 4.
+        let cfg = config();
+        let results: Vec<_> = pid.provision(&cfg, &[3, 4]).collect();
+        for (unit, result) in &results {
+            assert!(
+                matches!(result, Ok(changes) if changes.len() == 1),
+                "unit {}",
+                unit
+            );
+        }
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn rejects_out_of_range_before_reading() {
+        let mut pid = Syl2381::new(1, MockSerial::new());
+        let cfg = Config {
+            control_cycle: Some(Seconds(501)),
+            ..config()
+        };
+
+        assert!(matches!(pid.apply(&cfg), Err(Error::UnexpectedValue(v)) if v == 501.0));
+        assert!(pid.port.tx.is_empty());
+    }
+}
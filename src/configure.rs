@@ -0,0 +1,467 @@
+//! Multi-register configuration helpers.
+//!
+//! Some setups only work when several registers agree with each other. These
+//! helpers write them together and read them back, so a half-applied
+//! configuration is reported instead of silently left on the controller.
+
+use core::fmt;
+
+use crate::clock::DriverClock;
+use crate::limits;
+use crate::regs;
+use crate::transport::Transport;
+use crate::{DisplayUnit, Error, OutputMode, OutputType, Syl2381, TempRaw};
+
+/// What the J1 relay alarms on.
+///
+/// J1 energizes when PV reaches `on` and releases once it gets back to `off`.
+/// With `on` above `off` that's a high alarm, with `on` below `off` a low
+/// alarm; the gap between them is the alarm's hysteresis.
+#[derive(Clone, Copy, fmt::Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AlarmConfig {
+    /// Fixed temperatures, with PID control on the SSR port (OUTY 0).
+    Absolute { on: TempRaw, off: TempRaw },
+
+    /// Offsets from SV, so the alarm follows the set value, with PID control
+    /// on the SSR port (OUTY 1).
+    Deviation { on: TempRaw, off: TempRaw },
+
+    /// Fixed temperatures with the SSR port disabled, for a controller used
+    /// only as an alarm (OUTY 4).
+    AlarmOnly { on: TempRaw, off: TempRaw },
+}
+
+impl AlarmConfig {
+    /// The output mode (OUTY) that makes J1 this kind of alarm.
+    pub fn output_mode(self) -> OutputMode {
+        match self {
+            AlarmConfig::Absolute { .. } => {
+                OutputMode::J1RelayAsAbsoluteAlarmOutputSsrPortAsPidControlOutput
+            }
+            AlarmConfig::Deviation { .. } => {
+                OutputMode::J1RelayAsDerivationAlarmOutputSsrPortAsPidControlOutput
+            }
+            AlarmConfig::AlarmOnly { .. } => {
+                OutputMode::J1RelayAsAbsoluteAlarmOutputSsrPortDisabled
+            }
+        }
+    }
+
+    /// The values for AH1 and AL1.
+    pub fn thresholds(self) -> (TempRaw, TempRaw) {
+        match self {
+            AlarmConfig::Absolute { on, off }
+            | AlarmConfig::Deviation { on, off }
+            | AlarmConfig::AlarmOnly { on, off } => (on, off),
+        }
+    }
+}
+
+/// PID control on the main output, with the J1 relay as an absolute high
+/// alarm (e.g. a buzzer or fan).
+///
+/// J1 energizes when PV rises to `alarm_on` and releases once PV falls back to
+/// `alarm_off`.
+#[derive(Clone, Copy, fmt::Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SsrWithJ1Alarm {
+    /// Main output type (COTY).
+    pub output_type: OutputType,
+
+    /// J1 ON temperature (AH1).
+    pub alarm_on: TempRaw,
+
+    /// J1 OFF temperature (AL1); must be below `alarm_on`.
+    pub alarm_off: TempRaw,
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    /// Configure PID control on the main output with a J1 high alarm.
+    ///
+    /// Sets up an [`AlarmConfig::Absolute`] alarm with
+    /// [`Syl2381::configure_alarm`], checked the same way, then writes COTY
+    /// and reads it back. `alarm_off` must also be below `alarm_on`.
+    pub fn configure_ssr_with_j1_alarm(&mut self, cfg: &SsrWithJ1Alarm) -> crate::Result<(), UART> {
+        if cfg.alarm_off.0 >= cfg.alarm_on.0 || cfg.alarm_off.0.is_nan() {
+            return Err(Error::UnexpectedValue(cfg.alarm_off.0));
+        }
+
+        self.configure_alarm(AlarmConfig::Absolute {
+            on: cfg.alarm_on,
+            off: cfg.alarm_off,
+        })?;
+        self.set_output_type(cfg.output_type)?;
+        self.verify_holding(regs::COTY, cfg.output_type.into())
+    }
+
+    /// Set up the J1 alarm: OUTY, AH1 and AL1.
+    ///
+    /// Both thresholds are checked before anything is written: they must
+    /// differ, be within AH1 and AL1's limits and, for absolute alarms, be
+    /// temperatures the sensor can reach. All three registers are read back,
+    /// returning [`Error::VerificationFailed`] if any didn't take.
+    pub fn configure_alarm(&mut self, cfg: AlarmConfig) -> crate::Result<(), UART> {
+        let (TempRaw(on), TempRaw(off)) = cfg.thresholds();
+        if on == off || on.is_nan() {
+            return Err(Error::UnexpectedValue(on));
+        }
+        for (val, range) in [(on, limits::AH1), (off, limits::AL1)] {
+            if !range.contains(&val) {
+                return Err(Error::UnexpectedValue(val));
+            }
+            // Deviations are offsets, not temperatures.
+            if !matches!(cfg, AlarmConfig::Deviation { .. }) {
+                self.check_sensor_range(val)?;
+            }
+        }
+
+        let mode = cfg.output_mode();
+        self.set_output_mode(mode)?;
+        self.set_holding(regs::AL1, off)?;
+        self.set_holding(regs::AH1, on)?;
+
+        self.verify_holding(regs::OUTY, mode.into())?;
+        self.verify_holding(regs::AL1, off)?;
+        self.verify_holding(regs::AH1, on)?;
+
+        Ok(())
+    }
+
+    /// Switch to on/off control on the J1 relay (OUTY 3), holding PV at
+    /// `setpoint` give or take `hysteresis`.
+    ///
+    /// The relay switches at SV and again once PV has moved `hysteresis` (Hy)
+    /// past it, in the direction set by rd. Hy must be above zero, since a
+    /// relay with no hysteresis chatters around SV and soon wears out. SV is
+    /// checked as by [`Syl2381::set_sv`]. Writes OUTY, Hy and SV, then reads all
+    /// three back, returning [`Error::VerificationFailed`] if any didn't take.
+    pub fn configure_onoff_control(
+        &mut self,
+        setpoint: TempRaw,
+        hysteresis: TempRaw,
+    ) -> crate::Result<(), UART> {
+        let (TempRaw(sv), TempRaw(hy)) = (setpoint, hysteresis);
+        if hy <= 0.0 || !limits::HY.contains(&hy) {
+            return Err(Error::UnexpectedValue(hy));
+        }
+        if !limits::SV.contains(&sv) {
+            return Err(Error::UnexpectedValue(sv));
+        }
+        self.check_sensor_range(sv)?;
+
+        let mode = OutputMode::J1RelayAsOnOffControlOutputSsrPortDisabled;
+        self.set_output_mode(mode)?;
+        self.set_holding(regs::HY, hy)?;
+        self.set_holding(regs::SV, sv)?;
+
+        self.verify_holding(regs::OUTY, mode.into())?;
+        self.verify_holding(regs::HY, hy)?;
+        self.verify_holding(regs::SV, sv)?;
+
+        Ok(())
+    }
+
+    /// Switch the display unit (CorF) without changing what the controller
+    /// regulates to.
+    ///
+    /// CorF on its own only changes how numbers are interpreted, so 65 °C
+    /// would become 65 °F. This also converts SV, AH1, AL1 and PSb into the
+    /// new unit, rounded to the tenths the display shows. PSb is an offset,
+    /// and so are AH1 and AL1 for a deviation alarm (OUTY 1): those scale but
+    /// don't shift. Every converted value is checked against its limits and
+    /// the sensor's range before anything is written; CorF is written first,
+    /// then the converted values. Hy and BB are left as they are.
+    pub fn set_display_unit_converting(&mut self, unit: DisplayUnit) -> crate::Result<(), UART> {
+        let from = self.get_display_unit()?;
+        if from == unit {
+            return Ok(());
+        }
+
+        let deviation = matches!(
+            self.get_output_mode()?,
+            OutputMode::J1RelayAsDerivationAlarmOutputSsrPortAsPidControlOutput
+        );
+        let temp = |val: f32| tenths(unit.from_celsius(from.to_celsius(val)));
+        let offset = |val: f32| {
+            tenths(match unit {
+                DisplayUnit::Fahrenheit => val * 9.0 / 5.0,
+                DisplayUnit::Celsius => val * 5.0 / 9.0,
+            })
+        };
+        let alarm = |val: f32| if deviation { offset(val) } else { temp(val) };
+        let sv = temp(self.get_sv()?.0);
+        let ah1 = alarm(self.get_j1_on_temp()?.0);
+        let al1 = alarm(self.get_j1_off_temp()?.0);
+        let psb = offset(self.get_input_offset()?.0);
+
+        // Register, value, limits, and whether it's a temperature.
+        let vals = [
+            (regs::SV, sv, limits::SV, true),
+            (regs::AH1, ah1, limits::AH1, !deviation),
+            (regs::AL1, al1, limits::AL1, !deviation),
+            (regs::PSB, psb, limits::PSB, false),
+        ];
+        for (_, val, range, is_temp) in &vals {
+            if !range.contains(val) {
+                return Err(Error::UnexpectedValue(*val));
+            }
+            if *is_temp {
+                self.check_sensor_range_in(*val, unit)?;
+            }
+        }
+
+        self.set_display_unit(unit)?;
+        for (reg, val, ..) in vals {
+            self.set_holding(reg, val)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `val` rounded to the nearest tenth.
+fn tenths(val: f32) -> f32 {
+    let scaled = val * 10.0;
+    let rounded = if scaled < 0.0 {
+        scaled - 0.5
+    } else {
+        scaled + 0.5
+    };
+    (rounded as i32) as f32 / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockSerial;
+
+    #[test]
+    fn ssr_with_j1_alarm_writes_and_verifies() {
+        let mut port = MockSerial::new();
+        port.ack_f32(1, regs::OUTY.addr);
+        port.ack_f32(1, regs::AL1.addr);
+        port.ack_f32(1, regs::AH1.addr);
+        port.respond_f32(1, 0.0);
+        port.respond_f32(1, 95.0);
+        port.respond_f32(1, 100.0);
+        port.ack_f32(1, regs::COTY.addr);
+        port.respond_f32(1, 0.0);
+        let mut pid = Syl2381::new(1, port);
+        pid.live
+            .assume_sensor(crate::InputType::K, crate::DisplayUnit::Celsius);
+
+        let cfg = SsrWithJ1Alarm {
+            output_type: OutputType::SSR,
+            alarm_on: TempRaw(100.0),
+            alarm_off: TempRaw(95.0),
+        };
+        assert!(pid.configure_ssr_with_j1_alarm(&cfg).is_ok());
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn ssr_with_j1_alarm_reports_mismatch() {
+        let mut port = MockSerial::new();
+        port.ack_f32(1, regs::OUTY.addr);
+        port.ack_f32(1, regs::AL1.addr);
+        port.ack_f32(1, regs::AH1.addr);
+        // OUTY didn't take
+        port.respond_f32(1, 2.0);
+        let mut pid = Syl2381::new(1, port);
+        pid.live
+            .assume_sensor(crate::InputType::K, crate::DisplayUnit::Celsius);
+
+        let cfg = SsrWithJ1Alarm {
+            output_type: OutputType::SSR,
+            alarm_on: TempRaw(100.0),
+            alarm_off: TempRaw(95.0),
+        };
+        assert!(matches!(
+            pid.configure_ssr_with_j1_alarm(&cfg),
+            Err(Error::VerificationFailed { expected, actual }) if expected == 0.0 && actual == 2.0
+        ));
+    }
+
+    #[test]
+    fn ssr_with_j1_alarm_rejects_inverted_band() {
+        let mut pid = Syl2381::new(1, MockSerial::new());
+        let cfg = SsrWithJ1Alarm {
+            output_type: OutputType::SSR,
+            alarm_on: TempRaw(95.0),
+            alarm_off: TempRaw(100.0),
+        };
+        assert!(matches!(
+            pid.configure_ssr_with_j1_alarm(&cfg),
+            Err(Error::UnexpectedValue(_))
+        ));
+        assert!(pid.port.tx.is_empty());
+    }
+
+    #[test]
+    fn alarm_sets_mode_and_thresholds() {
+        let mut port = MockSerial::new();
+        port.ack_f32(1, regs::OUTY.addr);
+        port.ack_f32(1, regs::AL1.addr);
+        port.ack_f32(1, regs::AH1.addr);
+        port.respond_f32(1, 1.0);
+        port.respond_f32(1, -5.0);
+        port.respond_f32(1, -10.0);
+        let mut pid = Syl2381::new(1, port);
+        pid.live.input_type = Some(crate::InputType::B);
+
+        // Low alarm 10° under SV, clearing at 5° under.
+        let cfg = AlarmConfig::Deviation {
+            on: TempRaw(-10.0),
+            off: TempRaw(-5.0),
+        };
+        assert!(pid.configure_alarm(cfg).is_ok());
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn alarm_rejects_equal_thresholds() {
+        let mut pid = Syl2381::new(1, MockSerial::new());
+        let cfg = AlarmConfig::Absolute {
+            on: TempRaw(100.0),
+            off: TempRaw(100.0),
+        };
+        assert!(matches!(
+            pid.configure_alarm(cfg),
+            Err(Error::UnexpectedValue(_))
+        ));
+        assert!(pid.port.tx.is_empty());
+    }
+
+    #[test]
+    fn onoff_control_writes_and_verifies() {
+        let mut port = MockSerial::new();
+        port.ack_f32(1, regs::OUTY.addr);
+        port.ack_f32(1, regs::HY.addr);
+        port.ack_f32(1, regs::SV.addr);
+        port.respond_f32(1, 3.0);
+        port.respond_f32(1, 2.0);
+        port.respond_f32(1, 4.0);
+        let mut pid = Syl2381::new(1, port);
+        pid.live
+            .assume_sensor(crate::InputType::K, crate::DisplayUnit::Celsius);
+
+        assert!(pid
+            .configure_onoff_control(TempRaw(4.0), TempRaw(2.0))
+            .is_ok());
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn onoff_control_needs_hysteresis() {
+        let mut pid = Syl2381::new(1, MockSerial::new());
+        assert!(matches!(
+            pid.configure_onoff_control(TempRaw(4.0), TempRaw(0.0)),
+            Err(Error::UnexpectedValue(v)) if v == 0.0
+        ));
+        assert!(pid.port.tx.is_empty());
+    }
+
+    #[test]
+    fn display_unit_change_converts_temperatures() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 0.0); // CorF: °C
+        port.respond_f32(1, 0.0); // OUTY: absolute alarm
+        port.respond_f32(1, 65.0); // SV
+        port.respond_f32(1, 100.0); // AH1
+        port.respond_f32(1, 95.0); // AL1
+        port.respond_f32(1, -0.5); // PSb
+        port.respond_f32(1, 6.0); // INTY: K
+        port.ack_f32(1, regs::CORF.addr);
+        port.ack_f32(1, regs::SV.addr);
+        port.ack_f32(1, regs::AH1.addr);
+        port.ack_f32(1, regs::AL1.addr);
+        port.ack_f32(1, regs::PSB.addr);
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(pid
+            .set_display_unit_converting(DisplayUnit::Fahrenheit)
+            .is_ok());
+        assert!(pid.port.rx.is_empty());
+
+        // The last five requests are the writes; check the values sent.
+        let writes: Vec<f32> = pid.port.tx[7 * 8..]
+            .chunks(13)
+            .map(|req| {
+                crate::values_to_f32(
+                    u16::from_be_bytes([req[7], req[8]]),
+                    u16::from_be_bytes([req[9], req[10]]),
+                )
+            })
+            .collect();
+        assert_eq!(writes, [1.0, 149.0, 212.0, 203.0, -0.9]);
+    }
+
+    #[test]
+    fn display_unit_change_scales_deviations() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 1.0); // CorF: °F
+        port.respond_f32(1, 1.0); // OUTY: deviation alarm
+        port.respond_f32(1, 150.0); // SV
+        port.respond_f32(1, 9.0); // AH1
+        port.respond_f32(1, -5.0); // AL1
+        port.respond_f32(1, 1.0); // PSb
+        port.respond_f32(1, 6.0); // INTY: K
+        for reg in [regs::CORF, regs::SV, regs::AH1, regs::AL1, regs::PSB] {
+            port.ack_f32(1, reg.addr);
+        }
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(pid
+            .set_display_unit_converting(DisplayUnit::Celsius)
+            .is_ok());
+        assert!(pid.port.rx.is_empty());
+        let writes: Vec<f32> = pid.port.tx[7 * 8..]
+            .chunks(13)
+            .map(|req| {
+                crate::values_to_f32(
+                    u16::from_be_bytes([req[7], req[8]]),
+                    u16::from_be_bytes([req[9], req[10]]),
+                )
+            })
+            .collect();
+        assert_eq!(writes, [0.0, 65.6, 5.0, -2.8, 0.6]);
+    }
+
+    #[test]
+    fn display_unit_change_checks_before_writing() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 0.0); // CorF: °C
+        port.respond_f32(1, 0.0); // OUTY: absolute alarm
+        port.respond_f32(1, 65.0); // SV
+        port.respond_f32(1, 1350.0); // AH1: 2462 °F, past what K reaches
+        port.respond_f32(1, 95.0); // AL1
+        port.respond_f32(1, 0.0); // PSb
+        let mut pid = Syl2381::new(1, port);
+        pid.live.input_type = Some(crate::InputType::K);
+
+        assert!(matches!(
+            pid.set_display_unit_converting(DisplayUnit::Fahrenheit),
+            Err(Error::OutOfRange { .. })
+        ));
+        assert!(pid.port.rx.is_empty());
+        assert_eq!(pid.port.tx.len(), 6 * 8);
+    }
+
+    #[test]
+    fn display_unit_change_is_a_noop_when_unchanged() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 1.0);
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(pid
+            .set_display_unit_converting(DisplayUnit::Fahrenheit)
+            .is_ok());
+        assert_eq!(pid.port.tx.len(), 8);
+    }
+}
@@ -0,0 +1,184 @@
+//! CSV export of logged readings.
+//!
+//! Most runs end up graphed in a spreadsheet. [`CsvWriter`] writes
+//! [`Record`]s and [`Sample`]s as rows of plain numbers: time in seconds,
+//! temperatures in the controller's display unit, OUT in percent and the J1
+//! alarm as 0 or 1. Values a sample didn't read are left empty.
+//!
+//! Rows from several controllers can share a file by giving each writer the
+//! controller's [`Labels`] with [`CsvWriter::with_labels`].
+
+use std::io;
+
+use crate::logger::Record;
+use crate::sample::Sample;
+use crate::Labels;
+
+/// The first line written.
+pub const HEADER: &str = "time_s,pv,sv,out_pct,alarm";
+
+/// The first line written with [`CsvWriter::with_labels`].
+pub const LABELED_HEADER: &str = "time_s,pv,sv,out_pct,alarm,name,location,probe";
+
+pub struct CsvWriter<W> {
+    out: W,
+    started: bool,
+    labels: Option<Labels>,
+}
+
+impl<W> CsvWriter<W>
+where
+    W: io::Write,
+{
+    /// Write rows to `out`, starting with [`HEADER`].
+    pub fn new(out: W) -> Self {
+        CsvWriter {
+            out,
+            started: false,
+            labels: None,
+        }
+    }
+
+    /// End every row with `labels`' name, location and probe, e.g. a
+    /// [`Logger::labels`](crate::logger::Logger::labels), under
+    /// [`LABELED_HEADER`].
+    pub fn with_labels(mut self, labels: Labels) -> Self {
+        self.labels = Some(labels);
+        self
+    }
+
+    pub fn record(&mut self, record: &Record) -> io::Result<()> {
+        self.row(
+            record.at.as_secs_f64(),
+            record.pv.0,
+            Some(record.sv.0),
+            Some(record.out.percent()),
+            Some(record.status.alarm1()),
+        )
+    }
+
+    /// Write each of `records`, e.g. [`Logger::records`](crate::logger::Logger::records).
+    pub fn records<'a>(&mut self, records: impl IntoIterator<Item = &'a Record>) -> io::Result<()> {
+        records.into_iter().try_for_each(|r| self.record(r))
+    }
+
+    pub fn sample(&mut self, sample: &Sample) -> io::Result<()> {
+        self.row(
+            sample.at.as_secs_f64(),
+            sample.pv.0,
+            None,
+            sample.out.map(|out| out.percent()),
+            sample.status.map(|status| status.alarm1()),
+        )
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    fn row(
+        &mut self,
+        secs: f64,
+        pv: f32,
+        sv: Option<f32>,
+        out: Option<f32>,
+        alarm: Option<bool>,
+    ) -> io::Result<()> {
+        if !self.started {
+            match self.labels {
+                Some(_) => writeln!(self.out, "{LABELED_HEADER}")?,
+                None => writeln!(self.out, "{HEADER}")?,
+            }
+            self.started = true;
+        }
+
+        write!(self.out, "{secs:.3},{pv},")?;
+        if let Some(sv) = sv {
+            write!(self.out, "{sv}")?;
+        }
+        write!(self.out, ",")?;
+        if let Some(out) = out {
+            write!(self.out, "{out}")?;
+        }
+        write!(self.out, ",")?;
+        if let Some(alarm) = alarm {
+            write!(self.out, "{}", alarm as u8)?;
+        }
+        if let Some(labels) = &self.labels {
+            for label in [&labels.name, &labels.location, &labels.probe] {
+                write!(self.out, ",")?;
+                write_field(&mut self.out, label)?;
+            }
+        }
+        writeln!(self.out)
+    }
+}
+
+/// Write `val`, quoted if it holds a comma, quote or line break.
+pub(crate) fn write_field(out: &mut impl io::Write, val: &str) -> io::Result<()> {
+    if !val.contains([',', '"', '\n', '\r']) {
+        return out.write_all(val.as_bytes());
+    }
+    write!(out, "\"{}\"", val.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample::Provenance;
+    use crate::{Percent, Status, TempRaw};
+    use core::time::Duration;
+
+    #[test]
+    fn writes_records_and_samples() {
+        let mut csv = CsvWriter::new(Vec::new());
+        let record = Record {
+            at: Duration::from_millis(1500),
+            pv: TempRaw(64.5),
+            out: Percent(0.25),
+            sv: TempRaw(65.0),
+            status: Status(0b10_0000),
+            provenance: Provenance::default(),
+        };
+        csv.records([&record]).unwrap();
+        csv.sample(&Sample {
+            at: Duration::from_secs(2),
+            pv: TempRaw(64.75),
+            out: None,
+            status: None,
+            provenance: Provenance::default(),
+        })
+        .unwrap();
+
+        let text = String::from_utf8(csv.into_inner()).unwrap();
+        assert_eq!(
+            text,
+            "time_s,pv,sv,out_pct,alarm\n1.500,64.5,65,25,1\n2.000,64.75,,,\n"
+        );
+    }
+
+    #[test]
+    fn ends_rows_with_labels() {
+        let labels = Labels::new("Kiln \"2\", east").with_probe("K");
+        let mut csv = CsvWriter::new(Vec::new()).with_labels(labels);
+        csv.sample(&Sample {
+            at: Duration::from_secs(2),
+            pv: TempRaw(64.75),
+            out: None,
+            status: None,
+            provenance: Provenance::default(),
+        })
+        .unwrap();
+
+        let text = String::from_utf8(csv.into_inner()).unwrap();
+        assert_eq!(
+            text,
+            "time_s,pv,sv,out_pct,alarm,name,location,probe\n\
+             2.000,64.75,,,,\"Kiln \"\"2\"\", east\",,K\n"
+        );
+    }
+}
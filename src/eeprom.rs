@@ -0,0 +1,179 @@
+//! Sparing the controller's EEPROM.
+//!
+//! Everything outside the process bank (PV, OUT, CV) is stored in EEPROM,
+//! which wears out after a limited number of writes. A control loop that sets
+//! SV every second gets there quickly. With a [`WriteGuard`] the driver skips
+//! writes of the value a register already holds, and refuses to write the same
+//! register again within `min_interval`.
+
+use core::fmt;
+use core::time::Duration;
+
+use crate::clock::{Clock, DriverClock};
+use crate::regs::{self, Bank};
+use crate::transport::Transport;
+use crate::{Context, Error, Syl2381};
+
+/// Limits on EEPROM writes.
+///
+/// Time between writes is measured on the driver's clock; see
+/// [`Syl2381::with_clock`].
+#[derive(Clone, Copy, fmt::Debug)]
+pub struct WriteGuard {
+    /// Shortest time allowed between two writes to one register.
+    pub min_interval: Duration,
+}
+
+/// What the guard knows about each EEPROM register.
+#[derive(Default)]
+pub(crate) struct Writes {
+    guard: Option<WriteGuard>,
+
+    /// Last known value, and when it was last written.
+    regs: heapless::LinearMap<u16, (f32, Option<Duration>), 24>,
+}
+
+impl Writes {
+    /// Remember a value read from `reg`.
+    pub(crate) fn read(&mut self, reg: regs::Reg, val: f32) {
+        if self.guard.is_none() || !in_eeprom(reg) {
+            return;
+        }
+        let written = self.regs.get(&reg.addr).and_then(|&(_, at)| at);
+        let _ = self.regs.insert(reg.addr, (val, written));
+    }
+
+    /// Forget every known value, e.g. after a write the guard didn't see.
+    pub(crate) fn forget(&mut self) {
+        for (val, _) in self.regs.values_mut() {
+            *val = f32::NAN;
+        }
+    }
+
+    /// Forget every register, write times included, e.g. on moving to
+    /// another unit.
+    pub(crate) fn clear(&mut self) {
+        self.regs.clear();
+    }
+}
+
+fn in_eeprom(reg: regs::Reg) -> bool {
+    matches!(reg.bank, Bank::Setpoint | Bank::Pid | Bank::System)
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    pub fn write_guard(&self) -> Option<WriteGuard> {
+        self.writes.guard
+    }
+
+    /// Whether a write of `val` to `reg` should go out.
+    ///
+    /// `Ok(false)` means the register already holds `val`.
+    pub(crate) fn guard_write(
+        &mut self,
+        ctx: Context,
+        reg: regs::Reg,
+        val: f32,
+    ) -> crate::Result<bool, UART> {
+        let (Some(guard), Some(now)) = (self.writes.guard, self.now()) else {
+            return Ok(true);
+        };
+        if !in_eeprom(reg) {
+            return Ok(true);
+        }
+
+        match self.writes.regs.get(&reg.addr) {
+            Some(&(known, _)) if known == val => return Ok(false),
+            Some(&(_, Some(at))) if now.saturating_sub(at) < guard.min_interval => {
+                return Err(Error::WriteThrottled(ctx));
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    /// Record a write of `val` to `reg` that went through.
+    pub(crate) fn wrote(&mut self, reg: regs::Reg, val: f32) {
+        let (Some(_), Some(now)) = (self.writes.guard, self.now()) else {
+            return;
+        };
+        if in_eeprom(reg) {
+            let _ = self.writes.regs.insert(reg.addr, (val, Some(now)));
+        }
+    }
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: Clock,
+{
+    /// Guard EEPROM writes with `guard`, or stop guarding them with `None`.
+    pub fn set_write_guard(&mut self, guard: Option<WriteGuard>) {
+        self.writes = Writes {
+            guard,
+            ..Writes::default()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::VirtualClock;
+    use crate::mock::MockSerial;
+    use crate::{Percent, TempRaw};
+
+    #[test]
+    fn skips_repeats_and_throttles() {
+        let mut port = MockSerial::new();
+        port.ack_f32(1, regs::SV.addr);
+        port.ack_f32(1, regs::SV.addr);
+        let clock = VirtualClock::new();
+        let mut pid = Syl2381::new(1, port).with_clock(&clock);
+        pid.live
+            .assume_sensor(crate::InputType::K, crate::DisplayUnit::Celsius);
+        pid.set_write_guard(Some(WriteGuard {
+            min_interval: Duration::from_secs(60),
+        }));
+
+        assert!(pid.set_sv(TempRaw(65.0)).is_ok());
+        assert!(pid.set_sv(TempRaw(65.0)).is_ok());
+        assert_eq!(pid.port.tx.len(), 13);
+
+        clock.advance(Duration::from_secs(30));
+        assert!(matches!(
+            pid.set_sv(TempRaw(66.0)),
+            Err(Error::WriteThrottled(ctx)) if ctx.addr == regs::SV.addr
+        ));
+
+        clock.advance(Duration::from_secs(30));
+        assert!(pid.set_sv(TempRaw(66.0)).is_ok());
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn leaves_ram_registers_alone() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 0.5);
+        port.ack_f32(1, regs::SOUF.addr);
+        port.ack_f32(1, regs::CV.addr);
+        port.ack_f32(1, regs::CV.addr);
+        let mut pid = Syl2381::new(1, port).with_clock(VirtualClock::new());
+        pid.set_write_guard(Some(WriteGuard {
+            min_interval: Duration::ZERO,
+        }));
+
+        // Read first, so the write is known to be a no-op.
+        assert!(pid.get_souf().is_ok());
+        assert!(pid.set_souf(Percent(0.5)).is_ok());
+        assert!(pid.set_souf(Percent(0.6)).is_ok());
+        assert!(pid.set_cv(true).is_ok());
+        assert!(pid.set_cv(true).is_ok());
+        assert!(pid.port.rx.is_empty());
+    }
+}
@@ -0,0 +1,196 @@
+//! A single request/response exchange, with no I/O of its own.
+//!
+//! On an MCU with DMA the cheap way to talk to the controller is to hand the
+//! whole request to a TX transfer and collect the response in whatever chunks
+//! the RX side delivers, usually one per IDLE-line interrupt. An [`Exchange`]
+//! supports exactly that: [`Exchange::request`] is the request as one
+//! contiguous slice, and [`Exchange::feed`] takes received bytes in chunks of
+//! any size until the response is complete.
+//!
+//! Values go over the wire as-is, without the typed setters' range checks.
+
+use core::fmt;
+
+use rmodbus::ErrorKind;
+
+use crate::frame::{Frame, RESYNC_WINDOW};
+use crate::regs;
+
+/// A single request.
+#[derive(Clone, Copy, fmt::Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Op {
+    /// Read a holding parameter.
+    Get(regs::Reg),
+
+    /// Write a holding parameter.
+    Set(regs::Reg, f32),
+
+    /// Read `count` (at most 8) coils.
+    GetCoils(regs::Reg, u8),
+}
+
+/// The result of a completed [`Op`].
+#[derive(Clone, Copy, fmt::Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Output {
+    /// The value read by [`Op::Get`].
+    Value(f32),
+
+    /// The write was acknowledged.
+    Written,
+
+    /// The coils read by [`Op::GetCoils`], first coil in bit 0.
+    Coils(u8),
+}
+
+pub struct Exchange {
+    op: Op,
+    frame: Frame,
+    /// Response bytes in the frame buffer, once receiving has started.
+    received: Option<usize>,
+    /// Response length, once the header has arrived.
+    len: Option<usize>,
+    /// Stray bytes skipped ahead of the response.
+    skipped: usize,
+}
+
+impl Exchange {
+    /// Encode the request for `op`.
+    pub fn new(unit_id: u8, op: Op) -> Result<Self, ErrorKind> {
+        let frame = match op {
+            Op::Get(reg) => Frame::get_holding(unit_id, reg),
+            Op::Set(reg, val) => Frame::set_holding(unit_id, reg, val),
+            Op::GetCoils(reg, count) => Frame::get_coils(unit_id, reg.addr, count),
+        }?;
+        Ok(Exchange {
+            op,
+            frame,
+            received: None,
+            len: None,
+            skipped: 0,
+        })
+    }
+
+    pub fn op(&self) -> Op {
+        self.op
+    }
+
+    /// The encoded request.
+    ///
+    /// The response is received into the same buffer, so this is empty once
+    /// [`feed`](Self::feed) has been called.
+    pub fn request(&self) -> &[u8] {
+        match self.received {
+            None => self.frame.request(),
+            Some(_) => &[],
+        }
+    }
+
+    /// How many more response bytes are needed, as far as is known yet.
+    ///
+    /// Until the header has arrived this only covers the header, so a DMA
+    /// transfer sized from it may need topping up.
+    pub fn wanted(&self) -> usize {
+        self.len.unwrap_or(3) - self.received.unwrap_or(0)
+    }
+
+    /// Take the next chunk of received bytes.
+    ///
+    /// Returns the result once the response is complete; bytes past its end
+    /// are ignored. Don't feed the exchange any more after that.
+    pub fn feed(&mut self, mut chunk: &[u8]) -> Option<Result<Output, ErrorKind>> {
+        let mut received = match self.received {
+            Some(received) => received,
+            None => {
+                self.frame.response_header();
+                0
+            }
+        };
+
+        while !chunk.is_empty() {
+            let want = self.len.unwrap_or(3);
+            let n = (want - received).min(chunk.len());
+            self.frame.response_mut()[received..received + n].copy_from_slice(&chunk[..n]);
+            chunk = &chunk[n..];
+            received += n;
+            if received < want {
+                break;
+            }
+
+            if self.len.is_none() {
+                if self.frame.resync_header().is_some() {
+                    if self.skipped == RESYNC_WINDOW {
+                        return Some(Err(ErrorKind::FrameBroken));
+                    }
+                    self.skipped += 1;
+                    received = 2;
+                    continue;
+                }
+                match self.frame.response_body() {
+                    Ok(body) => self.len = Some(3 + body.len()),
+                    Err(err) => return Some(Err(err)),
+                }
+                continue;
+            }
+
+            self.received = Some(received);
+            return Some(self.parse());
+        }
+
+        self.received = Some(received);
+        None
+    }
+
+    fn parse(&self) -> Result<Output, ErrorKind> {
+        Ok(match self.op {
+            Op::Get(_) => Output::Value(self.frame.parse_f32()?),
+            Op::Set(..) => {
+                self.frame.parse_ok()?;
+                Output::Written
+            }
+            Op::GetCoils(..) => Output::Coils(self.frame.parse_coils()?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::frame;
+
+    #[test]
+    fn assembles_a_response_from_chunks() {
+        let mut ex = Exchange::new(1, Op::Get(regs::PV)).unwrap();
+        assert_eq!(ex.request(), frame(&[0x01, 0x03, 0x01, 0x64, 0x00, 0x02]));
+        assert_eq!(ex.wanted(), 3);
+
+        let mut rx = vec![0x55, 0xAA];
+        rx.extend(frame(&[0x01, 0x03, 0x04, 0x42, 0x48, 0x00, 0x00]));
+        rx.push(0xFF);
+
+        assert!(ex.feed(&rx[..4]).is_none());
+        assert!(ex.request().is_empty());
+        assert_eq!(ex.wanted(), 1);
+        assert!(ex.feed(&[]).is_none());
+        assert!(matches!(ex.feed(&rx[4..]), Some(Ok(Output::Value(v))) if v == 50.0));
+    }
+
+    #[test]
+    fn reports_exceptions() {
+        let mut ex = Exchange::new(1, Op::Set(regs::SV, 65.0)).unwrap();
+        assert_eq!(
+            ex.feed(&frame(&[0x01, 0x90, 0x02])),
+            Some(Err(ErrorKind::IllegalDataAddress))
+        );
+    }
+
+    #[test]
+    fn rejects_oversized_byte_counts() {
+        let mut ex = Exchange::new(1, Op::Get(regs::PV)).unwrap();
+        assert_eq!(
+            ex.feed(&[0x01, 0x03, 0xFF]),
+            Some(Err(ErrorKind::FrameBroken))
+        );
+    }
+}
@@ -0,0 +1,448 @@
+//! Recording real traffic and replaying it in tests.
+//!
+//! Wrap the port in a [`Recorder`] while reproducing a problem on real
+//! hardware, then [`Fixture::save`] what it captured. In a test,
+//! [`Fixture::load`] it back and run the same code over a [`Replay`], which
+//! checks that each request matches the recording and answers with the
+//! recorded response.
+//!
+//! Fixtures are text: a `>` line of hex bytes for each request, followed by a
+//! `<` line with its response, so they can be read and trimmed by hand. Lines
+//! starting with `#` are comments. A response cut short on the bus (a
+//! timeout, say) is recorded as far as it got, and replays the same way.
+//!
+//! To capture a whole field session, timing included, give the recorder the
+//! clock the application runs on ([`Recorder::with_clock`]). Each request is
+//! then preceded by an `@` line with the time it was sent, in seconds. A
+//! [`Replay`] given a [`VirtualClock`](crate::clock::VirtualClock) moves it
+//! forward to each request's recorded time, so cache expiry, lockouts and
+//! [`Logger`](crate::logger::Logger) timestamps come out as they did in the
+//! field, and a control-logic problem a user reports can be stepped through
+//! in a test.
+
+use core::fmt;
+use core::time::Duration;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::vec::Vec;
+
+use crate::clock::{Clock, DriverClock, NoClock};
+use crate::transport::{Ack, Transport};
+
+/// One request and the response bytes read after it.
+#[derive(Clone, Default, fmt::Debug, PartialEq, Eq)]
+pub struct Transaction {
+    pub request: Vec<u8>,
+    pub response: Vec<u8>,
+    /// When the request was sent, if the recorder had a clock.
+    pub at: Option<Duration>,
+}
+
+/// A recorded conversation with a controller.
+#[derive(Clone, Default, fmt::Debug, PartialEq, Eq)]
+pub struct Fixture {
+    pub transactions: Vec<Transaction>,
+}
+
+impl Fixture {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+/// `> 01 03 00 00 00 02 C4 0B` and `< 01 03 04 ...` lines, each request
+/// preceded by `@ 12.500000000` if it's timed.
+impl fmt::Display for Fixture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn line(f: &mut fmt::Formatter<'_>, dir: char, bytes: &[u8]) -> fmt::Result {
+            f.write_fmt(format_args!("{}", dir))?;
+            for b in bytes {
+                write!(f, " {:02X}", b)?;
+            }
+            writeln!(f)
+        }
+
+        for t in &self.transactions {
+            if let Some(at) = t.at {
+                writeln!(f, "@ {}.{:09}", at.as_secs(), at.subsec_nanos())?;
+            }
+            line(f, '>', &t.request)?;
+            line(f, '<', &t.response)?;
+        }
+        Ok(())
+    }
+}
+
+impl core::str::FromStr for Fixture {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> io::Result<Self> {
+        let mut fixture = Fixture::default();
+        let mut at = None;
+        for (n, line) in s.lines().enumerate() {
+            let bad = |what: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    std::format!("line {}: {}", n + 1, what),
+                )
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(secs) = line.strip_prefix('@') {
+                at = Some(parse_secs(secs.trim()).ok_or_else(|| bad("not a time"))?);
+                continue;
+            }
+            let (request, hex) = if let Some(hex) = line.strip_prefix('>') {
+                (true, hex)
+            } else if let Some(hex) = line.strip_prefix('<') {
+                (false, hex)
+            } else {
+                return Err(bad("expected '>' or '<'"));
+            };
+            let mut bytes = Vec::new();
+            for byte in hex.split_whitespace() {
+                bytes.push(u8::from_str_radix(byte, 16).map_err(|_| bad("not a hex byte"))?);
+            }
+            if request {
+                fixture.transactions.push(Transaction {
+                    request: bytes,
+                    response: Vec::new(),
+                    at: at.take(),
+                });
+            } else {
+                match fixture.transactions.last_mut() {
+                    Some(t) => t.response.extend(bytes),
+                    None => return Err(bad("response before any request")),
+                }
+            }
+        }
+        Ok(fixture)
+    }
+}
+
+/// `12.5` or `12.500000000` seconds, exactly.
+fn parse_secs(s: &str) -> Option<Duration> {
+    let (secs, frac) = s.split_once('.').unwrap_or((s, ""));
+    if frac.len() > 9 || !frac.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let nanos = std::format!("{:0<9}", frac).parse().ok()?;
+    Some(Duration::new(secs.parse().ok()?, nanos))
+}
+
+/// A transport that records everything passing through it.
+pub struct Recorder<T, C = NoClock> {
+    inner: T,
+    clock: C,
+    fixture: Fixture,
+}
+
+impl<T> Recorder<T>
+where
+    T: Transport,
+{
+    pub fn new(inner: T) -> Self {
+        Recorder {
+            inner,
+            clock: NoClock,
+            fixture: Fixture::default(),
+        }
+    }
+}
+
+impl<T, C> Recorder<T, C>
+where
+    T: Transport,
+    C: DriverClock,
+{
+    /// Note the time of each request on `clock`.
+    pub fn with_clock<D: Clock>(self, clock: D) -> Recorder<T, D> {
+        Recorder {
+            inner: self.inner,
+            clock,
+            fixture: self.fixture,
+        }
+    }
+
+    /// What has been recorded so far.
+    pub fn fixture(&self) -> &Fixture {
+        &self.fixture
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> (T, Fixture) {
+        (self.inner, self.fixture)
+    }
+}
+
+impl<T, C> Transport for Recorder<T, C>
+where
+    T: Transport,
+    C: DriverClock,
+{
+    type Error = T::Error;
+
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        self.fixture.transactions.push(Transaction {
+            request: frame.to_vec(),
+            response: Vec::new(),
+            at: self.clock.as_clock().map(|clock| clock.now()),
+        });
+        self.inner.write_frame(frame)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.inner.read_exact(buf)?;
+        if let Some(t) = self.fixture.transactions.last_mut() {
+            t.response.extend_from_slice(buf);
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner.flush()
+    }
+
+    fn is_timeout(err: &Self::Error) -> bool {
+        T::is_timeout(err)
+    }
+
+    fn acknowledged(&mut self, ack: &Ack) {
+        self.inner.acknowledged(ack)
+    }
+}
+
+/// Why a [`Replay`] couldn't go on.
+#[derive(Clone, fmt::Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    /// Request number `index` (from 0) isn't the one recorded.
+    Mismatch { index: usize, sent: Vec<u8> },
+
+    /// Every recorded request has been made.
+    Exhausted,
+
+    /// The recorded response ran out, as it did on the bus.
+    Timeout,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Mismatch { index, sent } => {
+                write!(
+                    f,
+                    "request {} doesn't match the recording: {:02X?}",
+                    index, sent
+                )
+            }
+            ReplayError::Exhausted => f.write_str("no more recorded requests"),
+            ReplayError::Timeout => f.write_str("recorded response ran out"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// A transport that plays a [`Fixture`] back.
+pub struct Replay<C = NoClock> {
+    fixture: Fixture,
+    clock: C,
+    /// Transactions started so far.
+    next: usize,
+    /// Response bytes already read.
+    pos: usize,
+}
+
+impl Replay {
+    pub fn new(fixture: Fixture) -> Self {
+        Replay {
+            fixture,
+            clock: NoClock,
+            next: 0,
+            pos: 0,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Replay::new(Fixture::load(path)?))
+    }
+}
+
+impl<C> Replay<C>
+where
+    C: DriverClock,
+{
+    /// Sleep on `clock` until each timed request's recorded time before
+    /// answering it, starting now with the first. With a
+    /// [`VirtualClock`](crate::clock::VirtualClock) this just moves it
+    /// forward; a real clock replays at the recorded pace.
+    pub fn with_clock<D: Clock>(self, clock: D) -> Replay<D> {
+        let remaining = &self.fixture.transactions[self.next..];
+        if let Some(at) = remaining.iter().find_map(|t| t.at) {
+            clock.sleep(at.saturating_sub(clock.now()));
+        }
+        Replay {
+            fixture: self.fixture,
+            clock,
+            next: self.next,
+            pos: self.pos,
+        }
+    }
+
+    /// Whether every recorded request has been made.
+    pub fn is_done(&self) -> bool {
+        self.next == self.fixture.transactions.len()
+    }
+}
+
+impl<C> Transport for Replay<C>
+where
+    C: DriverClock,
+{
+    type Error = ReplayError;
+
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        let index = self.next;
+        let t = self
+            .fixture
+            .transactions
+            .get(index)
+            .ok_or(ReplayError::Exhausted)?;
+        if t.request != frame {
+            return Err(ReplayError::Mismatch {
+                index,
+                sent: frame.to_vec(),
+            });
+        }
+        if let (Some(clock), Some(at)) = (self.clock.as_clock(), t.at) {
+            clock.sleep(at.saturating_sub(clock.now()));
+        }
+        self.next += 1;
+        self.pos = 0;
+        Ok(())
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Self::Error> {
+        let response = match self.next.checked_sub(1) {
+            Some(i) => &self.fixture.transactions[i].response,
+            None => return Err(ReplayError::Timeout),
+        };
+        let Some(bytes) = response.get(self.pos..self.pos + buf.len()) else {
+            self.pos = response.len();
+            return Err(ReplayError::Timeout);
+        };
+        buf.copy_from_slice(bytes);
+        self.pos += buf.len();
+        Ok(())
+    }
+
+    fn is_timeout(err: &Self::Error) -> bool {
+        matches!(err, ReplayError::Timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::VirtualClock;
+    use crate::logger::{Logger, Record};
+    use crate::mock::MockSerial;
+    use crate::{Error, Syl2381, TempRaw};
+
+    #[test]
+    fn replays_what_was_recorded() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 65.0);
+        port.respond(&[1, 0x01, 1, 0x20]);
+        let mut pid = Syl2381::new(1, Recorder::new(port));
+        assert!(pid.get_sv().is_ok());
+        assert!(pid.get_status().is_ok());
+        assert!(matches!(pid.get_pv(), Err(Error::SerialError(..))));
+
+        let text = pid.port.fixture().to_string();
+        assert!(text.starts_with("> 01 03 00 00 00 02 C4 0B\n< 01 03 04 42 82 00 00 4E 63\n"));
+        let mut pid = Syl2381::new(1, Replay::new(text.parse().unwrap()));
+        assert!(matches!(pid.get_sv(), Ok(TempRaw(v)) if v == 65.0));
+        assert!(pid.get_status().unwrap().alarm1());
+        assert!(matches!(pid.get_pv(), Err(Error::Timeout(_))));
+        assert!(pid.port.is_done());
+    }
+
+    #[test]
+    fn catches_different_requests() {
+        let fixture: Fixture = "# get SV\n> 01 03 00 00 00 02 C4 0B\n<\n".parse().unwrap();
+        let mut pid = Syl2381::new(1, Replay::new(fixture));
+
+        assert!(matches!(
+            pid.get_pv(),
+            Err(Error::SerialError(
+                _,
+                ReplayError::Mismatch { index: 0, .. }
+            ))
+        ));
+        assert!(matches!(pid.get_sv(), Err(Error::Timeout(_))));
+        assert!(matches!(
+            pid.get_sv(),
+            Err(Error::SerialError(_, ReplayError::Exhausted))
+        ));
+    }
+
+    #[test]
+    fn replays_a_session_on_its_recorded_clock() {
+        fn session<T: Transport>(pid: &mut Syl2381<T>, clock: &VirtualClock) -> Vec<Record> {
+            let mut log: Logger<4> = Logger::new();
+            for _ in 0..2 {
+                assert!(log.log(pid, clock).is_ok());
+                clock.sleep(Duration::from_secs(1));
+            }
+            log.to_vec()
+        }
+
+        let mut port = MockSerial::new();
+        for _ in 0..2 {
+            port.respond(&[1, 0x03, 8, 0x42, 0x70, 0, 0, 0x3E, 0x80, 0, 0]);
+            port.respond_f32(1, 65.0);
+            port.respond(&[1, 0x01, 1, 0]);
+        }
+        let clock = VirtualClock::new();
+        clock.advance(Duration::from_millis(90_250));
+        let mut pid = Syl2381::new(1, Recorder::new(port).with_clock(&clock));
+        let recorded = session(&mut pid, &clock);
+        let text = pid.port.fixture().to_string();
+        assert!(text.starts_with("@ 90.250000000\n> 01 "));
+
+        // As if the bus had stalled for five seconds before the second reading.
+        let mut fixture: Fixture = text.parse().unwrap();
+        for t in &mut fixture.transactions[3..] {
+            t.at = t.at.map(|at| at + Duration::from_secs(5));
+        }
+        let clock = VirtualClock::new();
+        let replay = Replay::new(fixture).with_clock(&clock);
+        let mut pid = Syl2381::new(1, replay);
+        let replayed = session(&mut pid, &clock);
+        assert!(pid.port.is_done());
+        let at = |records: &[Record]| records.iter().map(|r| r.at).collect::<Vec<_>>();
+        assert_eq!(at(&replayed), at(&recorded));
+        assert_eq!(at(&replayed)[1], Duration::from_millis(91_250));
+        assert_eq!(clock.now(), Duration::from_millis(97_250));
+    }
+
+    #[test]
+    fn rejects_malformed_fixtures() {
+        for text in ["< 01\n", "> 01 zz\n", "? 01\n", "→ 01\n", "@ 1.x\n"] {
+            let err = text.parse::<Fixture>().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert!(err.to_string().starts_with("line 1:"));
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Modbus RTU framing, independent of how the bytes get on the wire.
+//!
+//! A [`Frame`] holds one request and, once received, its response. The driver
+//! front ends (blocking and async) only move bytes in and out of it:
+//!
+//! 1. write [`Frame::request`],
+//! 2. read into [`Frame::response_header`] (address, function, byte count),
+//! 3. read into [`Frame::response_body`], sized from the header,
+//! 4. decode with one of the `parse_*` methods.
+
+use rmodbus::{client::ModbusRequest, guess_response_frame_len, ErrorKind, ModbusProto};
+
+use crate::regs;
+use crate::{f32_to_values, values_to_f32};
+
+/// How many stray bytes to skip looking for a response before giving up.
+pub const RESYNC_WINDOW: usize = 16;
+
+pub(crate) struct Frame {
+    mreq: ModbusRequest,
+    buf: heapless::Vec<u8, 256>,
+}
+
+impl Frame {
+    /// Read a holding param (an f32 spanning two registers).
+    pub fn get_holding(unit_id: u8, reg: regs::Reg) -> Result<Self, ErrorKind> {
+        Frame::get_words(unit_id, reg.addr, 2)
+    }
+
+    /// Write a holding param (an f32 spanning two registers).
+    pub fn set_holding(unit_id: u8, reg: regs::Reg, val: f32) -> Result<Self, ErrorKind> {
+        Frame::set_words(unit_id, reg.addr, &f32_to_values(val))
+    }
+
+    /// Read `count` consecutive holding registers.
+    pub fn get_words(unit_id: u8, addr: u16, count: u16) -> Result<Self, ErrorKind> {
+        let mut frame = Frame::new(unit_id);
+        frame
+            .mreq
+            .generate_get_holdings(addr, count, &mut frame.buf)?;
+        Ok(frame)
+    }
+
+    /// Write consecutive holding registers.
+    pub fn set_words(unit_id: u8, addr: u16, values: &[u16]) -> Result<Self, ErrorKind> {
+        let mut frame = Frame::new(unit_id);
+        frame
+            .mreq
+            .generate_set_holdings_bulk(addr, values, &mut frame.buf)?;
+        Ok(frame)
+    }
+
+    /// Write a single holding register (function 0x06).
+    pub fn set_word(unit_id: u8, addr: u16, value: u16) -> Result<Self, ErrorKind> {
+        let mut frame = Frame::new(unit_id);
+        frame
+            .mreq
+            .generate_set_holding(addr, value, &mut frame.buf)?;
+        Ok(frame)
+    }
+
+    /// Write a single coil.
+    pub fn set_coil(unit_id: u8, addr: u16, on: bool) -> Result<Self, ErrorKind> {
+        let mut frame = Frame::new(unit_id);
+        frame.mreq.generate_set_coil(addr, on, &mut frame.buf)?;
+        Ok(frame)
+    }
+
+    /// Read 1 to 8 consecutive coils; any other count is [`ErrorKind::OOB`].
+    pub fn get_coils(unit_id: u8, addr: u16, count: u8) -> Result<Self, ErrorKind> {
+        if count == 0 || count > 8 {
+            return Err(ErrorKind::OOB);
+        }
+
+        let mut frame = Frame::new(unit_id);
+        frame
+            .mreq
+            .generate_get_coils(addr, count as u16, &mut frame.buf)?;
+        Ok(frame)
+    }
+
+    fn new(unit_id: u8) -> Self {
+        Frame {
+            mreq: ModbusRequest::new(unit_id, ModbusProto::Rtu),
+            buf: heapless::Vec::new(),
+        }
+    }
+
+    /// The encoded request, ready to send.
+    pub fn request(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Start receiving, reusing the request buffer.
+    ///
+    /// Returns space for the first three bytes of the response:
+    /// addr (byte) + func (byte) + count (byte).
+    pub fn response_header(&mut self) -> &mut [u8] {
+        self.buf.clear();
+        let _ = self.buf.resize(3, 0);
+        &mut self.buf
+    }
+
+    /// Skip a byte of line noise ahead of the response.
+    ///
+    /// If the header doesn't start with our unit ID and function code (or its
+    /// exception form), drops its first byte and returns space for one more.
+    /// Returns `None` once the header lines up.
+    pub fn resync_header(&mut self) -> Option<&mut [u8]> {
+        let func = self.mreq.func;
+        if self.buf[0] == self.mreq.unit_id && (self.buf[1] == func || self.buf[1] == func | 0x80) {
+            return None;
+        }
+        self.buf.copy_within(1..3, 0);
+        Some(&mut self.buf[2..3])
+    }
+
+    /// Size the response from its header, returning space for the remainder.
+    ///
+    /// A byte count of more than 251 can't be a valid RTU frame, and wouldn't
+    /// fit the buffer.
+    pub fn response_body(&mut self) -> Result<&mut [u8], ErrorKind> {
+        let len = guess_response_frame_len(&self.buf, ModbusProto::Rtu)?;
+        self.buf
+            .resize(len as usize, 0)
+            .map_err(|_| ErrorKind::FrameBroken)?;
+        Ok(&mut self.buf[3..])
+    }
+
+    /// The response, once received.
+    pub fn response(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// The response received so far, for filling in pieces.
+    pub fn response_mut(&mut self) -> &mut [u8] {
+        &mut self.buf
+    }
+
+    /// Ensure the response frame was well formed.
+    pub fn parse_ok(&self) -> Result<(), ErrorKind> {
+        self.mreq.parse_ok(&self.buf)
+    }
+
+    /// Whether the response is an exception response.
+    pub fn is_exception(&self) -> bool {
+        self.buf.get(1).is_some_and(|func| func & 0x80 != 0)
+    }
+
+    /// Decode a holding param response.
+    pub fn parse_f32(&self) -> Result<f32, ErrorKind> {
+        let mut data: heapless::Vec<u16, 2> = heapless::Vec::new();
+        self.mreq.parse_u16(&self.buf, &mut data)?;
+        if data.len() != 2 {
+            return Err(ErrorKind::FrameBroken);
+        }
+
+        Ok(values_to_f32(data[0], data[1]))
+    }
+
+    /// Decode a holding registers response.
+    pub fn parse_words<const N: usize>(&self) -> Result<heapless::Vec<u16, N>, ErrorKind> {
+        let mut data = heapless::Vec::new();
+        self.mreq.parse_u16(&self.buf, &mut data)?;
+        Ok(data)
+    }
+
+    /// Decode a coil response.
+    ///
+    /// We only ever need to read up to 8 consecutive coils from the SYL-2381
+    /// (when reading the AT status register), so this makes the simplifying
+    /// assumption that we will only ever get 1 byte back; any other byte
+    /// count is a broken frame.
+    pub fn parse_coils(&self) -> Result<u8, ErrorKind> {
+        self.parse_ok()?;
+
+        // instead of using mreq.parse_bool, which fills a vec of bools,
+        // we'll just grab the byte directly.
+        match self.buf.get(2..4) {
+            Some(&[1, coils]) => Ok(coils),
+            _ => Err(ErrorKind::FrameBroken),
+        }
+    }
+}
+
+/// Modbus RTU CRC-16.
+#[cfg(any(test, feature = "simulator"))]
+pub fn crc16(frame: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for &b in frame {
+        crc ^= b as u16;
+        for _ in 0..8 {
+            if crc & 1 != 0 {
+                crc = (crc >> 1) ^ 0xA001;
+            } else {
+                crc >>= 1;
+            }
+        }
+    }
+    crc
+}
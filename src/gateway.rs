@@ -0,0 +1,272 @@
+//! Serving a controller over Modbus TCP.
+//!
+//! SCADA systems usually speak Modbus TCP, while the controller speaks RTU on
+//! a serial line. A [`Gateway`] answers each TCP request by making the same
+//! request of the controller through its driver, so a controller on a USB
+//! adapter can be reached across the network from, say, a Raspberry Pi.
+//! [`Gateway::serve`] handles one connection; see the `gateway` example for
+//! a listener.
+//!
+//! Holding register reads and writes (0x03, 0x06, 0x10) and coil reads and
+//! writes (0x01, 0x05) are passed through as-is, always to the driver's unit
+//! ID. The driver's [`Lockout`](crate::Lockout) policy still applies to
+//! writes. Any other function gets an illegal function exception.
+
+use std::io::{self, Read, Write};
+use std::vec::Vec;
+
+use crate::clock::{DriverClock, NoClock};
+use crate::transport::Transport;
+use crate::{Error, Syl2381};
+
+/// Transaction ID, protocol ID, length and unit ID.
+const MBAP_LEN: usize = 7;
+
+/// Longest PDU Modbus allows.
+const MAX_PDU: usize = 253;
+
+const READ_COILS: u8 = 0x01;
+const READ_HOLDINGS: u8 = 0x03;
+const WRITE_COIL: u8 = 0x05;
+const WRITE_HOLDING: u8 = 0x06;
+const WRITE_HOLDINGS: u8 = 0x10;
+
+const ILLEGAL_FUNCTION: u8 = 0x01;
+const ILLEGAL_DATA_ADDRESS: u8 = 0x02;
+const ILLEGAL_DATA_VALUE: u8 = 0x03;
+const DEVICE_FAILURE: u8 = 0x04;
+const DEVICE_BUSY: u8 = 0x06;
+const GATEWAY_TARGET_FAILED: u8 = 0x0B;
+
+pub struct Gateway<UART, CLOCK = NoClock> {
+    pid: Syl2381<UART, CLOCK>,
+}
+
+impl<UART, CLOCK> Gateway<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    pub fn new(pid: Syl2381<UART, CLOCK>) -> Self {
+        Gateway { pid }
+    }
+
+    pub fn get_mut(&mut self) -> &mut Syl2381<UART, CLOCK> {
+        &mut self.pid
+    }
+
+    pub fn into_inner(self) -> Syl2381<UART, CLOCK> {
+        self.pid
+    }
+
+    /// Answer requests on `stream` until the client disconnects.
+    ///
+    /// A frame that isn't Modbus TCP ends the connection with
+    /// `io::ErrorKind::InvalidData`.
+    pub fn serve<S: Read + Write>(&mut self, mut stream: S) -> io::Result<()> {
+        let mut header = [0; MBAP_LEN];
+        let mut pdu = [0; MAX_PDU];
+        loop {
+            match stream.read_exact(&mut header) {
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                read => read?,
+            }
+            let len = u16::from_be_bytes([header[4], header[5]]) as usize;
+            if header[2..4] != [0, 0] || !(2..=MAX_PDU + 1).contains(&len) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "not a Modbus TCP frame",
+                ));
+            }
+            let request = &mut pdu[..len - 1];
+            stream.read_exact(request)?;
+
+            let response = self.handle(request);
+            let mut adu = Vec::with_capacity(MBAP_LEN + response.len());
+            adu.extend_from_slice(&header[..4]);
+            adu.extend_from_slice(&(response.len() as u16 + 1).to_be_bytes());
+            adu.push(header[6]);
+            adu.extend_from_slice(&response);
+            stream.write_all(&adu)?;
+        }
+    }
+
+    /// Answer one request PDU (function code and data) with a response PDU.
+    ///
+    /// A failed request is answered with the matching exception; a controller
+    /// that didn't answer at all gets "gateway target failed to respond".
+    pub fn handle(&mut self, pdu: &[u8]) -> Vec<u8> {
+        let Some((&func, data)) = pdu.split_first() else {
+            return std::vec![0x80, ILLEGAL_FUNCTION];
+        };
+        match self.forward(func, data) {
+            Ok(response) => response,
+            Err(code) => std::vec![func | 0x80, code],
+        }
+    }
+
+    fn forward(&mut self, func: u8, data: &[u8]) -> Result<Vec<u8>, u8> {
+        if !matches!(
+            func,
+            READ_COILS | READ_HOLDINGS | WRITE_COIL | WRITE_HOLDING | WRITE_HOLDINGS
+        ) {
+            return Err(ILLEGAL_FUNCTION);
+        }
+        let word = |i: usize| {
+            data.get(i..i + 2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]))
+                .ok_or(ILLEGAL_DATA_VALUE)
+        };
+        let (addr, val) = (word(0)?, word(2)?);
+
+        let mut response = std::vec![func];
+        match func {
+            READ_COILS => {
+                if !(1..=8).contains(&val) {
+                    return Err(ILLEGAL_DATA_VALUE);
+                }
+                let coils = self.pid.read_coils_raw(addr, val as u8);
+                let mask = ((1u16 << val) - 1) as u8;
+                response.extend([1, coils.map_err(exception)? & mask]);
+            }
+            READ_HOLDINGS => {
+                let words = self.pid.read_holding_raw(addr, val).map_err(exception)?;
+                response.push(words.len() as u8 * 2);
+                for word in words {
+                    response.extend(word.to_be_bytes());
+                }
+            }
+            WRITE_COIL => {
+                let on = match val {
+                    0xFF00 => true,
+                    0x0000 => false,
+                    _ => return Err(ILLEGAL_DATA_VALUE),
+                };
+                self.pid.write_coil_raw(addr, on).map_err(exception)?;
+                response.extend_from_slice(&data[..4]);
+            }
+            WRITE_HOLDING => {
+                self.pid
+                    .write_holding_raw(addr, &[val])
+                    .map_err(exception)?;
+                response.extend_from_slice(&data[..4]);
+            }
+            WRITE_HOLDINGS => {
+                let bytes = data.get(5..).unwrap_or_default();
+                if data.get(4).map(|&n| n as usize) != Some(bytes.len())
+                    || bytes.len() != val as usize * 2
+                {
+                    return Err(ILLEGAL_DATA_VALUE);
+                }
+                let values: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|b| u16::from_be_bytes([b[0], b[1]]))
+                    .collect();
+                self.pid
+                    .write_holding_raw(addr, &values)
+                    .map_err(exception)?;
+                response.extend_from_slice(&data[..4]);
+            }
+            _ => unreachable!(),
+        }
+        Ok(response)
+    }
+}
+
+/// The exception code to pass on for a failed request.
+fn exception<E>(err: Error<E>) -> u8 {
+    match err {
+        Error::IllegalFunction(_) => ILLEGAL_FUNCTION,
+        Error::IllegalDataAddress(_) => ILLEGAL_DATA_ADDRESS,
+        Error::IllegalDataValue(_) | Error::UnexpectedValue(_) => ILLEGAL_DATA_VALUE,
+        Error::DeviceBusy(_) | Error::DeviceInSettingMode(_) | Error::WriteThrottled(_) => {
+            DEVICE_BUSY
+        }
+        Error::SerialError(..) | Error::Timeout(_) | Error::ModbusError(..) => {
+            GATEWAY_TARGET_FAILED
+        }
+        _ => DEVICE_FAILURE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{frame, MockSerial};
+
+    #[test]
+    fn forwards_reads_and_writes() {
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x03, 4, 0x42, 0x48, 0x00, 0x00]);
+        port.respond(&[1, 0x10, 0x00, 0x00, 0, 2]);
+        port.respond(&[1, 0x01, 1, 0b1111_0101]);
+        let mut gw = Gateway::new(Syl2381::new(1, port));
+
+        assert_eq!(
+            gw.handle(&[0x03, 0x01, 0x64, 0x00, 0x02]),
+            [0x03, 4, 0x42, 0x48, 0x00, 0x00]
+        );
+        assert_eq!(
+            gw.handle(&[0x10, 0x00, 0x00, 0x00, 0x02, 4, 0x42, 0x82, 0x00, 0x00]),
+            [0x10, 0x00, 0x00, 0x00, 0x02]
+        );
+        assert_eq!(gw.handle(&[0x01, 0x00, 0x00, 0x00, 0x03]), [0x01, 1, 0b101]);
+        assert_eq!(
+            gw.get_mut().port.tx[8..21],
+            frame(&[1, 0x10, 0x00, 0x00, 0x00, 0x02, 4, 0x42, 0x82, 0x00, 0x00])
+        );
+    }
+
+    #[test]
+    fn answers_with_exceptions() {
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x83, 0x02]);
+        let mut gw = Gateway::new(Syl2381::new(1, port));
+
+        assert_eq!(gw.handle(&[0x03, 0x30, 0x00, 0x00, 0x02]), [0x83, 0x02]);
+        assert_eq!(gw.handle(&[0x03, 0x00, 0x00, 0x00, 0x02]), [0x83, 0x0B]);
+        assert_eq!(gw.handle(&[0x2B, 0x0E, 0x01, 0x00]), [0xAB, 0x01]);
+        assert_eq!(gw.handle(&[0x05, 0x00, 0x01, 0x12, 0x34]), [0x85, 0x03]);
+    }
+
+    /// Reads come from `rx`, writes land in `tx`.
+    struct Duplex {
+        rx: io::Cursor<Vec<u8>>,
+        tx: Vec<u8>,
+    }
+
+    impl Read for Duplex {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.rx.read(buf)
+        }
+    }
+
+    impl Write for Duplex {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.tx.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn serves_a_tcp_stream() {
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x05, 0x00, 0x01, 0xFF, 0x00]);
+        let mut gw = Gateway::new(Syl2381::new(1, port));
+        let mut client = Duplex {
+            rx: io::Cursor::new(vec![
+                0x12, 0x34, 0, 0, 0, 6, 0xFF, 0x05, 0x00, 0x01, 0xFF, 0x00,
+            ]),
+            tx: Vec::new(),
+        };
+
+        gw.serve(&mut client).unwrap();
+        assert_eq!(
+            client.tx,
+            [0x12, 0x34, 0, 0, 0, 6, 0xFF, 0x05, 0x00, 0x01, 0xFF, 0x00]
+        );
+    }
+}
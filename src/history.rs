@@ -0,0 +1,204 @@
+//! Two-tier sample history with downsampling.
+//!
+//! A day-long kiln firing polled every second is far more data than a small
+//! gateway wants to hold, but the long-run trend still matters. [`History`]
+//! keeps the most recent `RECENT` samples at full rate; as they age out they
+//! are folded into fixed-width min/max/mean [`Bucket`]s, of which the newest
+//! `BUCKETS` are kept. Memory use is fixed at compile time.
+//!
+//! The full-rate tier is a [`Recent`] of its own, which
+//! [`Logger`](crate::logger::Logger) also keeps its records in.
+
+use core::fmt;
+use core::time::Duration;
+
+/// Summary of the samples that fell within one bucket-width of time.
+#[derive(Clone, Copy, fmt::Debug, PartialEq)]
+pub struct Bucket {
+    /// Start of the bucket, a multiple of the bucket width.
+    pub start: Duration,
+    pub min: f32,
+    pub max: f32,
+    sum: f32,
+    pub count: u32,
+}
+
+impl Bucket {
+    fn new(start: Duration, val: f32) -> Self {
+        Bucket {
+            start,
+            min: val,
+            max: val,
+            sum: val,
+            count: 1,
+        }
+    }
+
+    fn add(&mut self, val: f32) {
+        self.min = self.min.min(val);
+        self.max = self.max.max(val);
+        self.sum += val;
+        self.count += 1;
+    }
+
+    pub fn mean(&self) -> f32 {
+        self.sum / self.count as f32
+    }
+}
+
+/// The newest `N` items, oldest first.
+pub struct Recent<T, const N: usize> {
+    items: heapless::Deque<T, N>,
+}
+
+impl<T, const N: usize> Recent<T, N> {
+    pub fn new() -> Self {
+        Recent {
+            items: heapless::Deque::new(),
+        }
+    }
+
+    /// Add `item`, handing back the oldest if it had to make room.
+    pub fn push(&mut self, item: T) -> Option<T> {
+        let old = if self.items.is_full() {
+            self.items.pop_front()
+        } else {
+            None
+        };
+        let _ = self.items.push_back(item);
+        old
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    /// The newest item.
+    pub fn latest(&self) -> Option<&T> {
+        self.items.back()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.items.iter()
+    }
+}
+
+impl<T, const N: usize> Default for Recent<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct History<const RECENT: usize, const BUCKETS: usize> {
+    width: Duration,
+    recent: Recent<(Duration, f32), RECENT>,
+    buckets: heapless::Deque<Bucket, BUCKETS>,
+}
+
+impl<const RECENT: usize, const BUCKETS: usize> History<RECENT, BUCKETS> {
+    /// Downsample aged-out samples into buckets `width` long.
+    ///
+    /// # Panics
+    ///
+    /// If `width` is zero.
+    pub fn new(width: Duration) -> Self {
+        assert!(!width.is_zero());
+        History {
+            width,
+            recent: Recent::new(),
+            buckets: heapless::Deque::new(),
+        }
+    }
+
+    /// Record a sample taken at `at`. Samples must be pushed in time order.
+    ///
+    /// NaN readings are kept in the recent tier but left out of buckets.
+    pub fn push(&mut self, at: Duration, val: f32) {
+        if let Some((old_at, old_val)) = self.recent.push((at, val)) {
+            self.fold(old_at, old_val);
+        }
+    }
+
+    /// Full-rate samples, oldest first.
+    pub fn recent(&self) -> impl Iterator<Item = &(Duration, f32)> {
+        self.recent.iter()
+    }
+
+    /// Downsampled buckets, oldest first. All of them predate
+    /// [`recent`](Self::recent).
+    pub fn buckets(&self) -> impl Iterator<Item = &Bucket> {
+        self.buckets.iter()
+    }
+
+    fn fold(&mut self, at: Duration, val: f32) {
+        if val.is_nan() {
+            return;
+        }
+
+        let width = self.width.as_nanos();
+        let start = at.as_nanos() / width * width;
+        let start = Duration::new(
+            (start / 1_000_000_000) as u64,
+            (start % 1_000_000_000) as u32,
+        );
+
+        match self.buckets.back_mut() {
+            Some(last) if last.start == start => last.add(val),
+            _ => {
+                if self.buckets.is_full() {
+                    self.buckets.pop_front();
+                }
+                let _ = self.buckets.push_back(Bucket::new(start, val));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ages_samples_into_buckets() {
+        let mut h: History<4, 2> = History::new(Duration::from_secs(10));
+        let nan = f32::NAN;
+        let samples = [1.0, 5.0, 3.0, nan, 7.0, 2.0, 4.0, 6.0, 8.0, 9.0, 0.0, 1.0];
+        for (i, val) in samples.into_iter().enumerate() {
+            h.push(Duration::from_secs(i as u64 * 3), val);
+        }
+
+        // 0..=21 s aged out into [0, 10), [10, 20) and [20, 30); the first
+        // has since been dropped.
+        let buckets: Vec<_> = h.buckets().copied().collect();
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].start, Duration::from_secs(10));
+        assert_eq!(
+            (buckets[0].min, buckets[0].max, buckets[0].count),
+            (2.0, 7.0, 3)
+        );
+        assert_eq!(buckets[1].start, Duration::from_secs(20));
+        assert_eq!((buckets[1].min, buckets[1].max), (6.0, 6.0));
+        assert!((buckets[0].mean() - 13.0 / 3.0).abs() < 1e-6);
+
+        let recent: Vec<_> = h.recent().map(|&(_, v)| v).collect();
+        assert_eq!(recent, [8.0, 9.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn recent_hands_back_what_it_drops() {
+        let mut r: Recent<u8, 2> = Recent::new();
+        assert_eq!(r.push(1), None);
+        assert_eq!(r.push(2), None);
+        assert_eq!(r.push(3), Some(1));
+        assert_eq!(r.latest(), Some(&3));
+        assert_eq!(r.iter().copied().collect::<Vec<_>>(), [2, 3]);
+    }
+}
@@ -0,0 +1,203 @@
+//! Register images: the controller's holding parameters as raw Modbus words.
+//!
+//! PLC engineers and generic Modbus tools think in addresses and 16-bit words
+//! rather than typed parameters. A [`RegisterImage`] maps each holding register
+//! address to the word stored there, with every f32 parameter occupying two
+//! consecutive addresses.
+
+use core::fmt;
+
+use crate::clock::DriverClock;
+use crate::regs::{self, Bank, Kind};
+use crate::transport::Transport;
+use crate::{f32_to_values, values_to_f32, Error, Syl2381};
+
+/// Holding register address → word.
+#[derive(Clone, Default, fmt::Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "Words", into = "Words"))]
+pub struct RegisterImage {
+    words: heapless::Vec<(u16, u16), 64>,
+}
+
+impl RegisterImage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the word at `addr`, replacing any previous value.
+    ///
+    /// Returns `false` if the image is full.
+    pub fn insert(&mut self, addr: u16, word: u16) -> bool {
+        match self.words.binary_search_by_key(&addr, |&(a, _)| a) {
+            Ok(i) => {
+                self.words[i].1 = word;
+                true
+            }
+            Err(i) => self.words.insert(i, (addr, word)).is_ok(),
+        }
+    }
+
+    pub fn get(&self, addr: u16) -> Option<u16> {
+        self.words
+            .binary_search_by_key(&addr, |&(a, _)| a)
+            .ok()
+            .map(|i| self.words[i].1)
+    }
+
+    /// All `(address, word)` pairs, in address order.
+    pub fn words(&self) -> &[(u16, u16)] {
+        &self.words
+    }
+
+    fn insert_f32(&mut self, reg: regs::Reg, val: f32) {
+        let [d0, d1] = f32_to_values(val);
+        self.insert(reg.addr, d0);
+        self.insert(reg.addr + 1, d1);
+    }
+
+    fn get_f32(&self, reg: regs::Reg) -> Option<f32> {
+        Some(values_to_f32(self.get(reg.addr)?, self.get(reg.addr + 1)?))
+    }
+}
+
+#[cfg(feature = "serde")]
+type Words = heapless::Vec<(u16, u16), 64>;
+
+/// Goes through [`RegisterImage::insert`], so the pairs may come in any order.
+#[cfg(feature = "serde")]
+impl From<Words> for RegisterImage {
+    fn from(words: Words) -> Self {
+        let mut image = RegisterImage::new();
+        for (addr, word) in words {
+            image.insert(addr, word);
+        }
+        image
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<RegisterImage> for Words {
+    fn from(image: RegisterImage) -> Self {
+        image.words
+    }
+}
+
+/// One `0xADDR 0xWORD` pair per line.
+impl fmt::Display for RegisterImage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for &(addr, word) in &self.words {
+            writeln!(f, "0x{:04X} 0x{:04X}", addr, word)?;
+        }
+        Ok(())
+    }
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    /// Read every holding parameter into a register image.
+    pub fn read_register_image(&mut self) -> crate::Result<RegisterImage, UART> {
+        let mut image = RegisterImage::new();
+        for &reg in regs::TABLE.iter().filter(|r| r.kind() == Kind::Holding) {
+            let val = self.get_holding_raw(reg)?;
+            image.insert_f32(reg, val);
+        }
+        Ok(image)
+    }
+
+    /// Write the configuration parameters present in `image` to the
+    /// controller.
+    ///
+    /// Only the setpoint, PID and system banks are written; live values (PV,
+    /// OUT, CV) are skipped. The unit ID and baud rate are skipped too, since
+    /// changing them part way through would cut off the remaining writes.
+    ///
+    /// Values are written as-is, without the range checks of the typed
+    /// setters. A register whose two words aren't both in the image is
+    /// rejected with [`Error::UnexpectedValue`] before anything is written.
+    pub fn write_register_image(&mut self, image: &RegisterImage) -> crate::Result<(), UART> {
+        let writable = || {
+            regs::TABLE.iter().copied().filter(|r| {
+                matches!(r.bank, Bank::Setpoint | Bank::Pid | Bank::System)
+                    && r.addr != regs::ID.addr
+                    && r.addr != regs::BAUD.addr
+            })
+        };
+
+        for reg in writable() {
+            let halves = (image.get(reg.addr), image.get(reg.addr + 1));
+            if let (Some(_), None) | (None, Some(_)) = halves {
+                return Err(Error::UnexpectedValue(reg.addr as f32));
+            }
+        }
+
+        for reg in writable() {
+            if let Some(val) = image.get_f32(reg) {
+                self.set_holding_raw(reg, val)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockSerial;
+
+    #[test]
+    fn renders_words_in_address_order() {
+        let mut image = RegisterImage::new();
+        image.insert_f32(regs::HY, 2.0);
+        image.insert_f32(regs::SV, 10_000.0);
+
+        assert_eq!(
+            format!("{}", image),
+            "0x0000 0x461C\n0x0001 0x4000\n0x2006 0x4000\n0x2007 0x0000\n"
+        );
+    }
+
+    #[test]
+    fn writes_config_registers_only() {
+        let mut image = RegisterImage::new();
+        image.insert_f32(regs::PV, 25.0);
+        image.insert_f32(regs::SV, 65.0);
+        image.insert_f32(regs::ID, 7.0);
+
+        let mut port = MockSerial::new();
+        port.ack_f32(1, regs::SV.addr);
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(pid.write_register_image(&image).is_ok());
+        assert!(pid.port.rx.is_empty());
+        // address 0x0000, two registers
+        assert_eq!(pid.port.tx[1..6], [0x10, 0x00, 0x00, 0x00, 0x02]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserializes_unsorted_words() {
+        let image: RegisterImage = serde_json::from_str("[[8199, 0], [8198, 16384]]").unwrap();
+        assert_eq!(image.words(), [(0x2006, 0x4000), (0x2007, 0x0000)]);
+        assert_eq!(
+            serde_json::to_string(&image).unwrap(),
+            "[[8198,16384],[8199,0]]"
+        );
+    }
+
+    #[test]
+    fn rejects_half_registers() {
+        let mut image = RegisterImage::new();
+        image.insert(regs::P.addr, 0x4120);
+
+        let mut pid = Syl2381::new(1, MockSerial::new());
+        assert!(matches!(
+            pid.write_register_image(&image),
+            Err(Error::UnexpectedValue(_))
+        ));
+        assert!(pid.port.tx.is_empty());
+    }
+}
@@ -0,0 +1,129 @@
+//! Human-readable labels for a controller.
+//!
+//! With a dozen controllers on one site, "unit 7" means little in a log or
+//! an alert. [`Labels`] attach a name, location and probe description to a
+//! driver, and anything that reports on a particular controller passes them
+//! along: [`Breaker`](crate::breaker::Breaker) transitions,
+//! [`Logger`](crate::logger::Logger)s and the CSV rows written from them, and
+//! the MQTT bridge's state and status messages.
+
+use core::fmt;
+
+use crate::clock::DriverClock;
+use crate::transport::Transport;
+use crate::Syl2381;
+
+/// Longest label kept, in bytes; longer ones are cut short.
+pub const LABEL_LEN: usize = 32;
+
+pub type Label = heapless::String<LABEL_LEN>;
+
+#[derive(Clone, Default, fmt::Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Labels {
+    /// A short name, such as "Kiln 2".
+    pub name: Label,
+
+    /// Where the controller is.
+    pub location: Label,
+
+    /// What the probe is and where it sits.
+    pub probe: Label,
+}
+
+impl Labels {
+    pub fn new(name: &str) -> Self {
+        Labels {
+            name: label(name),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_location(mut self, location: &str) -> Self {
+        self.location = label(location);
+        self
+    }
+
+    pub fn with_probe(mut self, probe: &str) -> Self {
+        self.probe = label(probe);
+        self
+    }
+
+    /// Whether no label is set at all.
+    pub fn is_empty(&self) -> bool {
+        self.name.is_empty() && self.location.is_empty() && self.probe.is_empty()
+    }
+}
+
+/// `name (location, probe)`, leaving out whatever is empty.
+impl fmt::Display for Labels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        let mut sep = " (";
+        for extra in [&self.location, &self.probe] {
+            if !extra.is_empty() {
+                write!(f, "{}{}", sep, extra)?;
+                sep = ", ";
+            }
+        }
+        if sep == ", " {
+            f.write_str(")")?;
+        }
+        Ok(())
+    }
+}
+
+fn label(s: &str) -> Label {
+    let mut end = s.len().min(LABEL_LEN);
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    let mut label = Label::new();
+    let _ = label.push_str(&s[..end]);
+    label
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    pub fn labels(&self) -> &Labels {
+        &self.labels
+    }
+
+    pub fn set_labels(&mut self, labels: Labels) {
+        self.labels = labels;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_what_is_set() {
+        assert_eq!(Labels::new("Kiln 2").to_string(), "Kiln 2");
+        assert_eq!(
+            Labels::new("Kiln 2").with_probe("K, shelf 3").to_string(),
+            "Kiln 2 (K, shelf 3)"
+        );
+        assert_eq!(
+            Labels::new("Smoker")
+                .with_location("Shed")
+                .with_probe("PT100")
+                .to_string(),
+            "Smoker (Shed, PT100)"
+        );
+    }
+
+    #[test]
+    fn truncates_on_a_char_boundary() {
+        let name = "é".repeat(20);
+        let labels = Labels::new(&name);
+        assert_eq!(labels.name.len(), 32);
+        assert_eq!(labels.name.as_str(), &name[..32]);
+        let labels = Labels::new(&format!("x{}", name));
+        assert_eq!(labels.name.len(), 31);
+    }
+}
@@ -11,40 +11,122 @@ Also useful:
 
 #![cfg_attr(all(not(test), not(feature = "std")), no_std)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 use core::fmt;
 
-use rmodbus::{client::ModbusRequest, guess_response_frame_len, ModbusProto};
-
-use eh_nb_1_0_alpha as embedded_hal;
-
-mod regs {
-    pub const PV: u16 = 0x0164;
-    pub const OUT: u16 = 0x0166;
-    pub const AL1_STA: u16 = 0x0005;
-    pub const CV: u16 = 0x016C;
-    pub const AT: u16 = 0x0000;
-    pub const SV: u16 = 0x0000;
-    pub const AH1: u16 = 0x0002;
-    pub const AL1: u16 = 0x0004;
-    pub const P: u16 = 0x1000;
-    pub const I: u16 = 0x1002;
-    pub const D: u16 = 0x1004;
-    pub const BB: u16 = 0x1006;
-    pub const SOUF: u16 = 0x1008;
-    pub const OT: u16 = 0x100A;
-    pub const FILT: u16 = 0x100C;
-    pub const INTY: u16 = 0x2000;
-    pub const OUTY: u16 = 0x2002;
-    pub const COTY: u16 = 0x2004;
-    pub const HY: u16 = 0x2006;
-    pub const PSB: u16 = 0x2008;
-    pub const RD: u16 = 0x200A;
-    pub const CORF: u16 = 0x200C;
-    pub const ID: u16 = 0x200E;
-    pub const BAUD: u16 = 0x2010;
-}
+#[cfg(feature = "async")]
+pub mod asynch;
+#[cfg(feature = "audit")]
+pub mod audit;
+pub mod breaker;
+pub mod bus;
+mod cache;
+#[cfg(feature = "async")]
+pub mod client;
+pub mod clock;
+mod comm;
+pub mod config;
+mod configure;
+#[cfg(feature = "std")]
+pub mod csv;
+mod eeprom;
+pub mod exchange;
+#[cfg(feature = "std")]
+pub mod fixture;
+#[cfg(feature = "gateway")]
+pub mod gateway;
+pub mod history;
+pub mod image;
+pub mod labels;
+mod lockout;
+pub mod logger;
+#[cfg(feature = "embedded-io")]
+pub mod machine;
+mod manual;
+#[cfg(feature = "alloc")]
+pub mod merge;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+mod observe;
+pub mod param;
+pub mod plausibility;
+mod poll;
+mod probe;
+#[cfg(feature = "profile")]
+pub mod profile;
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+pub mod raw;
+pub mod sample;
+#[cfg(any(test, feature = "simulator"))]
+pub mod simulator;
+mod snapshot;
+mod statics;
+mod stats;
+pub mod storage;
+#[cfg(feature = "postcard")]
+pub mod stored;
+mod strategy;
+mod temperature;
+#[cfg(feature = "tokio")]
+pub mod tokio_port;
+pub mod transform;
+pub mod transport;
+mod tuning;
+#[cfg(feature = "tunnel")]
+pub mod tunnel;
+mod units;
+mod verify;
+pub mod watch;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(feature = "async")]
+pub use asynch::Syl2381Async;
+pub use bus::Bus;
+#[cfg(feature = "async")]
+pub use client::ModbusClient;
+pub use clock::Clock;
+pub use comm::CommChange;
+pub use config::Config;
+pub use configure::{AlarmConfig, SsrWithJ1Alarm};
+pub use eeprom::WriteGuard;
+pub use labels::Labels;
+pub use lockout::Lockout;
+pub use manual::ManualOutput;
+pub use observe::FrameObserver;
+pub use param::{Param, ParamValue};
+pub use poll::ProcessValues;
+pub use probe::Identity;
+pub use snapshot::DeviceSnapshot;
+pub use statics::StaticCache;
+pub use stats::Stats;
+pub use strategy::WriteStrategy;
+pub use temperature::{AnyTemp, Temperature};
+#[cfg(feature = "tokio")]
+pub use tokio_port::TokioPort;
+#[cfg(feature = "std")]
+pub use transport::IoTransport;
+pub use transport::{Ack, AckValue, TimeoutError, Transport};
+#[cfg(feature = "embedded-io")]
+pub use transport::{EmbeddedIo, SplitIo, Timed};
+pub use tuning::Pid;
+#[cfg(feature = "tunnel")]
+pub use tunnel::Tunnel;
+pub use units::{Percent, Seconds, TempRaw};
+
+use frame::Frame;
+
+mod frame;
+mod limits;
+pub mod regs;
 
 #[derive(Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Status(u8);
 
 impl Status {
@@ -88,11 +170,13 @@ impl fmt::Debug for Status {
 
 impl fmt::Display for Status {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        <Self as fmt::Debug>::fmt(&self, f)
+        <Self as fmt::Debug>::fmt(self, f)
     }
 }
 
 #[derive(Clone, Copy, fmt::Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Filter {
     Disabled,
     Weak,
@@ -126,11 +210,13 @@ impl From<Filter> for f32 {
 
 impl fmt::Display for Filter {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        <Self as fmt::Debug>::fmt(&self, f)
+        <Self as fmt::Debug>::fmt(self, f)
     }
 }
 
 #[derive(Clone, Copy, fmt::Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ControlDirection {
     Heating,
     Cooling,
@@ -161,11 +247,13 @@ impl From<ControlDirection> for f32 {
 
 impl fmt::Display for ControlDirection {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        <Self as fmt::Debug>::fmt(&self, f)
+        <Self as fmt::Debug>::fmt(self, f)
     }
 }
 
-#[derive(Clone, Copy, fmt::Debug)]
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DisplayUnit {
     Celsius,
     Fahrenheit,
@@ -196,11 +284,31 @@ impl From<DisplayUnit> for f32 {
 
 impl fmt::Display for DisplayUnit {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        <Self as fmt::Debug>::fmt(&self, f)
+        <Self as fmt::Debug>::fmt(self, f)
     }
 }
 
-#[derive(Clone, Copy, fmt::Debug)]
+impl DisplayUnit {
+    /// Convert a temperature in degrees Celsius to this unit.
+    pub fn from_celsius(self, celsius: f32) -> f32 {
+        match self {
+            DisplayUnit::Celsius => celsius,
+            DisplayUnit::Fahrenheit => celsius * 9.0 / 5.0 + 32.0,
+        }
+    }
+
+    /// Convert a temperature in this unit to degrees Celsius.
+    pub fn to_celsius(self, val: f32) -> f32 {
+        match self {
+            DisplayUnit::Celsius => val,
+            DisplayUnit::Fahrenheit => (val - 32.0) * 5.0 / 9.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BaudRate {
     Baud1200,
     Baud2400,
@@ -236,13 +344,27 @@ impl From<BaudRate> for f32 {
     }
 }
 
+impl BaudRate {
+    /// The line rate in bits per second, for configuring the port.
+    pub fn bps(self) -> u32 {
+        match self {
+            BaudRate::Baud1200 => 1200,
+            BaudRate::Baud2400 => 2400,
+            BaudRate::Baud4800 => 4800,
+            BaudRate::Baud9600 => 9600,
+        }
+    }
+}
+
 impl fmt::Display for BaudRate {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        <Self as fmt::Debug>::fmt(&self, f)
+        <Self as fmt::Debug>::fmt(self, f)
     }
 }
 
 #[derive(Clone, Copy, fmt::Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InputType {
     /// Type T thermocouple.
     T,
@@ -321,11 +443,32 @@ impl From<InputType> for f32 {
 
 impl fmt::Display for InputType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        <Self as fmt::Debug>::fmt(&self, f)
+        <Self as fmt::Debug>::fmt(self, f)
+    }
+}
+
+impl InputType {
+    /// The measuring range of the sensor in degrees Celsius, as `(min, max)`.
+    pub fn range_celsius(self) -> (f32, f32) {
+        match self {
+            InputType::T => (-200.0, 400.0),
+            InputType::R => (-50.0, 1600.0),
+            InputType::J => (-200.0, 1200.0),
+            InputType::Wre3_25 => (0.0, 2300.0),
+            InputType::B => (350.0, 1800.0),
+            InputType::S => (-50.0, 1600.0),
+            InputType::K => (-200.0, 1300.0),
+            InputType::E => (-200.0, 900.0),
+            InputType::P100 => (-200.0, 600.0),
+            InputType::P10_0 => (-199.9, 600.0),
+            InputType::CU50 => (-50.0, 150.0),
+        }
     }
 }
 
 #[derive(Clone, Copy, fmt::Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OutputType {
     /// SSR output.
     ///
@@ -372,11 +515,13 @@ impl From<OutputType> for f32 {
 
 impl fmt::Display for OutputType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        <Self as fmt::Debug>::fmt(&self, f)
+        <Self as fmt::Debug>::fmt(self, f)
     }
 }
 
 #[derive(Clone, Copy, fmt::Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OutputMode {
     /// J1 relay works as absolute alarm output; SSR port as PID control output.
     J1RelayAsAbsoluteAlarmOutputSsrPortAsPidControlOutput,
@@ -425,63 +570,328 @@ impl From<OutputMode> for f32 {
 
 impl fmt::Display for OutputMode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        <Self as fmt::Debug>::fmt(&self, f)
+        <Self as fmt::Debug>::fmt(self, f)
     }
 }
 
+/// What a failed request was doing.
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Operation {
+    ReadHolding,
+    WriteHolding,
+    ReadCoils,
+    WriteCoil,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <Self as fmt::Debug>::fmt(self, f)
+    }
+}
+
+/// The request an error came from.
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Context {
+    pub op: Operation,
+
+    /// Register address.
+    pub addr: u16,
+}
+
+impl Context {
+    pub(crate) fn new(op: Operation, reg: regs::Reg) -> Self {
+        Context { op, addr: reg.addr }
+    }
+}
+
+impl fmt::Display for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} 0x{:04X}", self.op, self.addr)
+    }
+}
+
+#[derive(fmt::Debug)]
 pub enum Error<UartError> {
-    SerialError(UartError),
+    SerialError(Context, UartError),
     UnexpectedValue(f32),
-    ModbusError(rmodbus::ErrorKind),
+    ModbusError(Context, rmodbus::ErrorKind),
+    /// The circuit breaker is open; the request wasn't sent.
+    CircuitOpen,
+    /// The reading can't come from a working sensor.
+    SensorFault(f32),
+    /// The value is outside what the configured sensor can measure.
+    OutOfRange {
+        value: f32,
+        min: f32,
+        max: f32,
+    },
+    /// A register didn't hold the value just written to it.
+    VerificationFailed {
+        expected: f32,
+        actual: f32,
+    },
+    /// The response didn't arrive in time.
+    Timeout(Context),
+    /// The front panel setting menu is open, so the write wasn't sent.
+    DeviceInSettingMode(Context),
+    /// The register was written too recently; see [`WriteGuard`].
+    WriteThrottled(Context),
+    /// The controller doesn't support the function (exception 0x01).
+    IllegalFunction(Context),
+    /// The controller has no register at that address (exception 0x02).
+    IllegalDataAddress(Context),
+    /// The controller rejected the value (exception 0x03).
+    IllegalDataValue(Context),
+    /// The controller failed while handling the request (exception 0x04).
+    DeviceFailure(Context),
+    /// The controller is busy; try again later (exception 0x06).
+    DeviceBusy(Context),
 }
 
-impl<UartError> From<rmodbus::ErrorKind> for Error<UartError> {
-    fn from(value: rmodbus::ErrorKind) -> Self {
-        Error::ModbusError(value)
+impl<UartError> Error<UartError> {
+    /// The request that failed, for errors that came off the bus.
+    pub fn context(&self) -> Option<Context> {
+        match *self {
+            Error::SerialError(ctx, _)
+            | Error::ModbusError(ctx, _)
+            | Error::Timeout(ctx)
+            | Error::DeviceInSettingMode(ctx)
+            | Error::WriteThrottled(ctx)
+            | Error::IllegalFunction(ctx)
+            | Error::IllegalDataAddress(ctx)
+            | Error::IllegalDataValue(ctx)
+            | Error::DeviceFailure(ctx)
+            | Error::DeviceBusy(ctx) => Some(ctx),
+            _ => None,
+        }
+    }
+
+    /// Exception responses get their own variants; the rarer exceptions and
+    /// framing errors stay [`Error::ModbusError`].
+    pub(crate) fn modbus(ctx: Context, kind: rmodbus::ErrorKind) -> Self {
+        use rmodbus::ErrorKind;
+        match kind {
+            ErrorKind::IllegalFunction => Error::IllegalFunction(ctx),
+            ErrorKind::IllegalDataAddress => Error::IllegalDataAddress(ctx),
+            ErrorKind::IllegalDataValue => Error::IllegalDataValue(ctx),
+            ErrorKind::SlaveDeviceFailure => Error::DeviceFailure(ctx),
+            ErrorKind::SlaveDeviceBusy => Error::DeviceBusy(ctx),
+            _ => Error::ModbusError(ctx, kind),
+        }
     }
 }
 
-pub struct Syl2381<UART> {
+/// Serial errors are shown with their `Debug` output, since not every
+/// transport's error type implements `Display`.
+impl<UartError: fmt::Debug> fmt::Display for Error<UartError> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::SerialError(ctx, err) => write!(f, "{}: serial error: {:?}", ctx, err),
+            Error::UnexpectedValue(val) => write!(f, "unexpected value {}", val),
+            Error::ModbusError(ctx, kind) => write!(f, "{}: modbus error: {}", ctx, kind),
+            Error::CircuitOpen => f.write_str("circuit breaker open"),
+            Error::SensorFault(val) => write!(f, "sensor fault (reading {})", val),
+            Error::OutOfRange { value, min, max } => {
+                write!(f, "{} outside sensor range {} to {}", value, min, max)
+            }
+            Error::VerificationFailed { expected, actual } => {
+                write!(f, "wrote {} but read back {}", expected, actual)
+            }
+            Error::Timeout(ctx) => write!(f, "{}: timed out", ctx),
+            Error::DeviceInSettingMode(ctx) => write!(f, "{}: in setting mode", ctx),
+            Error::WriteThrottled(ctx) => write!(f, "{}: written too recently", ctx),
+            Error::IllegalFunction(ctx) => write!(f, "{}: illegal function", ctx),
+            Error::IllegalDataAddress(ctx) => write!(f, "{}: illegal data address", ctx),
+            Error::IllegalDataValue(ctx) => write!(f, "{}: illegal data value", ctx),
+            Error::DeviceFailure(ctx) => write!(f, "{}: device failure", ctx),
+            Error::DeviceBusy(ctx) => write!(f, "{}: device busy", ctx),
+        }
+    }
+}
+
+/// This is also `std::error::Error`.
+impl<UartError: fmt::Debug> core::error::Error for Error<UartError> {}
+
+#[cfg(feature = "defmt")]
+impl<UartError: defmt::Format> defmt::Format for Error<UartError> {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Error::SerialError(ctx, err) => defmt::write!(f, "{}: serial error: {}", ctx, err),
+            Error::UnexpectedValue(val) => defmt::write!(f, "unexpected value {}", val),
+            Error::ModbusError(ctx, kind) => {
+                defmt::write!(f, "{}: modbus error: {=str}", ctx, modbus_kind_name(*kind))
+            }
+            Error::CircuitOpen => defmt::write!(f, "circuit breaker open"),
+            Error::SensorFault(val) => defmt::write!(f, "sensor fault (reading {})", val),
+            Error::OutOfRange { value, min, max } => {
+                defmt::write!(f, "{} outside sensor range {} to {}", value, min, max)
+            }
+            Error::VerificationFailed { expected, actual } => {
+                defmt::write!(f, "wrote {} but read back {}", expected, actual)
+            }
+            Error::Timeout(ctx) => defmt::write!(f, "{}: timed out", ctx),
+            Error::DeviceInSettingMode(ctx) => defmt::write!(f, "{}: in setting mode", ctx),
+            Error::WriteThrottled(ctx) => defmt::write!(f, "{}: written too recently", ctx),
+            Error::IllegalFunction(ctx) => defmt::write!(f, "{}: illegal function", ctx),
+            Error::IllegalDataAddress(ctx) => defmt::write!(f, "{}: illegal data address", ctx),
+            Error::IllegalDataValue(ctx) => defmt::write!(f, "{}: illegal data value", ctx),
+            Error::DeviceFailure(ctx) => defmt::write!(f, "{}: device failure", ctx),
+            Error::DeviceBusy(ctx) => defmt::write!(f, "{}: device busy", ctx),
+        }
+    }
+}
+
+/// rmodbus doesn't implement `defmt::Format`, and going through its `Debug`
+/// impl would pull in `core::fmt`.
+#[cfg(feature = "defmt")]
+fn modbus_kind_name(kind: rmodbus::ErrorKind) -> &'static str {
+    use rmodbus::ErrorKind;
+    match kind {
+        ErrorKind::OOB => "OOB",
+        ErrorKind::OOBContext => "OOBContext",
+        ErrorKind::FrameBroken => "FrameBroken",
+        ErrorKind::FrameCRCError => "FrameCRCError",
+        ErrorKind::IllegalFunction => "IllegalFunction",
+        ErrorKind::IllegalDataAddress => "IllegalDataAddress",
+        ErrorKind::IllegalDataValue => "IllegalDataValue",
+        ErrorKind::SlaveDeviceFailure => "SlaveDeviceFailure",
+        ErrorKind::Acknowledge => "Acknowledge",
+        ErrorKind::SlaveDeviceBusy => "SlaveDeviceBusy",
+        ErrorKind::NegativeAcknowledge => "NegativeAcknowledge",
+        ErrorKind::MemoryParityError => "MemoryParityError",
+        ErrorKind::GatewayPathUnavailable => "GatewayPathUnavailable",
+        ErrorKind::GatewayTargetFailed => "GatewayTargetFailed",
+        ErrorKind::CommunicationError => "CommunicationError",
+        ErrorKind::UnknownError => "UnknownError",
+        ErrorKind::Utf8Error => "Utf8Error",
+    }
+}
+
+pub struct Syl2381<UART, CLOCK = clock::NoClock> {
     unit_id: u8,
     port: UART,
+    clock: CLOCK,
+    live: cache::LiveCache,
+    transforms: transform::Transforms,
+    labels: labels::Labels,
+    lockout: lockout::Lockout,
+    writes: eeprom::Writes,
+    verify: bool,
+    strategy: strategy::WriteStrategy,
+    statics: statics::Statics,
+    tally: stats::Tally,
+    observer: Option<FrameObserver>,
 }
 
 impl<UART> Syl2381<UART>
 where
-    UART: embedded_hal::serial::Read<u8> + embedded_hal::serial::Write<u8>,
+    UART: Transport,
 {
     pub fn new(unit_id: u8, port: UART) -> Self {
         Syl2381 {
-            unit_id: unit_id,
-            port: port,
+            unit_id,
+            port,
+            clock: clock::NoClock,
+            live: cache::LiveCache::default(),
+            transforms: transform::Transforms::default(),
+            labels: labels::Labels::default(),
+            lockout: lockout::Lockout::default(),
+            writes: eeprom::Writes::default(),
+            verify: false,
+            strategy: strategy::WriteStrategy::default(),
+            statics: statics::Statics::default(),
+            tally: stats::Tally::default(),
+            observer: None,
+        }
+    }
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: clock::DriverClock,
+{
+    /// Measure time on `clock`, as a [`StaticCache`], a [`WriteGuard`] and
+    /// [`Lockout::Wait`] need to.
+    ///
+    /// Pass `&clock` to keep using the clock elsewhere.
+    pub fn with_clock<C: Clock>(self, clock: C) -> Syl2381<UART, C> {
+        Syl2381 {
+            unit_id: self.unit_id,
+            port: self.port,
+            clock,
+            live: self.live,
+            transforms: self.transforms,
+            labels: self.labels,
+            lockout: self.lockout,
+            writes: self.writes,
+            verify: self.verify,
+            strategy: self.strategy,
+            statics: self.statics,
+            tally: self.tally,
+            observer: self.observer,
         }
     }
 
+    pub fn clock(&self) -> &CLOCK {
+        &self.clock
+    }
+
+    /// The time by the driver's clock, if it has one.
+    fn now(&self) -> Option<core::time::Duration> {
+        self.clock.as_clock().map(|clock| clock.now())
+    }
+
     /// Get the process value (PV).
-    pub fn get_pv(&mut self) -> crate::Result<u16, UART> {
+    pub fn get_pv(&mut self) -> crate::Result<TempRaw, UART> {
         let val = self.get_holding(regs::PV)?;
-        Ok(val as u16)
+        Ok(TempRaw(val))
     }
 
     /// Get the power output percentage (OUT).
-    pub fn get_out(&mut self) -> crate::Result<f32, UART> {
-        self.get_holding(regs::OUT)
+    pub fn get_out(&mut self) -> crate::Result<Percent, UART> {
+        let val = self.get_holding(regs::OUT)?;
+        Ok(Percent(val))
+    }
+
+    /// Get J1 status flag (AL1_STA).
+    pub fn get_j1_status(&mut self) -> crate::Result<bool, UART> {
+        self.get_coil(regs::AL1_STA)
     }
 
-    /// Set the power output percentage (OUT).
+    /// Get the autotuning flag (AT).
     ///
-    /// To set the output value, the control flag (CV) must be set.
-    pub fn set_out(&mut self, val: f32) -> Result<(), UART> {
-        if !(val >= 0.0 && val <= 1.0) {
-            return Err(Error::UnexpectedValue(val));
-        }
-        self.set_holding(regs::OUT, val)
+    /// See [`Syl2381::start_autotune`] and [`Syl2381::abort_autotune`] to
+    /// change it.
+    pub fn get_autotune(&mut self) -> crate::Result<bool, UART> {
+        self.get_coil(regs::AT)
     }
 
-    /// Get J1 status flag (AL1_STA).
-    pub fn get_j1_status(&mut self) -> crate::Result<bool, UART> {
-        let val = self.get_coils(regs::AL1_STA, 1)?;
-        Ok(val & 1 == 1)
+    /// Get the manual mode flag (A/M).
+    pub fn get_manual_mode(&mut self) -> crate::Result<bool, UART> {
+        self.get_coil(regs::AM)
+    }
+
+    /// Switch between manual mode (`true`) and PID control (A/M).
+    pub fn set_manual_mode(&mut self, val: bool) -> crate::Result<(), UART> {
+        self.set_coil(regs::AM, val)
+    }
+
+    /// Get the cooling mode flag.
+    pub fn get_cooling_mode(&mut self) -> crate::Result<bool, UART> {
+        self.get_coil(regs::COOL)
+    }
+
+    /// Get the setting mode flag, set while the front panel is in a menu.
+    pub fn get_setting_mode(&mut self) -> crate::Result<bool, UART> {
+        self.get_coil(regs::SETTING)
+    }
+
+    /// Get the anomaly flag, set on a sensor fault.
+    pub fn get_anomaly(&mut self) -> crate::Result<bool, UART> {
+        self.get_coil(regs::ANOMALY)
     }
 
     /// Get the control flag for OUT (CV).
@@ -509,6 +919,8 @@ where
     /// output percentage, the controller itself will not change it (like manual mode).
     ///
     /// To exit, you can either reboot this controller, or set CV back to 0.
+    ///
+    /// [`Syl2381::enter_manual_output`] takes care of both ends.
     pub fn set_cv(&mut self, val: bool) -> crate::Result<(), UART> {
         let val = if val { 1.0 } else { 0.0 };
         self.set_holding(regs::CV, val)
@@ -521,47 +933,64 @@ where
     }
 
     /// Get the set value (SV).
-    pub fn get_sv(&mut self) -> crate::Result<i16, UART> {
+    pub fn get_sv(&mut self) -> crate::Result<TempRaw, UART> {
         let val = self.get_holding(regs::SV)?;
-        Ok(val as i16)
+        Ok(TempRaw(val))
     }
 
     /// Set the set value (SV).
-    pub fn set_sv(&mut self, val: i16) -> Result<(), UART> {
-        if !(val >= -1999 && val <= 9999) {
-            return Err(Error::UnexpectedValue(val as f32));
+    ///
+    /// Accepts -1999 to 9999, so sub-zero setpoints (e.g. freezers in
+    /// cooling mode) are supported.
+    ///
+    /// A [`Temperature`] is converted to the controller's display unit first.
+    /// Values the input sensor can't reach are rejected with
+    /// [`Error::OutOfRange`]; INTY and CorF are read the first time that's
+    /// checked, and remembered after.
+    pub fn set_sv(&mut self, val: impl Into<AnyTemp>) -> Result<(), UART> {
+        let val = self.raw_temp(val.into())?;
+        if !limits::SV.contains(&val) {
+            return Err(Error::UnexpectedValue(val));
         }
-        let val = val as f32;
+        self.check_sensor_range(val)?;
         self.set_holding(regs::SV, val)
     }
 
     /// Get J1 ON temperature (AH1).
-    pub fn get_j1_on_temp(&mut self) -> crate::Result<i16, UART> {
+    pub fn get_j1_on_temp(&mut self) -> crate::Result<TempRaw, UART> {
         let val = self.get_holding(regs::AH1)?;
-        Ok(val as i16)
+        Ok(TempRaw(val))
     }
 
     /// Set J1 ON temperature (AH1).
-    pub fn set_j1_on_temp(&mut self, val: i16) -> Result<(), UART> {
-        if !(val >= -1999 && val <= 9999) {
-            return Err(Error::UnexpectedValue(val as f32));
+    ///
+    /// Accepts -1999 to 9999, narrowed to the sensor's range like
+    /// [`Syl2381::set_sv`].
+    pub fn set_j1_on_temp(&mut self, val: impl Into<AnyTemp>) -> Result<(), UART> {
+        let val = self.raw_temp(val.into())?;
+        if !limits::AH1.contains(&val) {
+            return Err(Error::UnexpectedValue(val));
         }
-        let val = val as f32;
+        self.check_sensor_range(val)?;
         self.set_holding(regs::AH1, val)
     }
 
     /// Get J1 OFF temperature (AL1).
-    pub fn get_j1_off_temp(&mut self) -> crate::Result<i16, UART> {
+    pub fn get_j1_off_temp(&mut self) -> crate::Result<TempRaw, UART> {
         let val = self.get_holding(regs::AL1)?;
-        Ok(val as i16)
+        Ok(TempRaw(val))
     }
 
     /// Set J1 OFF temperature (AL1).
-    pub fn set_j1_off_temp(&mut self, val: i16) -> Result<(), UART> {
-        if !(val >= -1999 && val <= 9999) {
-            return Err(Error::UnexpectedValue(val as f32));
+    ///
+    /// Accepts -1999 to 9999, narrowed to the sensor's range like
+    /// [`Syl2381::set_sv`].
+    pub fn set_j1_off_temp(&mut self, val: impl Into<AnyTemp>) -> Result<(), UART> {
+        let val = self.raw_temp(val.into())?;
+        if !limits::AL1.contains(&val) {
+            return Err(Error::UnexpectedValue(val));
         }
-        let val = val as f32;
+        self.check_sensor_range(val)?;
         self.set_holding(regs::AL1, val)
     }
 
@@ -572,21 +1001,22 @@ where
 
     /// Get proportional constant (P).
     pub fn set_p(&mut self, val: f32) -> Result<(), UART> {
-        if !(val >= -0.1 && val <= 9999.9) {
+        if !limits::P.contains(&val) {
             return Err(Error::UnexpectedValue(val));
         }
         self.set_holding(regs::P, val)
     }
 
     /// Get integral time (I).
-    pub fn get_i(&mut self) -> crate::Result<u16, UART> {
+    pub fn get_i(&mut self) -> crate::Result<Seconds, UART> {
         let val = self.get_holding(regs::I)?;
-        Ok(val as u16)
+        try_from_f32(val)
     }
 
     /// Set integral time (I).
-    pub fn set_i(&mut self, val: u16) -> Result<(), UART> {
-        if !(val >= 2 && val <= 1999) {
+    pub fn set_i(&mut self, val: Seconds) -> Result<(), UART> {
+        let Seconds(val) = val;
+        if !limits::I.contains(&val) {
             return Err(Error::UnexpectedValue(val as f32));
         }
         let val = val as f32;
@@ -594,14 +1024,15 @@ where
     }
 
     /// Set derivative time (D).
-    pub fn get_d(&mut self) -> crate::Result<u16, UART> {
+    pub fn get_d(&mut self) -> crate::Result<Seconds, UART> {
         let val = self.get_holding(regs::D)?;
-        Ok(val as u16)
+        try_from_f32(val)
     }
 
     /// Set derivative time (D).
-    pub fn set_d(&mut self, val: u16) -> Result<(), UART> {
-        if !(val <= 999) {
+    pub fn set_d(&mut self, val: Seconds) -> Result<(), UART> {
+        let Seconds(val) = val;
+        if !limits::D.contains(&val) {
             return Err(Error::UnexpectedValue(val as f32));
         }
         let val = val as f32;
@@ -609,17 +1040,17 @@ where
     }
 
     /// Get proportional band range limit (BB).
-    pub fn get_bb(&mut self) -> crate::Result<u16, UART> {
+    pub fn get_bb(&mut self) -> crate::Result<TempRaw, UART> {
         let val = self.get_holding(regs::BB)?;
-        Ok(val as u16)
+        Ok(TempRaw(val))
     }
 
     /// Set proportional band range limit (BB).
-    pub fn set_bb(&mut self, val: u16) -> Result<(), UART> {
-        if !(val >= 1 && val <= 1999) {
-            return Err(Error::UnexpectedValue(val as f32));
+    pub fn set_bb(&mut self, val: TempRaw) -> Result<(), UART> {
+        let TempRaw(val) = val;
+        if !limits::BB.contains(&val) {
+            return Err(Error::UnexpectedValue(val));
         }
-        let val = val as f32;
         self.set_holding(regs::BB, val)
     }
 
@@ -629,8 +1060,9 @@ where
     /// improve its control quality. It uses the artificial intelligence to dampen the
     /// temperature overshot. When SouF is set to a small value, the system may
     /// overshoot; when SouF is set to a high value, the system will be over-damped.
-    pub fn get_souf(&mut self) -> crate::Result<f32, UART> {
-        self.get_holding(regs::SOUF)
+    pub fn get_souf(&mut self) -> crate::Result<Percent, UART> {
+        let val = self.get_holding(regs::SOUF)?;
+        Ok(Percent(val))
     }
 
     /// Set the Damp Constant (SouF).
@@ -639,9 +1071,12 @@ where
     /// improve its control quality. It uses the artificial intelligence to dampen the
     /// temperature overshot. When SouF is set to a small value, the system may
     /// overshoot; when SouF is set to a high value, the system will be over-damped.
-    pub fn set_souf(&mut self, val: f32) -> Result<(), UART> {
-        if !(val >= 0.0 && val <= 1.0) {
-            return Err(Error::UnexpectedValue(val as f32));
+    ///
+    /// Accepts 0.0 to 1.0 (0% to 100%).
+    pub fn set_souf(&mut self, val: Percent) -> Result<(), UART> {
+        let Percent(val) = val;
+        if !limits::SOUF.contains(&val) {
+            return Err(Error::UnexpectedValue(val));
         }
         self.set_holding(regs::SOUF, val)
     }
@@ -650,17 +1085,18 @@ where
     ///
     /// This is a time period setting (unit in seconds) that decides how often
     /// does the controller calculate and change its output.
-    pub fn get_control_cycle(&mut self) -> crate::Result<u16, UART> {
+    pub fn get_control_cycle(&mut self) -> crate::Result<Seconds, UART> {
         let val = self.get_holding(regs::OT)?;
-        Ok(val as u16)
+        try_from_f32(val)
     }
 
     /// Set control cycle (OT).
     ///
     /// This is a time period setting (unit in seconds) that decides how often
     /// does the controller calculate and change its output.
-    pub fn set_control_cycle(&mut self, val: u16) -> Result<(), UART> {
-        if !(val >= 1 && val <= 500) {
+    pub fn set_control_cycle(&mut self, val: Seconds) -> Result<(), UART> {
+        let Seconds(val) = val;
+        if !limits::OT.contains(&val) {
             return Err(Error::UnexpectedValue(val as f32));
         }
         let val = val as f32;
@@ -674,7 +1110,7 @@ where
     /// temperature is a time period setting (unit in seconds) that decides how often
     pub fn get_filter(&mut self) -> crate::Result<Filter, UART> {
         let val = self.get_holding(regs::FILT)?;
-        try_from_f32::<_, UART>(val)
+        try_from_f32(val)
     }
 
     /// Set digital filter (FILT).
@@ -690,19 +1126,22 @@ where
     /// Get input sensor type (INTY).
     pub fn get_input_sensor_type(&mut self) -> crate::Result<InputType, UART> {
         let val = self.get_holding(regs::INTY)?;
-        try_from_f32::<_, UART>(val)
+        let input = try_from_f32(val)?;
+        self.live.input_type = Some(input);
+        Ok(input)
     }
 
     /// Set input sensor type (INTY).
     pub fn set_input_sensor_type(&mut self, val: InputType) -> crate::Result<(), UART> {
-        let val = val.into();
-        self.set_holding(regs::INTY, val)
+        self.set_holding(regs::INTY, val.into())?;
+        self.live.input_type = Some(val);
+        Ok(())
     }
 
     /// Get output control mode (OUTY).
     pub fn get_output_mode(&mut self) -> crate::Result<OutputMode, UART> {
         let val = self.get_holding(regs::OUTY)?;
-        try_from_f32::<_, UART>(val)
+        try_from_f32(val)
     }
 
     /// Set output control mode (OUTY).
@@ -714,7 +1153,7 @@ where
     /// Get main output mode (COTY).
     pub fn get_output_type(&mut self) -> crate::Result<OutputType, UART> {
         let val = self.get_holding(regs::COTY)?;
-        try_from_f32::<_, UART>(val)
+        try_from_f32(val)
     }
 
     /// Set main output mode (COTY).
@@ -724,42 +1163,47 @@ where
     }
 
     /// Get hysteresis band (Hy).
-    pub fn get_hysteresis(&mut self) -> crate::Result<u16, UART> {
+    pub fn get_hysteresis(&mut self) -> crate::Result<TempRaw, UART> {
         let val = self.get_holding(regs::HY)?;
-        Ok(val as u16)
+        Ok(TempRaw(val))
     }
 
     /// Set hysteresis band (Hy).
-    pub fn set_hysteresis(&mut self, val: u16) -> Result<(), UART> {
-        if !(val <= 9999) {
-            return Err(Error::UnexpectedValue(val as f32));
+    pub fn set_hysteresis(&mut self, val: TempRaw) -> Result<(), UART> {
+        let TempRaw(val) = val;
+        if !limits::HY.contains(&val) {
+            return Err(Error::UnexpectedValue(val));
         }
-        let val = val as f32;
         self.set_holding(regs::HY, val)
     }
 
     /// Get input offset (PSb).
-    pub fn get_input_offset(&mut self) -> crate::Result<i16, UART> {
+    pub fn get_input_offset(&mut self) -> crate::Result<TempRaw, UART> {
         let val = self.get_holding(regs::PSB)?;
-        Ok(val as i16)
+        Ok(TempRaw(val))
     }
 
     /// Set input offset (PSb).
-    pub fn set_intput_offset(&mut self, val: i16) -> Result<(), UART> {
-        if !(val >= -1000 && val <= 1000) {
-            return Err(Error::UnexpectedValue(val as f32));
+    ///
+    /// Accepts -1000 to 1000.
+    pub fn set_intput_offset(&mut self, val: TempRaw) -> Result<(), UART> {
+        let TempRaw(val) = val;
+        if !limits::PSB.contains(&val) {
+            return Err(Error::UnexpectedValue(val));
         }
-        let val = val as f32;
         self.set_holding(regs::PSB, val)
     }
 
     /// Get control function (rd).
     pub fn get_control_direction(&mut self) -> crate::Result<ControlDirection, UART> {
         let val = self.get_holding(regs::RD)?;
-        try_from_f32::<_, UART>(val)
+        try_from_f32(val)
     }
 
     /// Set control function (rd).
+    ///
+    /// Use [`ControlDirection::Cooling`] for refrigeration, where the output
+    /// turns on when PV rises above SV.
     pub fn set_control_direction(&mut self, val: ControlDirection) -> crate::Result<(), UART> {
         let val = val.into();
         self.set_holding(regs::RD, val)
@@ -768,26 +1212,30 @@ where
     /// Get display unit (CorF).
     pub fn get_display_unit(&mut self) -> crate::Result<DisplayUnit, UART> {
         let val = self.get_holding(regs::CORF)?;
-        try_from_f32::<_, UART>(val)
+        let unit = try_from_f32(val)?;
+        self.live.display_unit = Some(unit);
+        Ok(unit)
     }
 
     /// Set display unit (CorF).
     pub fn set_display_unit(&mut self, val: DisplayUnit) -> crate::Result<(), UART> {
-        let val = val.into();
-        self.set_holding(regs::CORF, val)
+        self.set_holding(regs::CORF, val.into())?;
+        self.live.display_unit = Some(val);
+        Ok(())
     }
 
     /// Get unit ID (Id).
     pub fn get_unit_id(&mut self) -> crate::Result<u8, UART> {
         let val = self.get_holding(regs::ID)?;
-        Ok(val as u8)
+        limits::unit_id(val).ok_or(Error::UnexpectedValue(val))
     }
 
     /// Set unit ID (Id).
     ///
     /// NOTE: This reconfigures the temperature controller to use a different unit ID on the Modbus.
+    /// The driver keeps using the old one; [`change_unit_id`](Self::change_unit_id) follows it.
     pub fn set_unit_id(&mut self, val: u8) -> Result<(), UART> {
-        if !(val <= 64) {
+        if !limits::ID.contains(&val) {
             return Err(Error::UnexpectedValue(val as f32));
         }
         let val = val as f32;
@@ -797,159 +1245,184 @@ where
     /// Get baud rate (bAud).
     pub fn get_baud_rate(&mut self) -> crate::Result<BaudRate, UART> {
         let val = self.get_holding(regs::BAUD)?;
-        try_from_f32::<_, UART>(val)
+        try_from_f32(val)
     }
 
     /// Set baud rate (bAud).
+    ///
+    /// The port stays at the old rate; [`change_baud_rate`](Self::change_baud_rate) switches it
+    /// over too.
     pub fn set_baud_rate(&mut self, val: BaudRate) -> crate::Result<(), UART> {
         let val = val.into();
         self.set_holding(regs::BAUD, val)
     }
 
-    /// ---------------------------
+    // ---------------------------
 
     /// Set holding param.
     ///
     /// All holding params on the SYL-2381 are f32,
     /// encoded as two consecutive values.
-    fn set_holding(&mut self, reg: u16, val: f32) -> Result<(), UART> {
-        let values = f32_to_values(val);
-        let mut mreq = ModbusRequest::new(self.unit_id, ModbusProto::Rtu);
-
-        let mut request: heapless::Vec<u8, 256> = heapless::Vec::new();
-        mreq.generate_set_holdings_bulk(reg, &values, &mut request)?;
-
-        self.write_all(&request)?;
-
-        // reuse request buffer
-        request.clear();
-        let mut response = request;
-
-        // read: addr (byte) + func (byte) + count (byte)
-        let _ = response.resize(3, 0);
-        self.read_exact(&mut response)?;
-
-        let len = guess_response_frame_len(&response, ModbusProto::Rtu)?;
+    fn set_holding(&mut self, reg: regs::Reg, val: f32) -> Result<(), UART> {
+        let val = self.transforms.write(reg, val);
+        self.set_holding_raw(reg, val)
+    }
 
-        let _ = response.resize(len as usize, 0);
-        self.read_exact(&mut response[3..])?;
+    /// Set holding param, bypassing any transform.
+    fn set_holding_raw(&mut self, reg: regs::Reg, val: f32) -> Result<(), UART> {
+        let ctx = Context::new(Operation::WriteHolding, reg);
+        if !self.guard_write(ctx, reg, val)? {
+            return Ok(());
+        }
+        self.check_lockout(ctx)?;
+        self.live.invalidate(reg);
+        self.statics.invalidate(reg);
 
-        mreq.parse_ok(&response)?;
+        self.write_f32(ctx, reg, val)?;
+        self.wrote(reg, val);
 
-        Ok(())
+        self.verify_written(reg, val)
     }
 
     /// Get holding param.
     ///
     /// All holding params on the SYL-2381 are f32,
     /// encoded as two consecutive values.
-    fn get_holding(&mut self, reg: u16) -> Result<f32, UART> {
-        let mut mreq = ModbusRequest::new(self.unit_id, ModbusProto::Rtu);
-
-        let mut request: heapless::Vec<u8, 256> = heapless::Vec::new();
-        mreq.generate_get_holdings(reg, 2, &mut request)?;
-
-        self.write_all(&request)?;
-
-        // reuse request buffer
-        request.clear();
-        let mut response = request;
-
-        // read: addr (byte) + func (byte) + count (byte)
-        let _ = response.resize(3, 0);
-        self.read_exact(&mut response)?;
-
-        let len = guess_response_frame_len(&response, ModbusProto::Rtu)?;
-
-        let _ = response.resize(len as usize, 0);
-        self.read_exact(&mut response[3..])?;
-
-        let mut data: heapless::Vec<u16, 2> = heapless::Vec::new();
-        mreq.parse_u16(&response, &mut data)?;
+    fn get_holding(&mut self, reg: regs::Reg) -> Result<f32, UART> {
+        let val = self.get_holding_raw(reg)?;
+        Ok(self.transforms.read(reg, val))
+    }
 
-        let val = values_to_f32(data[0], data[1]);
+    /// Get holding param, bypassing any transform.
+    fn get_holding_raw(&mut self, reg: regs::Reg) -> Result<f32, UART> {
+        if let Some(val) = self.statics.get(reg, self.now()) {
+            return Ok(val);
+        }
+        let ctx = Context::new(Operation::ReadHolding, reg);
+        let fail = |kind| Error::modbus(ctx, kind);
+        let mut frame = Frame::get_holding(self.unit_id, reg).map_err(fail)?;
+        self.transact(ctx, &mut frame)?;
+        let val = frame.parse_f32().map_err(fail)?;
+        self.writes.read(reg, val);
+        self.statics.read(reg, val, self.now());
 
         Ok(val)
     }
 
+    /// Read back a holding param and check it matches `expected`.
+    fn verify_holding(&mut self, reg: regs::Reg, expected: f32) -> Result<(), UART> {
+        let actual = self.get_holding(reg)?;
+        if actual != expected {
+            return Err(Error::VerificationFailed { expected, actual });
+        }
+        Ok(())
+    }
+
     /// Get `count` coils.
     ///
-    /// We only ever need to read up to 8 consecutive coils from the SYL-2381 (when reading the AT status register),
-    /// so this makes the simplifying assumption that we will only ever get 1 byte back.
-    fn get_coils(&mut self, reg: u16, count: u8) -> crate::Result<u8, UART> {
-        assert!(count <= 8);
-
-        let mut mreq = ModbusRequest::new(self.unit_id, ModbusProto::Rtu);
-
-        let mut request: heapless::Vec<u8, 256> = heapless::Vec::new();
-        mreq.generate_get_coils(reg, count as u16, &mut request)?;
+    /// See [`Frame::parse_coils`] for the single byte limitation.
+    fn get_coils(&mut self, reg: regs::Reg, count: u8) -> crate::Result<u8, UART> {
+        let ctx = Context::new(Operation::ReadCoils, reg);
+        let fail = |kind| Error::modbus(ctx, kind);
+        let mut frame = Frame::get_coils(self.unit_id, reg.addr, count).map_err(fail)?;
+        self.transact(ctx, &mut frame)?;
+        let val = frame.parse_coils().map_err(fail)?;
 
-        self.write_all(&request)?;
-
-        // reuse request buffer for response
-        request.clear();
-        let mut response = request;
-
-        // read: addr (byte) + func (byte) + count (byte)
-        let _ = response.resize(3, 0);
-        self.read_exact(&mut response)?;
-
-        let len = guess_response_frame_len(&response, ModbusProto::Rtu)?;
-
-        let _ = response.resize(len as usize, 0);
-        self.read_exact(&mut response[3..])?;
-        // println!("response buffer: {:02X?}", response);
+        Ok(val)
+    }
 
-        // ensure the response frame was well formed
-        mreq.parse_ok(&response)?;
+    /// Read a single coil.
+    fn get_coil(&mut self, reg: regs::Reg) -> crate::Result<bool, UART> {
+        let val = self.get_coils(reg, 1)?;
+        Ok(val & 1 == 1)
+    }
 
-        // As mentioned earlier, only expecting one byte.
-        // TODO: new error variant?
-        let byte_count = response[2];
-        if byte_count != 1 {
-            // this should never happen
-            return Ok(0);
-        }
+    /// Set a single coil.
+    fn set_coil(&mut self, reg: regs::Reg, on: bool) -> crate::Result<(), UART> {
+        let ctx = Context::new(Operation::WriteCoil, reg);
+        self.check_lockout(ctx)?;
+        self.live.invalidate(reg);
+        let fail = |kind| Error::modbus(ctx, kind);
+        let mut frame = Frame::set_coil(self.unit_id, reg.addr, on).map_err(fail)?;
+        self.transact(ctx, &mut frame)?;
+        frame.parse_ok().map_err(fail)?;
+        self.acknowledged(reg.addr, AckValue::Coil(on));
 
-        // instead of using mreq.parse_bool, which fills a vec of bools,
-        // we'll just grab the byte directly.
-        // TODO: make this work also work for non-RTU
-        let val = response[3];
+        self.verify_coil(reg, on)
+    }
 
-        Ok(val)
+    /// Pass an acknowledged write on to the transport.
+    fn acknowledged(&mut self, addr: u16, value: AckValue) {
+        let ack = Ack {
+            unit_id: self.unit_id,
+            addr,
+            value,
+        };
+        self.port.acknowledged(&ack);
     }
 
-    fn read_exact(&mut self, buf: &mut [u8]) -> crate::Result<(), UART> {
-        for i in 0..buf.len() {
-            let b = nb::block!(self.port.read()).map_err(|err| Error::SerialError(err))?;
-            buf[i] = b
-        }
-        Ok(())
+    /// Address `unit_id` from now on, dropping everything cached about the
+    /// unit addressed before.
+    pub(crate) fn readdress(&mut self, unit_id: u8) {
+        self.unit_id = unit_id;
+        self.live.clear();
+        self.statics.clear();
+        self.writes.clear();
     }
 
-    fn write_all(&mut self, buf: &[u8]) -> crate::Result<(), UART> {
-        for &b in buf {
-            nb::block!(self.port.write(b)).map_err(|err| Error::SerialError(err))?;
+    /// Send the request and receive the response.
+    fn transact(&mut self, ctx: Context, frame: &mut Frame) -> crate::Result<(), UART> {
+        if let Some(observer) = self.observer {
+            (observer.on_tx)(frame.request());
         }
+        self.tally.sent(frame.request());
+        let received = self.exchange(ctx, frame);
+        self.tally.received(&received, frame);
+        if let (Some(observer), Ok(())) = (self.observer, &received) {
+            (observer.on_rx)(frame.response());
+        }
+        received
+    }
+
+    fn exchange(&mut self, ctx: Context, frame: &mut Frame) -> crate::Result<(), UART> {
+        let port = &mut self.port;
+        let io_error = |err| transport_error::<UART>(ctx, err);
+        port.write_frame(frame.request()).map_err(io_error)?;
+        port.flush().map_err(io_error)?;
+        port.read_exact(frame.response_header()).map_err(io_error)?;
+        let mut skipped = 0;
+        while let Some(next) = frame.resync_header() {
+            if skipped == frame::RESYNC_WINDOW {
+                return Err(Error::ModbusError(ctx, rmodbus::ErrorKind::FrameBroken));
+            }
+            skipped += 1;
+            port.read_exact(next).map_err(io_error)?;
+        }
+        let body = frame
+            .response_body()
+            .map_err(|kind| Error::modbus(ctx, kind))?;
+        port.read_exact(body).map_err(io_error)?;
 
         Ok(())
     }
 }
 
-pub type Result<T, UART> =
-    core::result::Result<T, Error<<UART as embedded_hal::serial::ErrorType>::Error>>;
+pub type Result<T, UART> = core::result::Result<T, Error<<UART as Transport>::Error>>;
+
+fn transport_error<UART: Transport>(ctx: Context, err: UART::Error) -> Error<UART::Error> {
+    if UART::is_timeout(&err) {
+        Error::Timeout(ctx)
+    } else {
+        Error::SerialError(ctx, err)
+    }
+}
 
 #[inline(always)]
-fn try_from_f32<T, UART>(val: f32) -> crate::Result<T, UART>
+fn try_from_f32<T, E>(val: f32) -> core::result::Result<T, Error<E>>
 where
     T: TryFrom<f32>,
-    UART: embedded_hal::serial::ErrorType,
 {
-    let v = T::try_from(val)
-        .map(|v| Ok(v))
-        .unwrap_or(Err(Error::UnexpectedValue(val)))?;
-
-    Ok(v)
+    T::try_from(val).map_err(|_| Error::UnexpectedValue(val))
 }
 
 /// Read an f32 from two consecutive holding register values.
@@ -974,7 +1447,12 @@ fn f32_to_values(val: f32) -> [u16; 2] {
 #[cfg(test)]
 mod tests {
     use crate::f32_to_values;
+    use crate::mock::{frame, MockSerial};
+    use crate::regs::{self, Kind};
     use crate::values_to_f32;
+    use crate::{
+        Context, DisplayUnit, Error, InputType, Operation, Param, ParamValue, Syl2381, TempRaw,
+    };
 
     #[test]
     fn f32_representation_roundtrips() {
@@ -999,4 +1477,323 @@ mod tests {
         let vals = f32_to_values(val);
         assert_eq!(vals, [0x461C, 0x4000]);
     }
+
+    #[test]
+    fn f32_negative_roundtrips() {
+        for f in [-0.5, -18.5, -40.0, -1000.0, -1999.0] {
+            let [d0, d1] = f32_to_values(f);
+            assert_eq!(values_to_f32(d0, d1), f);
+        }
+
+        // -40.0 encoded as two holding register values:
+        assert_eq!(f32_to_values(-40.0), [0xC220, 0x0000]);
+    }
+
+    #[test]
+    fn negative_setpoint_write() {
+        let mut port = MockSerial::new();
+        port.ack_f32(1, regs::SV.addr);
+        let mut pid = Syl2381::new(1, port);
+        pid.live.assume_sensor(InputType::K, DisplayUnit::Celsius);
+
+        assert!(pid.set_sv(TempRaw(-40.0)).is_ok());
+        assert_eq!(
+            pid.port.tx,
+            frame(&[0x01, 0x10, 0x00, 0x00, 0x00, 0x02, 0x04, 0xC2, 0x20, 0x00, 0x00])
+        );
+    }
+
+    #[test]
+    fn negative_values_read() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, -18.5);
+        port.respond_f32(1, -25.0);
+        port.respond_f32(1, -30.0);
+        port.respond_f32(1, -1000.0);
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(matches!(pid.get_sv(), Ok(TempRaw(v)) if v == -18.5));
+        assert!(matches!(pid.get_j1_on_temp(), Ok(TempRaw(v)) if v == -25.0));
+        assert!(matches!(pid.get_j1_off_temp(), Ok(TempRaw(v)) if v == -30.0));
+        assert!(matches!(pid.get_input_offset(), Ok(TempRaw(v)) if v == -1000.0));
+    }
+
+    #[test]
+    fn times_must_be_whole_seconds() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 120.5);
+        port.respond_f32(1, -1.0);
+        port.respond_f32(1, f32::NAN);
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(matches!(pid.get_i(), Err(Error::UnexpectedValue(v)) if v == 120.5));
+        assert!(matches!(pid.get_d(), Err(Error::UnexpectedValue(v)) if v == -1.0));
+        assert!(matches!(pid.get_control_cycle(), Err(Error::UnexpectedValue(v)) if v.is_nan()));
+    }
+
+    #[test]
+    fn unit_ids_must_be_whole_and_in_range() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 64.0);
+        port.respond_f32(1, 65.0);
+        port.respond_f32(1, 7.5);
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(matches!(pid.get_unit_id(), Ok(64)));
+        assert!(matches!(pid.get_unit_id(), Err(Error::UnexpectedValue(v)) if v == 65.0));
+        assert!(matches!(pid.get_unit_id(), Err(Error::UnexpectedValue(v)) if v == 7.5));
+    }
+
+    #[test]
+    fn pv_keeps_sign_and_fraction() {
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x03, 4, 0x42, 0x8E, 0xCC, 0xCD]); // 71.4 on P10.0
+        port.respond_f32(1, -12.5);
+        port.respond_f32(1, -199.9);
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(matches!(pid.get_pv(), Ok(TempRaw(v)) if v == 71.4));
+        assert!(matches!(pid.get_pv(), Ok(TempRaw(v)) if v == -12.5));
+        assert!(matches!(pid.get_pv(), Ok(TempRaw(v)) if v == -199.9));
+    }
+
+    #[test]
+    fn setpoints_keep_tenths() {
+        let mut port = MockSerial::new();
+        port.ack_f32(1, regs::SV.addr);
+        port.ack_f32(1, regs::AH1.addr);
+        port.ack_f32(1, regs::AL1.addr);
+        port.respond_f32(1, 62.5);
+        let mut pid = Syl2381::new(1, port);
+        pid.live.assume_sensor(InputType::K, DisplayUnit::Celsius);
+
+        assert!(pid.set_sv(TempRaw(62.5)).is_ok());
+        assert!(pid.set_j1_on_temp(TempRaw(63.1)).is_ok());
+        assert!(pid.set_j1_off_temp(TempRaw(61.9)).is_ok());
+        assert!(matches!(pid.get_sv(), Ok(TempRaw(v)) if v == 62.5));
+        assert_eq!(pid.port.tx[7..11], 62.5f32.to_be_bytes());
+        assert_eq!(pid.port.tx[13 + 7..13 + 11], 63.1f32.to_be_bytes());
+        assert_eq!(pid.port.tx[26 + 7..26 + 11], 61.9f32.to_be_bytes());
+    }
+
+    #[test]
+    fn negative_range_limits() {
+        let mut port = MockSerial::new();
+        port.ack_f32(1, regs::SV.addr);
+        port.ack_f32(1, regs::AH1.addr);
+        port.ack_f32(1, regs::AL1.addr);
+        port.ack_f32(1, regs::PSB.addr);
+        let mut pid = Syl2381::new(1, port);
+        pid.live.assume_sensor(InputType::K, DisplayUnit::Celsius);
+
+        assert!(pid.set_sv(TempRaw(-200.0)).is_ok());
+        assert!(pid.set_j1_on_temp(TempRaw(-200.0)).is_ok());
+        assert!(pid.set_j1_off_temp(TempRaw(-200.0)).is_ok());
+        assert!(pid.set_intput_offset(TempRaw(-1000.0)).is_ok());
+
+        // Within what the controller takes, but not what a K thermocouple
+        // reaches.
+        assert!(matches!(
+            pid.set_sv(TempRaw(-1999.0)),
+            Err(Error::OutOfRange { .. })
+        ));
+
+        assert!(matches!(
+            pid.set_sv(TempRaw(-2000.0)),
+            Err(Error::UnexpectedValue(_))
+        ));
+        assert!(matches!(
+            pid.set_j1_on_temp(TempRaw(-1999.5)),
+            Err(Error::UnexpectedValue(_))
+        ));
+        assert!(matches!(
+            pid.set_j1_off_temp(TempRaw(-2000.0)),
+            Err(Error::UnexpectedValue(_))
+        ));
+        assert!(matches!(
+            pid.set_intput_offset(TempRaw(-1000.5)),
+            Err(Error::UnexpectedValue(_))
+        ));
+    }
+
+    #[test]
+    fn coil_accessors() {
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x01, 1, 0x01]);
+        port.respond(&[1, 0x05, 0x00, 0x01, 0xFF, 0x00]);
+        port.respond(&[1, 0x01, 1, 0x00]);
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(matches!(pid.get_manual_mode(), Ok(true)));
+        assert!(pid.set_manual_mode(true).is_ok());
+        assert!(matches!(pid.get_anomaly(), Ok(false)));
+        assert_eq!(pid.port.tx[..8], frame(&[1, 0x01, 0x00, 0x01, 0x00, 0x01]));
+        assert_eq!(
+            pid.port.tx[8..16],
+            frame(&[1, 0x05, 0x00, 0x01, 0xFF, 0x00])
+        );
+        assert_eq!(pid.port.tx[16..], frame(&[1, 0x01, 0x00, 0x04, 0x00, 0x01]));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrips() {
+        use crate::{InputType, Status};
+
+        let json = serde_json::to_string(&InputType::P100).unwrap();
+        assert_eq!(json, "\"P100\"");
+        assert!(matches!(
+            serde_json::from_str::<InputType>(&json),
+            Ok(InputType::P100)
+        ));
+
+        let status: Status = serde_json::from_str("32").unwrap();
+        assert!(status.alarm1());
+    }
+
+    #[test]
+    fn decodes_exceptions() {
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x83, 0x06]);
+        port.respond(&[1, 0x90, 0x02]);
+        port.respond(&[1, 0x83, 0x08]);
+        let mut pid = Syl2381::new(1, port);
+        pid.live.assume_sensor(InputType::K, DisplayUnit::Celsius);
+
+        assert!(matches!(pid.get_pv(), Err(Error::DeviceBusy(_))));
+        let err = pid.set_sv(TempRaw(65.0)).unwrap_err();
+        assert!(matches!(err, Error::IllegalDataAddress(_)));
+        assert_eq!(
+            err.context(),
+            Some(Context {
+                op: Operation::WriteHolding,
+                addr: regs::SV.addr
+            })
+        );
+        assert_eq!(err.to_string(), "WriteHolding 0x0000: illegal data address");
+        assert!(matches!(
+            pid.get_pv(),
+            Err(Error::ModbusError(_, rmodbus::ErrorKind::MemoryParityError))
+        ));
+    }
+
+    #[test]
+    fn skips_noise_before_response() {
+        let mut port = MockSerial::new();
+        port.rx.extend([0x00, 0xFF, 0x01]);
+        port.respond_f32(1, 25.0);
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(matches!(pid.get_pv(), Ok(TempRaw(v)) if v == 25.0));
+        assert!(pid.port.rx.is_empty());
+    }
+
+    /// Frames written out byte for byte, CRC included, so they don't lean on
+    /// the mock's own CRC.
+    #[test]
+    fn literal_frames() {
+        let mut port = MockSerial::new();
+        port.rx
+            .extend([0x01, 0x03, 0x04, 0x42, 0x82, 0x00, 0x00, 0x4E, 0x63]);
+        port.rx
+            .extend([0x01, 0x10, 0x00, 0x00, 0x00, 0x02, 0x41, 0xC8]);
+        port.rx.extend([0x01, 0x01, 0x01, 0x22, 0xD1, 0x91]);
+        port.rx
+            .extend([0x01, 0x05, 0x00, 0x00, 0xFF, 0x00, 0x8C, 0x3A]);
+        let mut pid = Syl2381::new(1, port);
+        pid.live.assume_sensor(InputType::K, DisplayUnit::Celsius);
+
+        assert!(matches!(pid.get_sv(), Ok(TempRaw(v)) if v == 65.0));
+        assert!(pid.set_sv(TempRaw(65.0)).is_ok());
+        let status = pid.get_status().unwrap();
+        assert!(status.alarm1() && status.manual_mode() && !status.anomaly());
+        assert!(pid.start_autotune().is_ok());
+        assert_eq!(
+            pid.port.tx,
+            [
+                0x01, 0x03, 0x00, 0x00, 0x00, 0x02, 0xC4, 0x0B, // get SV
+                0x01, 0x10, 0x00, 0x00, 0x00, 0x02, 0x04, 0x42, 0x82, 0x00, 0x00, 0x47,
+                0xFF, // set SV
+                0x01, 0x01, 0x00, 0x00, 0x00, 0x08, 0x3D, 0xCC, // status coils
+                0x01, 0x05, 0x00, 0x00, 0xFF, 0x00, 0x8C, 0x3A, // set AT
+            ]
+        );
+    }
+
+    #[test]
+    fn coil_responses_are_one_byte() {
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x01, 2, 0x22, 0x00]);
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(matches!(
+            pid.get_status(),
+            Err(Error::ModbusError(_, rmodbus::ErrorKind::FrameBroken))
+        ));
+    }
+
+    /// Every typed getter, through [`Syl2381::get_param`].
+    #[test]
+    fn every_getter_on_the_wire() {
+        for param in Param::ALL {
+            let reg = param.reg();
+            let [hi, lo] = reg.addr.to_be_bytes();
+            let mut port = MockSerial::new();
+            match reg.kind() {
+                Kind::Holding => port.respond_f32(1, 1.0),
+                Kind::Coil => port.respond(&[1, 0x01, 1, 0x01]),
+            }
+            let mut pid = Syl2381::new(1, port);
+
+            let val = pid.get_param(param).unwrap();
+            assert_eq!(f32::from(val), 1.0, "{}", param);
+            let request = match reg.kind() {
+                Kind::Holding => frame(&[1, 0x03, hi, lo, 0x00, 0x02]),
+                Kind::Coil => frame(&[1, 0x01, hi, lo, 0x00, 0x01]),
+            };
+            assert_eq!(pid.port.tx, request, "{}", param);
+            assert!(pid.port.rx.is_empty(), "{}", param);
+        }
+    }
+
+    /// Every typed setter, through [`Syl2381::set_param`].
+    #[test]
+    fn every_setter_on_the_wire() {
+        for param in Param::ALL.into_iter().filter(|p| p.writable()) {
+            let reg = param.reg();
+            let [hi, lo] = reg.addr.to_be_bytes();
+            let stored = param.meta().min.max(1.0);
+            let mut port = MockSerial::new();
+            let request = match reg.kind() {
+                Kind::Holding => {
+                    port.ack_f32(1, reg.addr);
+                    let [b0, b1, b2, b3] = stored.to_be_bytes();
+                    frame(&[1, 0x10, hi, lo, 0x00, 0x02, 4, b0, b1, b2, b3])
+                }
+                Kind::Coil => {
+                    port.respond(&[1, 0x05, hi, lo, 0xFF, 0x00]);
+                    frame(&[1, 0x05, hi, lo, 0xFF, 0x00])
+                }
+            };
+            let mut pid = Syl2381::new(1, port);
+            pid.live.assume_sensor(InputType::K, DisplayUnit::Celsius);
+
+            let val = ParamValue::from_stored(param, stored).unwrap();
+            assert!(pid.set_param(param, val).is_ok(), "{}", param);
+            assert_eq!(pid.port.tx, request, "{}", param);
+            assert!(pid.port.rx.is_empty(), "{}", param);
+        }
+    }
+
+    #[test]
+    fn gives_up_on_endless_noise() {
+        let mut port = MockSerial::new();
+        port.rx.extend([0xFF; 32]);
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(matches!(
+            pid.get_pv(),
+            Err(Error::ModbusError(_, rmodbus::ErrorKind::FrameBroken))
+        ));
+    }
 }
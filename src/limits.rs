@@ -0,0 +1,25 @@
+//! Valid ranges for writable parameters, shared by every driver front end.
+
+use core::ops::RangeInclusive;
+
+pub const OUT: RangeInclusive<f32> = 0.0..=1.0;
+pub const SV: RangeInclusive<f32> = -1999.0..=9999.0;
+pub const AH1: RangeInclusive<f32> = -1999.0..=9999.0;
+pub const AL1: RangeInclusive<f32> = -1999.0..=9999.0;
+pub const P: RangeInclusive<f32> = -0.1..=9999.9;
+pub const I: RangeInclusive<u16> = 2..=1999;
+pub const D: RangeInclusive<u16> = 0..=999;
+pub const BB: RangeInclusive<f32> = 1.0..=1999.0;
+pub const SOUF: RangeInclusive<f32> = 0.0..=1.0;
+pub const OT: RangeInclusive<u16> = 1..=500;
+pub const HY: RangeInclusive<f32> = 0.0..=9999.0;
+pub const PSB: RangeInclusive<f32> = -1000.0..=1000.0;
+pub const ID: RangeInclusive<u8> = 0..=64;
+
+/// The unit ID stored as `val`, if it's a whole number within [`ID`].
+pub(crate) fn unit_id(val: f32) -> Option<u8> {
+    // `as` saturates (and takes NaN to 0), so anything that isn't a whole
+    // number in range won't make the round trip.
+    let id = val as u8;
+    (id as f32 == val && ID.contains(&id)).then_some(id)
+}
@@ -0,0 +1,164 @@
+//! Writing while someone is in the front panel menu.
+//!
+//! The controller won't take writes while its setting menu is open (the
+//! setting mode status flag). By default the driver writes anyway and leaves
+//! the controller to reject it; a [`Lockout`] policy checks the flag first.
+
+use core::fmt;
+use core::time::Duration;
+
+use crate::clock::DriverClock;
+use crate::transport::Transport;
+use crate::{Context, Error, Syl2381};
+
+/// What to do about writes while the controller is in setting mode.
+#[derive(Clone, Copy, fmt::Debug, Default)]
+pub enum Lockout {
+    /// Write without checking.
+    #[default]
+    Ignore,
+
+    /// Fail with [`Error::DeviceInSettingMode`].
+    Fail,
+
+    /// Check again every `poll` (at least [`Lockout::MIN_POLL`]), sleeping on
+    /// the driver's clock, and fail with [`Error::DeviceInSettingMode`] once
+    /// `timeout` has passed by it. A driver without a clock (see
+    /// [`Syl2381::with_clock`]) fails at once, as under [`Lockout::Fail`].
+    Wait { poll: Duration, timeout: Duration },
+}
+
+impl Lockout {
+    /// Shortest wait between two checks under [`Lockout::Wait`].
+    pub const MIN_POLL: Duration = Duration::from_millis(1);
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    pub fn lockout(&self) -> Lockout {
+        self.lockout
+    }
+
+    /// Check the setting mode flag before every write, as `policy` says.
+    ///
+    /// Each check is an extra read of the status coils.
+    pub fn set_lockout(&mut self, policy: Lockout) {
+        self.lockout = policy;
+    }
+
+    /// Apply the lockout policy ahead of the write described by `ctx`.
+    pub(crate) fn check_lockout(&mut self, ctx: Context) -> crate::Result<(), UART> {
+        let (poll, timeout) = match self.lockout {
+            Lockout::Ignore => return Ok(()),
+            Lockout::Fail => (Duration::ZERO, Duration::ZERO),
+            Lockout::Wait { poll, timeout } => (poll.max(Lockout::MIN_POLL), timeout),
+        };
+
+        let start = self.now();
+        while self.get_status()?.setting_mode() {
+            match (start, self.clock.as_clock()) {
+                (Some(start), Some(clock)) if clock.now().saturating_sub(start) < timeout => {
+                    clock.sleep(poll)
+                }
+                _ => return Err(Error::DeviceInSettingMode(ctx)),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::VirtualClock;
+    use crate::mock::MockSerial;
+    use crate::{regs, Operation, TempRaw};
+
+    const MENU: &[u8] = &[1, 0x01, 1, 0b1000];
+    const IDLE: &[u8] = &[1, 0x01, 1, 0b0000];
+
+    #[test]
+    fn fails_while_in_the_menu() {
+        let mut port = MockSerial::new();
+        port.respond(MENU);
+        let mut pid = Syl2381::new(1, port);
+        pid.live
+            .assume_sensor(crate::InputType::K, crate::DisplayUnit::Celsius);
+        pid.set_lockout(Lockout::Fail);
+
+        assert!(matches!(
+            pid.set_sv(TempRaw(65.0)),
+            Err(Error::DeviceInSettingMode(ctx)) if ctx.op == Operation::WriteHolding
+        ));
+        assert_eq!(pid.port.tx.len(), 8);
+    }
+
+    #[test]
+    fn waits_for_the_menu_to_close() {
+        let mut port = MockSerial::new();
+        port.respond(MENU);
+        port.respond(MENU);
+        port.respond(IDLE);
+        port.ack_f32(1, regs::SV.addr);
+        for _ in 0..3 {
+            port.respond(MENU);
+        }
+        let mut pid = Syl2381::new(1, port).with_clock(VirtualClock::new());
+        pid.live
+            .assume_sensor(crate::InputType::K, crate::DisplayUnit::Celsius);
+        pid.set_lockout(Lockout::Wait {
+            poll: Duration::from_millis(500),
+            timeout: Duration::from_secs(1),
+        });
+
+        assert!(pid.set_sv(TempRaw(65.0)).is_ok());
+        assert!(matches!(
+            pid.start_autotune(),
+            Err(Error::DeviceInSettingMode(ctx)) if ctx.op == Operation::WriteCoil
+        ));
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn waits_at_least_min_poll() {
+        let mut port = MockSerial::new();
+        for _ in 0..4 {
+            port.respond(MENU);
+        }
+        let mut pid = Syl2381::new(1, port).with_clock(VirtualClock::new());
+        pid.live
+            .assume_sensor(crate::InputType::K, crate::DisplayUnit::Celsius);
+        pid.set_lockout(Lockout::Wait {
+            poll: Duration::ZERO,
+            timeout: Lockout::MIN_POLL * 3,
+        });
+
+        assert!(matches!(
+            pid.set_sv(TempRaw(65.0)),
+            Err(Error::DeviceInSettingMode(_))
+        ));
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn gives_up_at_once_without_a_clock() {
+        let mut port = MockSerial::new();
+        port.respond(MENU);
+        let mut pid = Syl2381::new(1, port);
+        pid.live
+            .assume_sensor(crate::InputType::K, crate::DisplayUnit::Celsius);
+        pid.set_lockout(Lockout::Wait {
+            poll: Duration::from_millis(500),
+            timeout: Duration::from_secs(1),
+        });
+
+        assert!(matches!(
+            pid.set_sv(TempRaw(65.0)),
+            Err(Error::DeviceInSettingMode(_))
+        ));
+        assert!(pid.port.rx.is_empty());
+    }
+}
@@ -0,0 +1,316 @@
+//! Fixed-size log of recent readings.
+//!
+//! A post-run report wants the last few minutes at full rate: what PV did
+//! against SV, how hard the output worked, and when the alarm went off. A
+//! [`Logger`] keeps the newest `N` [`Record`]s in the same full-rate tier as
+//! [`History`](crate::history::History), dropping the oldest once full, and
+//! summarizes any stretch of them. Memory use is fixed at compile time.
+//! Like [`Sample`](crate::sample::Sample)s, records note their reading's
+//! [`Provenance`], so suspect ones can be left out downstream.
+//!
+//! With the `alloc` feature, a run too long for any fixed `N` can be kept in a
+//! `Vec` (see [`Logger::to_vec`]) and summarized with [`summarize`].
+
+use core::fmt;
+use core::ops::RangeBounds;
+use core::time::Duration;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::clock::{Clock, DriverClock};
+use crate::history::Recent;
+use crate::sample::Provenance;
+use crate::transport::Transport;
+use crate::{Labels, Percent, Status, Syl2381, TempRaw};
+
+/// One logged reading.
+#[derive(Clone, Copy, fmt::Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Record {
+    /// When the reading was taken, on the logging clock.
+    pub at: Duration,
+    pub pv: TempRaw,
+    pub out: Percent,
+    pub sv: TempRaw,
+    pub status: Status,
+
+    /// Always bulk: PV and OUT are read together.
+    pub provenance: Provenance,
+}
+
+impl Record {
+    /// Read PV and OUT (one request), SV and the status flags, as taken at
+    /// `at`. With no clock to go by, the provenance's latency is left at
+    /// zero; [`Logger::log`] measures it.
+    pub fn read<UART, CLOCK>(
+        pid: &mut Syl2381<UART, CLOCK>,
+        at: Duration,
+    ) -> crate::Result<Record, UART>
+    where
+        UART: Transport,
+        CLOCK: DriverClock,
+    {
+        let before = pid.stats();
+        let (pv, out) = pid.get_pv_out()?;
+        let sv = pid.get_sv()?;
+        let status = pid.get_status()?;
+        Ok(Record {
+            at,
+            pv,
+            out,
+            sv,
+            status,
+            provenance: Provenance::between(before, pid.stats(), true),
+        })
+    }
+}
+
+/// A numeric value in a [`Record`].
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Field {
+    Pv,
+    Out,
+    Sv,
+}
+
+impl Field {
+    pub fn of(self, record: &Record) -> f32 {
+        match self {
+            Field::Pv => record.pv.0,
+            Field::Out => record.out.0,
+            Field::Sv => record.sv.0,
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <Self as fmt::Debug>::fmt(self, f)
+    }
+}
+
+/// Min, max and mean of one field over a stretch of records.
+#[derive(Clone, Copy, fmt::Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Summary {
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    pub count: u32,
+}
+
+/// `min 60, max 65, mean 62.5 (4 records)`
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "min {}, max {}, mean {} ({} records)",
+            self.min, self.max, self.mean, self.count
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct Logger<const N: usize> {
+    labels: Labels,
+    records: Recent<Record, N>,
+}
+
+impl<const N: usize> Logger<N> {
+    pub fn new() -> Self {
+        Logger {
+            labels: Labels::default(),
+            records: Recent::new(),
+        }
+    }
+
+    /// Labels of the controller the records come from, as of the last
+    /// [`Logger::log`].
+    pub fn labels(&self) -> &Labels {
+        &self.labels
+    }
+
+    /// Label records added with [`Logger::push`].
+    pub fn set_labels(&mut self, labels: Labels) {
+        self.labels = labels;
+    }
+
+    /// Add a record, dropping the oldest if full. Records must be pushed in
+    /// time order.
+    pub fn push(&mut self, record: Record) {
+        self.records.push(record);
+    }
+
+    /// [`Record::read`] at `clock`'s current time, and log it under the
+    /// controller's labels.
+    pub fn log<UART, CLOCK>(
+        &mut self,
+        pid: &mut Syl2381<UART, CLOCK>,
+        clock: &impl Clock,
+    ) -> crate::Result<Record, UART>
+    where
+        UART: Transport,
+        CLOCK: DriverClock,
+    {
+        let at = clock.now();
+        let mut record = Record::read(pid, at)?;
+        record.provenance.latency = clock.now().saturating_sub(at);
+        if self.labels != *pid.labels() {
+            self.labels = pid.labels().clone();
+        }
+        self.push(record);
+        Ok(record)
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    /// The newest record.
+    pub fn latest(&self) -> Option<&Record> {
+        self.records.latest()
+    }
+
+    /// Every record, oldest first.
+    pub fn records(&self) -> impl Iterator<Item = &Record> {
+        self.records.iter()
+    }
+
+    /// Records taken within `window`, oldest first.
+    pub fn window(&self, window: impl RangeBounds<Duration>) -> impl Iterator<Item = &Record> {
+        self.records.iter().filter(move |r| window.contains(&r.at))
+    }
+
+    /// Summarize `field` over the records taken within `window`.
+    ///
+    /// NaN readings are left out; `None` if nothing is left.
+    pub fn summary(&self, field: Field, window: impl RangeBounds<Duration>) -> Option<Summary> {
+        summarize(field, self.window(window))
+    }
+
+    /// Every record, oldest first, copied out to grow or keep past the next
+    /// [`Logger::clear`].
+    #[cfg(feature = "alloc")]
+    pub fn to_vec(&self) -> Vec<Record> {
+        self.records().copied().collect()
+    }
+}
+
+/// Summarize `field` over `records`, however they're kept.
+///
+/// NaN readings are left out; `None` if nothing is left.
+pub fn summarize<'a>(
+    field: Field,
+    records: impl IntoIterator<Item = &'a Record>,
+) -> Option<Summary> {
+    let mut summary: Option<Summary> = None;
+    let mut sum = 0.0;
+    for val in records.into_iter().map(|r| field.of(r)) {
+        if val.is_nan() {
+            continue;
+        }
+        sum += val;
+        let s = summary.get_or_insert(Summary {
+            min: val,
+            max: val,
+            mean: 0.0,
+            count: 0,
+        });
+        s.min = s.min.min(val);
+        s.max = s.max.max(val);
+        s.count += 1;
+    }
+    summary.map(|s| Summary {
+        mean: sum / s.count as f32,
+        ..s
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::VirtualClock;
+    use crate::mock::MockSerial;
+
+    fn record(secs: u64, pv: f32) -> Record {
+        Record {
+            at: Duration::from_secs(secs),
+            pv: TempRaw(pv),
+            out: Percent(0.5),
+            sv: TempRaw(65.0),
+            status: Status(0),
+            provenance: Provenance::default(),
+        }
+    }
+
+    #[test]
+    fn keeps_the_newest_and_summarizes_windows() {
+        let mut log: Logger<4> = Logger::new();
+        for (secs, pv) in [(0, 50.0), (1, 60.0), (2, f32::NAN), (3, 62.0), (4, 66.0)] {
+            log.push(record(secs, pv));
+        }
+
+        assert_eq!(log.len(), 4);
+        assert_eq!(log.records().next().unwrap().at, Duration::from_secs(1));
+        let all = log.summary(Field::Pv, ..).unwrap();
+        assert_eq!(
+            all.to_string(),
+            "min 60, max 66, mean 62.666668 (3 records)"
+        );
+        let late = log.summary(Field::Pv, Duration::from_secs(3)..).unwrap();
+        assert_eq!((late.min, late.max, late.count), (62.0, 66.0, 2));
+        assert!(log.summary(Field::Sv, Duration::from_secs(9)..).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn grows_past_the_ring() {
+        let mut log: Logger<2> = Logger::new();
+        let mut run = Vec::new();
+        for secs in 0..6 {
+            log.push(record(secs, secs as f32));
+            if log.len() == 2 {
+                run.extend(log.to_vec());
+                log.clear();
+            }
+        }
+
+        assert_eq!(run.len(), 6);
+        let all = summarize(Field::Pv, &run).unwrap();
+        assert_eq!((all.min, all.max, all.mean), (0.0, 5.0, 2.5));
+    }
+
+    #[test]
+    fn logs_from_the_driver() {
+        let clock = VirtualClock::new();
+        clock.advance(Duration::from_secs(5));
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x03, 8, 0x42, 0x70, 0, 0, 0x3E, 0x80, 0, 0]);
+        port.respond_f32(1, 65.0);
+        port.respond(&[1, 0x01, 1, 0b10_0000]);
+        let mut pid = Syl2381::new(1, port);
+        pid.set_labels(Labels::new("Kiln"));
+        let mut log: Logger<8> = Logger::new();
+
+        let record = log.log(&mut pid, &clock).unwrap();
+        assert_eq!(log.labels().name, "Kiln");
+        assert_eq!(record.at, Duration::from_secs(5));
+        assert_eq!((record.pv.0, record.out.0, record.sv.0), (60.0, 0.25, 65.0));
+        assert!(log.latest().unwrap().status.alarm1());
+        assert!(record.provenance.bulk);
+        assert_eq!(record.provenance.requests, 3);
+        assert!(pid.port.rx.is_empty());
+    }
+}
@@ -0,0 +1,305 @@
+//! A non-blocking driver for superloop firmware.
+//!
+//! Bare-metal firmware without an executor can't afford to sit in
+//! `nb::block!` for the length of a Modbus round trip. A [`Machine`] takes one
+//! [`Op`] at a time and advances it a little on every [`Machine::step`]: it
+//! only writes what the port can take and only reads what has already arrived,
+//! so a step never blocks. Call it from the main loop and pick up the result
+//! with [`Machine::take`] once the [`Phase`] reaches `Done`, or give up on an
+//! overdue response with [`Machine::cancel`].
+//!
+//! Values go over the wire as-is, without the typed setters' range checks.
+
+use core::fmt;
+
+use embedded_io::{Read, ReadReady, Write, WriteReady};
+
+use crate::exchange::Exchange;
+pub use crate::exchange::{Op, Output};
+use crate::{Context, Error, Operation};
+
+/// Where the machine is in the current round trip.
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Phase {
+    /// Nothing queued.
+    Idle,
+
+    /// Writing the request; `sent` bytes are out.
+    Sending { sent: usize },
+
+    /// Waiting for the response; `received` bytes are in.
+    Receiving { received: usize },
+
+    /// Finished; the result is waiting for [`Machine::take`].
+    Done,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <Self as fmt::Debug>::fmt(self, f)
+    }
+}
+
+pub type MachineResult<UART> =
+    core::result::Result<Output, Error<<UART as embedded_io::ErrorType>::Error>>;
+
+pub struct Machine<UART>
+where
+    UART: embedded_io::ErrorType,
+{
+    unit_id: u8,
+    port: UART,
+    phase: Phase,
+    exchange: Option<Exchange>,
+    result: Option<MachineResult<UART>>,
+}
+
+impl<UART> Machine<UART>
+where
+    UART: Read + ReadReady + Write + WriteReady,
+{
+    pub fn new(unit_id: u8, port: UART) -> Self {
+        Machine {
+            unit_id,
+            port,
+            phase: Phase::Idle,
+            exchange: None,
+            result: None,
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Start `op`, or hand it back if another operation is still in flight or
+    /// its result hasn't been taken.
+    pub fn enqueue(&mut self, op: Op) -> core::result::Result<(), Op> {
+        if self.phase != Phase::Idle {
+            return Err(op);
+        }
+
+        match Exchange::new(self.unit_id, op) {
+            Ok(exchange) => {
+                self.exchange = Some(exchange);
+                self.phase = Phase::Sending { sent: 0 };
+            }
+            Err(err) => self.finish(Err(Error::modbus(context(op), err))),
+        }
+        Ok(())
+    }
+
+    /// Make whatever progress is possible without blocking.
+    pub fn step(&mut self) -> Phase {
+        if let Err(err) = self.advance() {
+            self.finish(Err(err));
+        }
+        self.phase
+    }
+
+    /// Give up on the operation in flight, finishing it with
+    /// [`Error::Timeout`].
+    ///
+    /// The machine has no clock of its own, so a controller that never
+    /// answers leaves it `Receiving` until this is called, typically once the
+    /// caller's own timer says the response is overdue. Does nothing if no
+    /// operation is in flight.
+    pub fn cancel(&mut self) {
+        if let Some(exchange) = &self.exchange {
+            let ctx = context(exchange.op());
+            self.finish(Err(Error::Timeout(ctx)));
+        }
+    }
+
+    /// Take the result of the finished operation, making the machine idle.
+    pub fn take(&mut self) -> Option<MachineResult<UART>> {
+        let result = self.result.take()?;
+        self.phase = Phase::Idle;
+        Some(result)
+    }
+
+    pub fn into_inner(self) -> UART {
+        self.port
+    }
+
+    fn advance(&mut self) -> core::result::Result<(), Error<UART::Error>> {
+        let Some(exchange) = self.exchange.as_mut() else {
+            return Ok(());
+        };
+        let ctx = context(exchange.op());
+        let serial_error = |err| Error::SerialError(ctx, err);
+
+        if let Phase::Sending { sent } = self.phase {
+            let request = exchange.request();
+            if !self.port.write_ready().map_err(serial_error)? {
+                return Ok(());
+            }
+            let n = self.port.write(&request[sent..]).map_err(serial_error)?;
+            let sent = sent + n;
+            if sent < request.len() {
+                self.phase = Phase::Sending { sent };
+                return Ok(());
+            }
+            self.phase = Phase::Receiving { received: 0 };
+        }
+
+        if let Phase::Receiving { mut received } = self.phase {
+            let mut chunk = [0; 16];
+            while self.port.read_ready().map_err(serial_error)? {
+                let want = exchange.wanted().min(chunk.len());
+                let n = self.port.read(&mut chunk[..want]).map_err(serial_error)?;
+                received += n;
+                if let Some(result) = exchange.feed(&chunk[..n]) {
+                    self.finish(result.map_err(|kind| Error::modbus(ctx, kind)));
+                    return Ok(());
+                }
+                if n < want {
+                    break;
+                }
+            }
+            self.phase = Phase::Receiving { received };
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self, result: MachineResult<UART>) {
+        self.exchange = None;
+        self.result = Some(result);
+        self.phase = Phase::Done;
+    }
+}
+
+fn context(op: Op) -> Context {
+    match op {
+        Op::Get(reg) => Context::new(Operation::ReadHolding, reg),
+        Op::Set(reg, _) => Context::new(Operation::WriteHolding, reg),
+        Op::GetCoils(reg, _) => Context::new(Operation::ReadCoils, reg),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::frame;
+    use crate::regs;
+    use std::collections::VecDeque;
+
+    /// Delivers at most one queued byte per read, and only every other step.
+    #[derive(Default)]
+    struct Trickle {
+        rx: VecDeque<u8>,
+        tx: Vec<u8>,
+        ready: bool,
+    }
+
+    impl embedded_io::ErrorType for Trickle {
+        type Error = embedded_io::ErrorKind;
+    }
+
+    impl ReadReady for Trickle {
+        fn read_ready(&mut self) -> core::result::Result<bool, Self::Error> {
+            self.ready = !self.ready;
+            Ok(self.ready && !self.rx.is_empty())
+        }
+    }
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> core::result::Result<usize, Self::Error> {
+            match (buf.first_mut(), self.rx.pop_front()) {
+                (Some(b), Some(q)) => {
+                    *b = q;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    impl WriteReady for Trickle {
+        fn write_ready(&mut self) -> core::result::Result<bool, Self::Error> {
+            Ok(true)
+        }
+    }
+
+    impl Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> core::result::Result<usize, Self::Error> {
+            // half a frame at a time
+            let n = buf.len().min(4);
+            self.tx.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> core::result::Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn completes_a_read_across_many_steps() {
+        let mut port = Trickle::default();
+        port.rx
+            .extend(frame(&[0x01, 0x03, 0x04, 0x42, 0x48, 0x00, 0x00]));
+        let mut m = Machine::new(1, port);
+
+        assert!(m.enqueue(Op::Get(regs::PV)).is_ok());
+        assert!(m.enqueue(Op::Get(regs::SV)).is_err());
+
+        let mut steps = 0;
+        while m.step() != Phase::Done {
+            steps += 1;
+            assert!(steps < 100);
+        }
+        assert!(steps > 9);
+        assert!(matches!(m.take(), Some(Ok(Output::Value(v))) if v == 50.0));
+        assert_eq!(m.phase(), Phase::Idle);
+        assert_eq!(
+            m.into_inner().tx,
+            frame(&[0x01, 0x03, 0x01, 0x64, 0x00, 0x02])
+        );
+    }
+
+    #[test]
+    fn reports_exceptions() {
+        let mut port = Trickle::default();
+        port.rx.extend(frame(&[0x01, 0x90, 0x02]));
+        let mut m = Machine::new(1, port);
+
+        assert!(m.enqueue(Op::Set(regs::SV, 65.0)).is_ok());
+        while m.step() != Phase::Done {}
+        assert!(matches!(m.take(), Some(Err(Error::IllegalDataAddress(_)))));
+    }
+
+    #[test]
+    fn rejects_too_many_coils() {
+        let mut m = Machine::new(1, Trickle::default());
+
+        assert!(m.enqueue(Op::GetCoils(regs::AT, 9)).is_ok());
+        assert_eq!(m.phase(), Phase::Done);
+        assert!(matches!(
+            m.take(),
+            Some(Err(Error::ModbusError(_, rmodbus::ErrorKind::OOB)))
+        ));
+        assert!(m.into_inner().tx.is_empty());
+    }
+
+    #[test]
+    fn cancels_an_unanswered_request() {
+        let mut m = Machine::new(1, Trickle::default());
+
+        m.cancel();
+        assert_eq!(m.phase(), Phase::Idle);
+        assert!(m.enqueue(Op::Get(regs::PV)).is_ok());
+        for _ in 0..10 {
+            m.step();
+        }
+        assert!(matches!(m.phase(), Phase::Receiving { received: 0 }));
+        m.cancel();
+        assert!(matches!(
+            m.take(),
+            Some(Err(Error::Timeout(ctx))) if ctx.op == Operation::ReadHolding
+        ));
+        assert!(m.enqueue(Op::Get(regs::PV)).is_ok());
+    }
+}
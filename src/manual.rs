@@ -0,0 +1,120 @@
+//! Writing OUT by hand.
+//!
+//! The controller ignores writes to OUT unless the control flag (CV) is set,
+//! and keeps holding the written output until CV is cleared again. The only
+//! way to get at [`ManualOutput::set_out`] is through the guard returned by
+//! [`Syl2381::enter_manual_output`], which clears CV when it goes away.
+
+use crate::clock::{DriverClock, NoClock};
+use crate::transport::Transport;
+use crate::{limits, regs, Error, Percent, Syl2381};
+
+/// The controller with CV set; OUT can be written until this is dropped.
+pub struct ManualOutput<'a, UART, CLOCK = NoClock>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    pid: &'a mut Syl2381<UART, CLOCK>,
+    exited: bool,
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    /// Set CV, handing back a guard that can write OUT.
+    pub fn enter_manual_output(&mut self) -> crate::Result<ManualOutput<'_, UART, CLOCK>, UART> {
+        self.set_cv(true)?;
+        Ok(ManualOutput {
+            pid: self,
+            exited: false,
+        })
+    }
+}
+
+impl<UART, CLOCK> ManualOutput<'_, UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    /// Get the power output percentage (OUT).
+    pub fn get_out(&mut self) -> crate::Result<Percent, UART> {
+        self.pid.get_out()
+    }
+
+    /// Set the power output percentage (OUT).
+    ///
+    /// Accepts 0.0 to 1.0, a fraction of full power, not 0 to 100; see
+    /// [`Percent::from_percent`]. The controller holds this output until CV
+    /// is cleared.
+    pub fn set_out(&mut self, val: Percent) -> crate::Result<(), UART> {
+        let Percent(val) = val;
+        if !limits::OUT.contains(&val) {
+            return Err(Error::UnexpectedValue(val));
+        }
+        self.pid.set_holding(regs::OUT, val)
+    }
+
+    /// Clear CV, handing control back to the controller.
+    ///
+    /// Dropping the guard does the same, but can't report a failure.
+    pub fn exit(mut self) -> crate::Result<(), UART> {
+        self.exited = true;
+        self.pid.set_cv(false)
+    }
+}
+
+impl<UART, CLOCK> Drop for ManualOutput<'_, UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    fn drop(&mut self) {
+        if !self.exited {
+            let _ = self.pid.set_cv(false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{frame, MockSerial};
+
+    fn cv(on: bool) -> Vec<u8> {
+        let val = if on { 0x3F80 } else { 0x0000 };
+        let [v0, v1] = u16::to_be_bytes(val);
+        frame(&[1, 0x10, 0x01, 0x6C, 0, 2, 4, v0, v1, 0, 0])
+    }
+
+    #[test]
+    fn clears_cv_on_drop() {
+        let mut port = MockSerial::new();
+        port.ack_f32(1, regs::CV.addr);
+        port.ack_f32(1, regs::OUT.addr);
+        port.ack_f32(1, regs::CV.addr);
+        let mut pid = Syl2381::new(1, port);
+
+        {
+            let mut manual = pid.enter_manual_output().unwrap();
+            manual.set_out(Percent(0.4)).unwrap();
+        }
+        assert_eq!(pid.port.tx[..13], cv(true));
+        assert_eq!(pid.port.tx[26..], cv(false));
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn exit_reports_failure() {
+        let mut port = MockSerial::new();
+        port.ack_f32(1, regs::CV.addr);
+        let mut pid = Syl2381::new(1, port);
+
+        let manual = pid.enter_manual_output().unwrap();
+        assert!(matches!(manual.exit(), Err(Error::SerialError(..))));
+        // Not retried on drop.
+        assert_eq!(pid.port.tx[13..], cv(false));
+    }
+}
@@ -0,0 +1,289 @@
+//! Merge sample series from several controllers onto a common timeline.
+//!
+//! The SYL-2381 has no clock, so samples from different controllers on a bus
+//! are taken at slightly different times. [`merge`] resamples each series at
+//! the given timestamps and produces one wide row per timestamp, which is the
+//! shape spreadsheet users expect; [`write_csv`] writes the rows out with a
+//! column per series.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::time::Duration;
+
+#[cfg(feature = "std")]
+use std::io;
+
+use crate::logger::Record;
+use crate::sample::Sample;
+
+/// How a series is resampled at a timestamp it wasn't read at.
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq)]
+pub enum Align {
+    /// Use the sample closest in time.
+    Nearest,
+
+    /// Interpolate linearly between the samples either side.
+    Linear,
+}
+
+/// Which reading a series follows.
+///
+/// Temperatures are in the controller's display unit and OUT is in percent,
+/// as in [`csv`](crate::csv).
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq)]
+pub enum Field {
+    Pv,
+
+    /// Only [`Record`]s carry SV; a series of [`Sample`]s has none.
+    Sv,
+
+    /// Only in [`Sample`]s taken with `Sampler::with_out`.
+    Out,
+}
+
+/// Where a series' readings come from, sorted by time.
+#[derive(Clone, Copy, fmt::Debug)]
+pub enum Readings<'a> {
+    Records(&'a [Record]),
+    Samples(&'a [Sample]),
+}
+
+/// One reading of one controller over time.
+#[derive(Clone, Copy, fmt::Debug)]
+pub struct Series<'a> {
+    /// The column's header in [`write_csv`].
+    pub name: &'a str,
+    pub readings: Readings<'a>,
+    pub field: Field,
+}
+
+impl<'a> Series<'a> {
+    /// `field` of a [`Logger`](crate::logger::Logger)'s records.
+    pub fn records(name: &'a str, records: &'a [Record], field: Field) -> Self {
+        Series {
+            name,
+            readings: Readings::Records(records),
+            field,
+        }
+    }
+
+    /// `field` of a [`Sampler`](crate::sample::Sampler)'s samples.
+    pub fn samples(name: &'a str, samples: &'a [Sample], field: Field) -> Self {
+        Series {
+            name,
+            readings: Readings::Samples(samples),
+            field,
+        }
+    }
+
+    /// `(timestamp, value)` of every reading that has the field.
+    fn points(&self) -> Vec<(Duration, f32)> {
+        match self.readings {
+            Readings::Records(records) => records
+                .iter()
+                .map(|r| {
+                    let val = match self.field {
+                        Field::Pv => r.pv.0,
+                        Field::Sv => r.sv.0,
+                        Field::Out => r.out.percent(),
+                    };
+                    (r.at, val)
+                })
+                .collect(),
+            Readings::Samples(samples) => samples
+                .iter()
+                .filter_map(|s| {
+                    let val = match self.field {
+                        Field::Pv => Some(s.pv.0),
+                        Field::Sv => None,
+                        Field::Out => s.out.map(|out| out.percent()),
+                    };
+                    Some((s.at, val?))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// One timestamp of the merged table; `values[i]` belongs to `series[i]`.
+#[derive(Clone, fmt::Debug, PartialEq)]
+pub struct Row {
+    pub at: Duration,
+    pub values: Vec<Option<f32>>,
+}
+
+/// Evenly spaced timestamps from `start` up to and including `end`.
+pub fn timeline(start: Duration, end: Duration, step: Duration) -> Vec<Duration> {
+    let mut out = Vec::new();
+    if step.is_zero() {
+        return out;
+    }
+    let mut t = start;
+    while t <= end {
+        out.push(t);
+        t += step;
+    }
+    out
+}
+
+/// Resample every series at each timestamp in `at`.
+///
+/// A value is only produced if the sample(s) used are within `max_gap` of the
+/// timestamp; otherwise the cell is `None` rather than a made-up reading.
+pub fn merge(series: &[Series<'_>], at: &[Duration], align: Align, max_gap: Duration) -> Vec<Row> {
+    let points: Vec<_> = series.iter().map(Series::points).collect();
+    at.iter()
+        .map(|&t| Row {
+            at: t,
+            values: points
+                .iter()
+                .map(|p| sample_at(p, t, align, max_gap))
+                .collect(),
+        })
+        .collect()
+}
+
+/// Write `rows`, as merged from `series`, as CSV: time in seconds, then a
+/// column per series headed by its name. Cells with nothing to align are
+/// left empty.
+#[cfg(feature = "std")]
+pub fn write_csv(mut out: impl io::Write, series: &[Series<'_>], rows: &[Row]) -> io::Result<()> {
+    write!(out, "time_s")?;
+    for s in series {
+        write!(out, ",")?;
+        crate::csv::write_field(&mut out, s.name)?;
+    }
+    writeln!(out)?;
+
+    for row in rows {
+        write!(out, "{:.3}", row.at.as_secs_f64())?;
+        for val in &row.values {
+            write!(out, ",")?;
+            if let Some(val) = val {
+                write!(out, "{val}")?;
+            }
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn sample_at(
+    samples: &[(Duration, f32)],
+    t: Duration,
+    align: Align,
+    max_gap: Duration,
+) -> Option<f32> {
+    let idx = samples.partition_point(|&(at, _)| at < t);
+    let after = samples.get(idx).copied();
+    let before = idx.checked_sub(1).map(|i| samples[i]);
+    let gap = |at: Duration| at.abs_diff(t);
+
+    match (align, before, after) {
+        (Align::Linear, Some((t0, v0)), Some((t1, v1))) => {
+            if gap(t0) > max_gap || gap(t1) > max_gap {
+                return None;
+            }
+            let span = (t1 - t0).as_secs_f32();
+            if span == 0.0 {
+                return Some(v1);
+            }
+            let frac = (t - t0).as_secs_f32() / span;
+            Some(v0 + (v1 - v0) * frac)
+        }
+        (Align::Linear, None, Some((t1, v1))) if t1 == t => Some(v1),
+        (Align::Linear, _, _) => None,
+        (Align::Nearest, before, after) => {
+            let nearest = match (before, after) {
+                (Some(b), Some(a)) => Some(if gap(a.0) < gap(b.0) { a } else { b }),
+                (b, a) => b.or(a),
+            };
+            nearest
+                .filter(|&(at, _)| gap(at) <= max_gap)
+                .map(|(_, v)| v)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample::Provenance;
+    use crate::{Percent, Status, TempRaw};
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    fn record(at: u64, pv: f32, out: f32) -> Record {
+        Record {
+            at: secs(at),
+            pv: TempRaw(pv),
+            out: Percent(out),
+            sv: TempRaw(65.0),
+            status: Status(0),
+            provenance: Provenance::default(),
+        }
+    }
+
+    fn sample(at: u64, pv: f32) -> Sample {
+        Sample {
+            at: secs(at),
+            pv: TempRaw(pv),
+            out: None,
+            status: None,
+            provenance: Provenance::default(),
+        }
+    }
+
+    #[test]
+    fn merges_onto_common_timeline() {
+        let mash = [record(0, 10.0, 0.5), record(10, 20.0, 0.25)];
+        let boil = [sample(2, 50.0), sample(9, 60.0)];
+        let series = [
+            Series::records("mash", &mash, Field::Pv),
+            Series::samples("boil", &boil, Field::Pv),
+            Series::records("mash out", &mash, Field::Out),
+            Series::samples("boil out", &boil, Field::Out),
+        ];
+        let at = timeline(secs(0), secs(10), secs(5));
+
+        let rows = merge(&series, &at, Align::Nearest, secs(3));
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].values, [Some(10.0), Some(50.0), Some(50.0), None]);
+        assert_eq!(rows[1].values, [None, Some(50.0), None, None]);
+        assert_eq!(rows[2].values, [Some(20.0), Some(60.0), Some(25.0), None]);
+
+        let rows = merge(&series[..2], &at, Align::Linear, secs(10));
+        assert_eq!(rows[0].values, [Some(10.0), None]);
+        assert_eq!(rows[1].values, [Some(15.0), Some(50.0 + 10.0 * 3.0 / 7.0)]);
+        assert_eq!(rows[2].values, [Some(20.0), None]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn exports_one_column_per_series() {
+        let mash = [record(0, 10.0, 0.5), record(10, 20.0, 0.25)];
+        let boil = [sample(2, 50.0), sample(9, 60.0)];
+        let series = [
+            Series::records("mash", &mash, Field::Pv),
+            Series::samples("boil, kettle", &boil, Field::Pv),
+        ];
+        let rows = merge(
+            &series,
+            &timeline(secs(0), secs(10), secs(5)),
+            Align::Nearest,
+            secs(3),
+        );
+
+        let mut out = Vec::new();
+        write_csv(&mut out, &series, &rows).unwrap();
+        assert_eq!(
+            std::string::String::from_utf8(out).unwrap(),
+            "time_s,mash,\"boil, kettle\"\n\
+             0.000,10,50\n\
+             5.000,,50\n\
+             10.000,20,60\n"
+        );
+    }
+}
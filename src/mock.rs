@@ -0,0 +1,107 @@
+//! A scripted serial port for exercising the driver without hardware.
+
+use std::collections::VecDeque;
+use std::vec::Vec;
+
+pub use crate::frame::crc16;
+use crate::transport::Transport;
+
+/// Serial port that records everything written to it and replays queued bytes
+/// on read.
+///
+/// Reading past the end of the queued bytes fails rather than blocking
+/// forever.
+#[derive(Default)]
+pub struct MockSerial {
+    pub rx: VecDeque<u8>,
+    pub tx: Vec<u8>,
+}
+
+impl MockSerial {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a response frame, appending its CRC.
+    pub fn respond(&mut self, frame: &[u8]) {
+        self.rx.extend(frame);
+        self.rx.extend(crc16(frame).to_le_bytes());
+    }
+
+    /// Queue a read-holdings response carrying a single f32.
+    pub fn respond_f32(&mut self, unit_id: u8, val: f32) {
+        let [b0, b1, b2, b3] = val.to_be_bytes();
+        self.respond(&[unit_id, 0x03, 4, b0, b1, b2, b3]);
+    }
+
+    /// Queue a write-holdings acknowledgement for a two register (f32) write.
+    pub fn ack_f32(&mut self, unit_id: u8, reg: u16) {
+        let [r0, r1] = reg.to_be_bytes();
+        self.respond(&[unit_id, 0x10, r0, r1, 0, 2]);
+    }
+}
+
+impl Transport for MockSerial {
+    type Error = ();
+
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), ()> {
+        self.tx.extend_from_slice(frame);
+        Ok(())
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), ()> {
+        if buf.len() > self.rx.len() {
+            self.rx.clear();
+            return Err(());
+        }
+        let n = buf.len();
+        for (b, q) in buf.iter_mut().zip(self.rx.drain(..n)) {
+            *b = q;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_io_async::ErrorType for MockSerial {
+    type Error = embedded_io_async::ErrorKind;
+}
+
+/// Reads return whatever is queued; an empty queue reads as end of file.
+#[cfg(feature = "async")]
+impl embedded_io_async::Read for MockSerial {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = buf.len().min(self.rx.len());
+        for (b, q) in buf.iter_mut().zip(self.rx.drain(..n)) {
+            *b = q;
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_io_async::Write for MockSerial {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.tx.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+}
+
+/// Build a complete request frame (with CRC) for comparison against `tx`.
+pub fn frame(body: &[u8]) -> Vec<u8> {
+    let mut v = body.to_vec();
+    v.extend(crc16(body).to_le_bytes());
+    v
+}
+
+/// Run `fut` to completion. The mocks never pend, so polling once is enough.
+#[cfg(feature = "async")]
+pub fn block_on<F: core::future::Future>(fut: F) -> F::Output {
+    use core::task::{Context, Poll, Waker};
+
+    let mut cx = Context::from_waker(Waker::noop());
+    match core::pin::pin!(fut).poll(&mut cx) {
+        Poll::Ready(out) => out,
+        Poll::Pending => panic!("mock pended"),
+    }
+}
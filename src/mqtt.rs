@@ -0,0 +1,430 @@
+//! Bridging a controller to MQTT.
+//!
+//! Home automation usually talks MQTT. A [`Bridge`] publishes
+//! [`DeviceSnapshot`]s as JSON to a state topic, applies [`Command`]s arriving
+//! on a command topic, and reports how each went on a status topic. Both carry
+//! the controller's [`Labels`], when it has any. The bridge brings
+//! no MQTT client of its own: implement [`Publish`] for whichever one the
+//! application uses, and pass incoming messages to [`Bridge::handle`].
+//!
+//! Commands set one parameter by name, with the value tagged by its kind, for
+//! example `{"param": "Sv", "value": {"Temp": 65.0}}`. See [`Param`] and
+//! [`ParamValue`].
+//!
+//! [`Bridge::publish_discovery`] announces the controller to Home Assistant
+//! as a thermostat with PV and SV, plus sensors for PV and OUT and a binary
+//! sensor for the J1 alarm, all reading the state topic.
+
+use core::fmt;
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::clock::DriverClock;
+use crate::plausibility::Plausibility;
+use crate::transport::Transport;
+use crate::{DeviceSnapshot, DisplayUnit, Identity, Labels, Param, ParamValue, Syl2381};
+
+/// Home Assistant's default discovery prefix.
+pub const DISCOVERY_PREFIX: &str = "homeassistant";
+
+/// Sends messages to the broker.
+pub trait Publish {
+    type Error;
+
+    fn publish(&mut self, topic: &str, payload: &[u8], retain: bool) -> Result<(), Self::Error>;
+}
+
+/// Where the bridge publishes and listens.
+#[derive(Clone, fmt::Debug, PartialEq, Eq)]
+pub struct Topics {
+    /// Snapshots, retained.
+    pub state: String,
+
+    /// Commands to apply; subscribe to this.
+    pub command: String,
+
+    /// The outcome of each command, and read errors.
+    pub status: String,
+}
+
+impl Topics {
+    /// `{prefix}/state`, `{prefix}/set` and `{prefix}/status`.
+    pub fn new(prefix: &str) -> Self {
+        Topics {
+            state: std::format!("{prefix}/state"),
+            command: std::format!("{prefix}/set"),
+            status: std::format!("{prefix}/status"),
+        }
+    }
+}
+
+/// Set `param` to `value`.
+#[derive(Clone, Copy, fmt::Debug, Serialize, Deserialize)]
+pub struct Command {
+    pub param: Param,
+    pub value: ParamValue,
+}
+
+/// What goes on the state topic.
+#[derive(Serialize)]
+struct State<'a> {
+    #[serde(flatten)]
+    snapshot: &'a DeviceSnapshot,
+    #[serde(skip_serializing_if = "Labels::is_empty")]
+    labels: &'a Labels,
+}
+
+/// What goes on the status topic.
+#[derive(Serialize)]
+struct Report<'a> {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    param: Option<Param>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Labels::is_empty")]
+    labels: &'a Labels,
+}
+
+pub struct Bridge<P> {
+    client: P,
+    topics: Topics,
+}
+
+impl<P> Bridge<P>
+where
+    P: Publish,
+{
+    pub fn new(client: P, topics: Topics) -> Self {
+        Bridge { client, topics }
+    }
+
+    pub fn topics(&self) -> &Topics {
+        &self.topics
+    }
+
+    /// Read every parameter and publish the snapshot.
+    ///
+    /// A failed read is reported on the status topic and returns `None`;
+    /// only a failure to publish is returned as an error.
+    pub fn publish_state<UART, CLOCK>(
+        &mut self,
+        pid: &mut Syl2381<UART, CLOCK>,
+    ) -> Result<Option<DeviceSnapshot>, P::Error>
+    where
+        UART: Transport,
+        CLOCK: DriverClock,
+        UART::Error: fmt::Debug,
+    {
+        match pid.read_snapshot() {
+            Ok(snapshot) => {
+                let state = State {
+                    snapshot: &snapshot,
+                    labels: pid.labels(),
+                };
+                let json = serde_json::to_vec(&state).expect("snapshots serialize");
+                self.client.publish(&self.topics.state, &json, true)?;
+                Ok(Some(snapshot))
+            }
+            Err(err) => {
+                self.report(pid.labels(), None, Some(err.to_string()))?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Handle a message that arrived on `topic`.
+    ///
+    /// Messages on the command topic are parsed and applied, and the outcome
+    /// is published on the status topic. Returns `false` for any other topic.
+    pub fn handle<UART, CLOCK>(
+        &mut self,
+        pid: &mut Syl2381<UART, CLOCK>,
+        topic: &str,
+        payload: &[u8],
+    ) -> Result<bool, P::Error>
+    where
+        UART: Transport,
+        CLOCK: DriverClock,
+        UART::Error: fmt::Debug,
+    {
+        if topic != self.topics.command {
+            return Ok(false);
+        }
+
+        let (param, error) = match serde_json::from_slice::<Command>(payload) {
+            Ok(cmd) => match pid.set_param(cmd.param, cmd.value) {
+                Ok(()) => (Some(cmd.param), None),
+                Err(err) => (Some(cmd.param), Some(err.to_string())),
+            },
+            Err(err) => (None, Some(std::format!("bad command: {err}"))),
+        };
+        self.report(pid.labels(), param, error)?;
+        Ok(true)
+    }
+
+    /// Home Assistant discovery messages for the controller `identity`
+    /// describes, as `(topic, payload)` pairs.
+    ///
+    /// `node_id` identifies the controller to Home Assistant and may only
+    /// hold ASCII letters, digits, `_` and `-`. The thermostat's range is what
+    /// the sensor can measure.
+    pub fn discovery(
+        &self,
+        node_id: &str,
+        labels: &Labels,
+        identity: &Identity,
+    ) -> Vec<(String, String)> {
+        let name = match labels.name.is_empty() {
+            true => node_id,
+            false => labels.name.as_str(),
+        };
+        let mut device = json!({
+            "identifiers": [std::format!("syl2381_{node_id}")],
+            "name": name,
+            "manufacturer": "Auber Instruments",
+            "model": "SYL-2381",
+        });
+        if !labels.location.is_empty() {
+            device["suggested_area"] = json!(labels.location.as_str());
+        }
+        let (unit, climate_unit) = match identity.display_unit {
+            DisplayUnit::Celsius => ("°C", "C"),
+            DisplayUnit::Fahrenheit => ("°F", "F"),
+        };
+        let (min, max) =
+            Plausibility::new(identity.input_sensor_type, identity.display_unit).bounds();
+        let state = &self.topics.state;
+
+        let entities = [
+            (
+                "climate",
+                json!({
+                    "name": null,
+                    "modes": ["heat"],
+                    "current_temperature_topic": state,
+                    "current_temperature_template": "{{ value_json.pv }}",
+                    "temperature_state_topic": state,
+                    "temperature_state_template": "{{ value_json.sv }}",
+                    "temperature_command_topic": self.topics.command,
+                    "temperature_command_template":
+                        "{\"param\": \"Sv\", \"value\": {\"Temp\": {{ value }}}}",
+                    "temperature_unit": climate_unit,
+                    "min_temp": min.0,
+                    "max_temp": max.0,
+                    "precision": 0.1,
+                }),
+            ),
+            (
+                "sensor",
+                json!({
+                    "name": "PV",
+                    "device_class": "temperature",
+                    "state_class": "measurement",
+                    "unit_of_measurement": unit,
+                    "state_topic": state,
+                    "value_template": "{{ value_json.pv }}",
+                }),
+            ),
+            (
+                "sensor",
+                json!({
+                    "name": "Output",
+                    "state_class": "measurement",
+                    "unit_of_measurement": "%",
+                    "state_topic": state,
+                    "value_template": "{{ (value_json.out * 100) | round(1) }}",
+                }),
+            ),
+            (
+                "binary_sensor",
+                json!({
+                    "name": "Alarm",
+                    "device_class": "problem",
+                    "state_topic": state,
+                    "value_template": "{{ 'ON' if value_json.j1_status else 'OFF' }}",
+                }),
+            ),
+        ];
+
+        entities
+            .into_iter()
+            .zip(["climate", "pv", "out", "alarm"])
+            .map(|((component, mut config), object)| {
+                let unique_id = std::format!("syl2381_{node_id}_{object}");
+                config["unique_id"] = json!(unique_id);
+                config["device"] = device.clone();
+                let topic = std::format!("{DISCOVERY_PREFIX}/{component}/{unique_id}/config");
+                (topic, config.to_string())
+            })
+            .collect()
+    }
+
+    /// Publish [`Bridge::discovery`]'s messages, retained.
+    pub fn publish_discovery(
+        &mut self,
+        node_id: &str,
+        labels: &Labels,
+        identity: &Identity,
+    ) -> Result<(), P::Error> {
+        for (topic, payload) in self.discovery(node_id, labels, identity) {
+            self.client.publish(&topic, payload.as_bytes(), true)?;
+        }
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> P {
+        self.client
+    }
+
+    fn report(
+        &mut self,
+        labels: &Labels,
+        param: Option<Param>,
+        error: Option<String>,
+    ) -> Result<(), P::Error> {
+        let report = Report {
+            ok: error.is_none(),
+            param,
+            error,
+            labels,
+        };
+        let json = serde_json::to_vec(&report).expect("reports serialize");
+        self.client.publish(&self.topics.status, &json, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockSerial;
+    use crate::regs;
+
+    #[derive(Default)]
+    struct Broker {
+        sent: Vec<(String, String, bool)>,
+    }
+
+    impl Publish for Broker {
+        type Error = ();
+
+        fn publish(&mut self, topic: &str, payload: &[u8], retain: bool) -> Result<(), ()> {
+            let payload = String::from_utf8(payload.to_vec()).unwrap();
+            self.sent.push((topic.to_string(), payload, retain));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn applies_commands_and_reports() {
+        let mut port = MockSerial::new();
+        port.ack_f32(1, regs::SV.addr);
+        let mut pid = Syl2381::new(1, port);
+        pid.live
+            .assume_sensor(crate::InputType::K, crate::DisplayUnit::Celsius);
+        let mut bridge = Bridge::new(Broker::default(), Topics::new("kiln"));
+
+        let cmd = br#"{"param": "Sv", "value": {"Temp": 65.0}}"#;
+        assert_eq!(bridge.handle(&mut pid, "kiln/set", cmd), Ok(true));
+        let bad = br#"{"param": "Pv", "value": {"Temp": 65.0}}"#;
+        assert_eq!(bridge.handle(&mut pid, "kiln/set", bad), Ok(true));
+        assert_eq!(bridge.handle(&mut pid, "kiln/set", b"65"), Ok(true));
+        assert_eq!(bridge.handle(&mut pid, "other", cmd), Ok(false));
+        assert!(pid.port.rx.is_empty());
+
+        let sent = bridge.into_inner().sent;
+        assert_eq!(sent.len(), 3);
+        assert_eq!(sent[0].0, "kiln/status");
+        assert_eq!(sent[0].1, r#"{"ok":true,"param":"Sv"}"#);
+        assert_eq!(
+            sent[1].1,
+            r#"{"ok":false,"param":"Pv","error":"unexpected value 65"}"#
+        );
+        assert!(sent[2]
+            .1
+            .starts_with(r#"{"ok":false,"error":"bad command: "#));
+    }
+
+    #[test]
+    fn labels_the_state() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 25.0); // PV
+        port.respond_f32(1, 0.5); // OUT
+        port.respond(&[1, 0x01, 1, 0x01]); // AL1_STA
+        port.respond_f32(1, 0.0); // CV
+        port.respond(&[1, 0x01, 1, 0x20]); // AT
+        for val in [65.0, 70.0, 68.0, 10.0, 120.0, 30.0, 5.0, 0.8, 2.0, 1.0] {
+            port.respond_f32(1, val); // SV ..= FILT
+        }
+        for val in [0.0, 2.0, 0.0, 0.5, -1.0, 0.0, 0.0, 1.0, 3.0] {
+            port.respond_f32(1, val); // INTY ..= bAud
+        }
+        let mut pid = Syl2381::new(1, port);
+        pid.set_labels(Labels::new("Kiln"));
+        let mut bridge = Bridge::new(Broker::default(), Topics::new("kiln"));
+
+        assert!(matches!(bridge.publish_state(&mut pid), Ok(Some(_))));
+        let sent = bridge.into_inner().sent;
+        let state: serde_json::Value = serde_json::from_str(&sent[0].1).unwrap();
+        assert_eq!(state["sv"], 65.0);
+        assert_eq!(state["labels"]["name"], "Kiln");
+    }
+
+    #[test]
+    fn reports_read_errors() {
+        let mut pid = Syl2381::new(1, MockSerial::new());
+        pid.set_labels(Labels::new("Kiln").with_location("Shed"));
+        let mut bridge = Bridge::new(Broker::default(), Topics::new("kiln"));
+
+        assert!(matches!(bridge.publish_state(&mut pid), Ok(None)));
+        let sent = bridge.into_inner().sent;
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, "kiln/status");
+        assert!(sent[0]
+            .1
+            .ends_with(r#""labels":{"name":"Kiln","location":"Shed","probe":""}}"#));
+        assert!(!sent[0].2);
+    }
+
+    #[test]
+    fn announces_to_home_assistant() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 1.0); // Id
+        port.respond_f32(1, 3.0); // bAud
+        port.respond_f32(1, 6.0); // INTY: K
+        port.respond_f32(1, 0.0); // CorF
+        port.respond(&[1, 0x01, 1, 0x00]);
+        let mut pid = Syl2381::new(1, port);
+        let identity = pid.probe().unwrap();
+        let mut bridge = Bridge::new(Broker::default(), Topics::new("kiln"));
+
+        let labels = Labels::new("Kiln").with_location("Shed");
+        bridge
+            .publish_discovery("kiln", &labels, &identity)
+            .unwrap();
+        let sent = bridge.into_inner().sent;
+        assert_eq!(sent.len(), 4);
+        assert!(sent.iter().all(|(_, _, retain)| *retain));
+
+        let (topic, payload, _) = &sent[0];
+        assert_eq!(topic, "homeassistant/climate/syl2381_kiln_climate/config");
+        let climate: serde_json::Value = serde_json::from_str(payload).unwrap();
+        assert_eq!(climate["current_temperature_topic"], "kiln/state");
+        assert_eq!(climate["temperature_command_topic"], "kiln/set");
+        assert_eq!(climate["temperature_unit"], "C");
+        assert_eq!(climate["max_temp"], 1300.0);
+        assert_eq!(climate["device"]["suggested_area"], "Shed");
+
+        // The command template renders to a command the bridge accepts.
+        let template = climate["temperature_command_template"].as_str().unwrap();
+        let cmd: Command = serde_json::from_str(&template.replace("{{ value }}", "65")).unwrap();
+        assert!(matches!(cmd.value, ParamValue::Temp(t) if t.0 == 65.0));
+        assert_eq!(
+            sent[3].0,
+            "homeassistant/binary_sensor/syl2381_kiln_alarm/config"
+        );
+    }
+}
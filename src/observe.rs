@@ -0,0 +1,83 @@
+//! Watching the raw traffic.
+//!
+//! A [`FrameObserver`] sees every request frame as it goes out and every
+//! response frame once it's in, CRC included, so traffic can be hexdumped to
+//! RTT or a log without a bus analyzer on the RS-485 lines. Stray bytes
+//! skipped ahead of a response, and responses that never arrived in full,
+//! aren't passed on.
+
+use core::fmt;
+
+use crate::clock::DriverClock;
+use crate::transport::Transport;
+use crate::Syl2381;
+
+/// Callbacks for raw frames.
+#[derive(Clone, Copy, fmt::Debug)]
+pub struct FrameObserver {
+    /// Called with each request, just before it's sent.
+    pub on_tx: fn(&[u8]),
+
+    /// Called with each complete response, before it's checked.
+    pub on_rx: fn(&[u8]),
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    pub fn observer(&self) -> Option<FrameObserver> {
+        self.observer
+    }
+
+    /// Pass raw frames to `observer`, or stop with `None`.
+    pub fn set_observer(&mut self, observer: Option<FrameObserver>) {
+        self.observer = observer;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{frame, MockSerial};
+    use crate::regs;
+    use std::cell::RefCell;
+    use std::vec::Vec;
+
+    std::thread_local! {
+        static SEEN: RefCell<Vec<(char, Vec<u8>)>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn on_tx(bytes: &[u8]) {
+        SEEN.with(|seen| seen.borrow_mut().push(('>', bytes.to_vec())));
+    }
+
+    fn on_rx(bytes: &[u8]) {
+        SEEN.with(|seen| seen.borrow_mut().push(('<', bytes.to_vec())));
+    }
+
+    #[test]
+    fn sees_every_frame() {
+        let mut port = MockSerial::new();
+        port.rx.push_back(0x55);
+        port.respond(&[1, 0x83, 0x02]);
+        let mut pid = Syl2381::new(1, port);
+        pid.set_observer(Some(FrameObserver { on_tx, on_rx }));
+
+        assert!(pid.read_holding_raw(regs::SV.addr, 2).is_err());
+        assert!(pid.get_pv().is_err());
+        pid.set_observer(None);
+        assert!(pid.get_pv().is_err());
+
+        let seen = SEEN.with(|seen| seen.take());
+        assert_eq!(
+            seen,
+            [
+                ('>', frame(&[1, 0x03, 0x00, 0x00, 0x00, 0x02])),
+                ('<', frame(&[1, 0x83, 0x02])),
+                ('>', frame(&[1, 0x03, 0x01, 0x64, 0x00, 0x02])),
+            ]
+        );
+    }
+}
@@ -0,0 +1,587 @@
+//! Parameters by name.
+//!
+//! The typed getters and setters are the way to talk to a controller from
+//! code that knows what it wants. Tooling that works on whatever parameter the
+//! user picked (a settings screen, a logger) can go through
+//! [`Syl2381::get_param`] and [`Syl2381::set_param`] instead, and find how to
+//! present each one in [`META`].
+
+use core::fmt;
+use core::ops::RangeInclusive;
+
+use crate::clock::DriverClock;
+use crate::limits;
+use crate::regs::{self, Reg};
+use crate::transport::Transport;
+use crate::{
+    BaudRate, ControlDirection, DisplayUnit, Error, Filter, InputType, OutputMode, OutputType,
+    Percent, Seconds, Syl2381, TempRaw,
+};
+
+/// A register the controller documents.
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Param {
+    Autotune,
+    ManualMode,
+    CoolingMode,
+    SettingMode,
+    Anomaly,
+    J1Status,
+    Sv,
+    J1OnTemp,
+    J1OffTemp,
+    Pv,
+    Out,
+    Cv,
+    P,
+    I,
+    D,
+    Bb,
+    Souf,
+    ControlCycle,
+    Filter,
+    InputSensorType,
+    OutputMode,
+    OutputType,
+    Hysteresis,
+    InputOffset,
+    ControlDirection,
+    DisplayUnit,
+    UnitId,
+    BaudRate,
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <Self as fmt::Debug>::fmt(self, f)
+    }
+}
+
+impl Param {
+    /// Every parameter, in register order.
+    pub const ALL: [Param; 28] = [
+        Param::Autotune,
+        Param::ManualMode,
+        Param::CoolingMode,
+        Param::SettingMode,
+        Param::Anomaly,
+        Param::J1Status,
+        Param::Sv,
+        Param::J1OnTemp,
+        Param::J1OffTemp,
+        Param::Pv,
+        Param::Out,
+        Param::Cv,
+        Param::P,
+        Param::I,
+        Param::D,
+        Param::Bb,
+        Param::Souf,
+        Param::ControlCycle,
+        Param::Filter,
+        Param::InputSensorType,
+        Param::OutputMode,
+        Param::OutputType,
+        Param::Hysteresis,
+        Param::InputOffset,
+        Param::ControlDirection,
+        Param::DisplayUnit,
+        Param::UnitId,
+        Param::BaudRate,
+    ];
+
+    pub const fn reg(self) -> Reg {
+        self.meta().reg
+    }
+
+    /// What an HMI needs to know to show and edit this parameter.
+    pub const fn meta(self) -> Meta {
+        META[self as usize]
+    }
+
+    /// Whether [`Syl2381::set_param`] accepts this parameter.
+    ///
+    /// OUT is only written through [`ManualOutput`](crate::ManualOutput), and
+    /// bAud through [`Syl2381::change_baud_rate`], which switches the port
+    /// over too.
+    pub const fn writable(self) -> bool {
+        !matches!(
+            self,
+            Param::CoolingMode
+                | Param::SettingMode
+                | Param::Anomaly
+                | Param::J1Status
+                | Param::Pv
+                | Param::Out
+                | Param::BaudRate
+        )
+    }
+}
+
+/// Looks a parameter up by its front panel code, ignoring case: `"sv"`,
+/// `"SouF"`, `"a/m"`.
+impl core::str::FromStr for Param {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Param::ALL
+            .into_iter()
+            .find(|param| param.meta().code.eq_ignore_ascii_case(s))
+            .ok_or(())
+    }
+}
+
+/// How a parameter's value is represented.
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ValueKind {
+    /// 0 or 1.
+    Bool,
+
+    /// A whole number.
+    Int,
+
+    /// Any number in range.
+    Float,
+
+    /// One of a few options, numbered from 0.
+    Enum,
+}
+
+impl fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <Self as fmt::Debug>::fmt(self, f)
+    }
+}
+
+/// What a parameter's value is measured in.
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Unit {
+    None,
+
+    /// The controller's display unit (CorF).
+    Temperature,
+
+    Seconds,
+
+    /// A fraction of full scale, 0 to 1.
+    Fraction,
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <Self as fmt::Debug>::fmt(self, f)
+    }
+}
+
+/// A row of [`META`].
+#[derive(Clone, Copy, fmt::Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Meta {
+    pub param: Param,
+    pub reg: Reg,
+
+    /// The code the manual and the front panel use.
+    pub code: &'static str,
+    pub kind: ValueKind,
+    pub unit: Unit,
+
+    /// Smallest value the controller takes, as stored.
+    pub min: f32,
+
+    /// Largest value the controller takes, as stored.
+    pub max: f32,
+
+    /// Digits the front panel shows after the decimal point: 1 for tenths,
+    /// 0 for whole numbers.
+    pub decimals: u8,
+}
+
+const fn row(
+    param: Param,
+    reg: Reg,
+    code: &'static str,
+    kind: ValueKind,
+    unit: Unit,
+    range: RangeInclusive<f32>,
+) -> Meta {
+    // Temperatures and P are shown to the tenth, OUT and SouF to the whole
+    // percent; everything else is a whole number or an option.
+    let decimals = match (kind, unit) {
+        (ValueKind::Float, Unit::Fraction) => 2,
+        (ValueKind::Float, _) => 1,
+        _ => 0,
+    };
+    Meta {
+        param,
+        reg,
+        code,
+        kind,
+        unit,
+        min: *range.start(),
+        max: *range.end(),
+        decimals,
+    }
+}
+
+const fn secs(range: RangeInclusive<u16>) -> RangeInclusive<f32> {
+    *range.start() as f32..=*range.end() as f32
+}
+
+/// Every parameter, indexed by [`Param`].
+pub const META: [Meta; 28] = {
+    use Param as P;
+    use Unit::{Fraction, Seconds as Secs, Temperature as Temp};
+    use ValueKind::*;
+
+    const FLAG: RangeInclusive<f32> = 0.0..=1.0;
+    const NONE: Unit = Unit::None;
+    const ID: RangeInclusive<f32> = *limits::ID.start() as f32..=*limits::ID.end() as f32;
+
+    [
+        row(P::Autotune, regs::AT, "AT", Bool, NONE, FLAG),
+        row(P::ManualMode, regs::AM, "A/M", Bool, NONE, FLAG),
+        row(P::CoolingMode, regs::COOL, "COOL", Bool, NONE, FLAG),
+        row(P::SettingMode, regs::SETTING, "SETTING", Bool, NONE, FLAG),
+        row(P::Anomaly, regs::ANOMALY, "ANOMALY", Bool, NONE, FLAG),
+        row(P::J1Status, regs::AL1_STA, "AL1_STA", Bool, NONE, FLAG),
+        row(P::Sv, regs::SV, "SV", Float, Temp, limits::SV),
+        row(P::J1OnTemp, regs::AH1, "AH1", Float, Temp, limits::AH1),
+        row(P::J1OffTemp, regs::AL1, "AL1", Float, Temp, limits::AL1),
+        row(P::Pv, regs::PV, "PV", Float, Temp, -1999.0..=9999.0),
+        row(P::Out, regs::OUT, "OUT", Float, Fraction, limits::OUT),
+        row(P::Cv, regs::CV, "CV", Bool, NONE, FLAG),
+        row(P::P, regs::P, "P", Float, NONE, limits::P),
+        row(P::I, regs::I, "I", Int, Secs, secs(limits::I)),
+        row(P::D, regs::D, "D", Int, Secs, secs(limits::D)),
+        row(P::Bb, regs::BB, "BB", Float, Temp, limits::BB),
+        row(P::Souf, regs::SOUF, "SouF", Float, Fraction, limits::SOUF),
+        row(P::ControlCycle, regs::OT, "OT", Int, Secs, secs(limits::OT)),
+        row(P::Filter, regs::FILT, "FILT", Enum, NONE, 0.0..=2.0),
+        row(
+            P::InputSensorType,
+            regs::INTY,
+            "INTY",
+            Enum,
+            NONE,
+            0.0..=10.0,
+        ),
+        row(P::OutputMode, regs::OUTY, "OUTY", Enum, NONE, 0.0..=4.0),
+        row(P::OutputType, regs::COTY, "COTY", Enum, NONE, 0.0..=2.0),
+        row(P::Hysteresis, regs::HY, "Hy", Float, Temp, limits::HY),
+        row(P::InputOffset, regs::PSB, "PSb", Float, Temp, limits::PSB),
+        row(P::ControlDirection, regs::RD, "rd", Enum, NONE, 0.0..=1.0),
+        row(P::DisplayUnit, regs::CORF, "CorF", Enum, NONE, 0.0..=1.0),
+        row(P::UnitId, regs::ID, "Id", Int, NONE, ID),
+        row(P::BaudRate, regs::BAUD, "bAud", Enum, NONE, 0.0..=3.0),
+    ]
+};
+
+/// The value of a [`Param`], in the type its typed getter returns.
+#[derive(Clone, Copy, fmt::Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ParamValue {
+    Flag(bool),
+    Temp(TempRaw),
+    Percent(Percent),
+    Seconds(Seconds),
+    Number(f32),
+    Filter(Filter),
+    InputType(InputType),
+    OutputMode(OutputMode),
+    OutputType(OutputType),
+    ControlDirection(ControlDirection),
+    DisplayUnit(DisplayUnit),
+    UnitId(u8),
+    BaudRate(BaudRate),
+}
+
+/// Numbers are shown to the resolution in [`META`], as on the front panel:
+/// `65.0°`, `120 s`, `25%`.
+impl fmt::Display for ParamValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let decimals = self.decimals();
+        match self {
+            ParamValue::Flag(val) => val.fmt(f),
+            ParamValue::Temp(val) => write!(f, "{:.*}", decimals, val),
+            // Percent shows hundredths of the stored fraction as whole percent.
+            ParamValue::Percent(val) => write!(f, "{:.*}", decimals.saturating_sub(2), val),
+            ParamValue::Seconds(val) => val.fmt(f),
+            ParamValue::Number(val) => write!(f, "{:.*}", decimals, val),
+            ParamValue::Filter(val) => val.fmt(f),
+            ParamValue::InputType(val) => val.fmt(f),
+            ParamValue::OutputMode(val) => val.fmt(f),
+            ParamValue::OutputType(val) => val.fmt(f),
+            ParamValue::ControlDirection(val) => val.fmt(f),
+            ParamValue::DisplayUnit(val) => val.fmt(f),
+            ParamValue::UnitId(val) => val.fmt(f),
+            ParamValue::BaudRate(val) => val.fmt(f),
+        }
+    }
+}
+
+/// The value as the controller stores it.
+impl From<ParamValue> for f32 {
+    fn from(val: ParamValue) -> f32 {
+        match val {
+            ParamValue::Flag(val) => val as u8 as f32,
+            ParamValue::Temp(TempRaw(val)) => val,
+            ParamValue::Percent(Percent(val)) => val,
+            ParamValue::Seconds(Seconds(val)) => val as f32,
+            ParamValue::Number(val) => val,
+            ParamValue::Filter(val) => val.into(),
+            ParamValue::InputType(val) => val.into(),
+            ParamValue::OutputMode(val) => val.into(),
+            ParamValue::OutputType(val) => val.into(),
+            ParamValue::ControlDirection(val) => val.into(),
+            ParamValue::DisplayUnit(val) => val.into(),
+            ParamValue::UnitId(val) => val as f32,
+            ParamValue::BaudRate(val) => val.into(),
+        }
+    }
+}
+
+impl ParamValue {
+    /// [`Meta::decimals`] of the parameters this variant holds.
+    ///
+    /// Every parameter of a variant has the same resolution, so one row of
+    /// [`META`] stands for all of them.
+    fn decimals(&self) -> usize {
+        let param = match self {
+            ParamValue::Temp(_) => Param::Sv,
+            ParamValue::Percent(_) => Param::Out,
+            ParamValue::Number(_) => Param::P,
+            _ => return 0,
+        };
+        param.meta().decimals as usize
+    }
+
+    /// `param`'s value from what the controller stores, or `None` if `val`
+    /// isn't one of the options of an enumerated parameter, a whole number of
+    /// seconds, or a unit ID in range.
+    pub fn from_stored(param: Param, val: f32) -> Option<Self> {
+        use ParamValue as V;
+
+        Some(match param {
+            Param::Autotune
+            | Param::ManualMode
+            | Param::CoolingMode
+            | Param::SettingMode
+            | Param::Anomaly
+            | Param::J1Status
+            | Param::Cv => V::Flag(val != 0.0),
+            Param::Sv
+            | Param::J1OnTemp
+            | Param::J1OffTemp
+            | Param::Pv
+            | Param::Bb
+            | Param::Hysteresis
+            | Param::InputOffset => V::Temp(TempRaw(val)),
+            Param::Out | Param::Souf => V::Percent(Percent(val)),
+            Param::I | Param::D | Param::ControlCycle => V::Seconds(Seconds::try_from(val).ok()?),
+            Param::P => V::Number(val),
+            Param::Filter => V::Filter(val.try_into().ok()?),
+            Param::InputSensorType => V::InputType(val.try_into().ok()?),
+            Param::OutputMode => V::OutputMode(val.try_into().ok()?),
+            Param::OutputType => V::OutputType(val.try_into().ok()?),
+            Param::ControlDirection => V::ControlDirection(val.try_into().ok()?),
+            Param::DisplayUnit => V::DisplayUnit(val.try_into().ok()?),
+            Param::UnitId => V::UnitId(limits::unit_id(val)?),
+            Param::BaudRate => V::BaudRate(val.try_into().ok()?),
+        })
+    }
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    /// Read `param` with its typed getter.
+    pub fn get_param(&mut self, param: Param) -> crate::Result<ParamValue, UART> {
+        use ParamValue as V;
+
+        Ok(match param {
+            Param::Autotune => V::Flag(self.get_autotune()?),
+            Param::ManualMode => V::Flag(self.get_manual_mode()?),
+            Param::CoolingMode => V::Flag(self.get_cooling_mode()?),
+            Param::SettingMode => V::Flag(self.get_setting_mode()?),
+            Param::Anomaly => V::Flag(self.get_anomaly()?),
+            Param::J1Status => V::Flag(self.get_j1_status()?),
+            Param::Sv => V::Temp(self.get_sv()?),
+            Param::J1OnTemp => V::Temp(self.get_j1_on_temp()?),
+            Param::J1OffTemp => V::Temp(self.get_j1_off_temp()?),
+            Param::Pv => V::Temp(self.get_pv()?),
+            Param::Out => V::Percent(self.get_out()?),
+            Param::Cv => V::Flag(self.get_cv()?),
+            Param::P => V::Number(self.get_p()?),
+            Param::I => V::Seconds(self.get_i()?),
+            Param::D => V::Seconds(self.get_d()?),
+            Param::Bb => V::Temp(self.get_bb()?),
+            Param::Souf => V::Percent(self.get_souf()?),
+            Param::ControlCycle => V::Seconds(self.get_control_cycle()?),
+            Param::Filter => V::Filter(self.get_filter()?),
+            Param::InputSensorType => V::InputType(self.get_input_sensor_type()?),
+            Param::OutputMode => V::OutputMode(self.get_output_mode()?),
+            Param::OutputType => V::OutputType(self.get_output_type()?),
+            Param::Hysteresis => V::Temp(self.get_hysteresis()?),
+            Param::InputOffset => V::Temp(self.get_input_offset()?),
+            Param::ControlDirection => V::ControlDirection(self.get_control_direction()?),
+            Param::DisplayUnit => V::DisplayUnit(self.get_display_unit()?),
+            Param::UnitId => V::UnitId(self.get_unit_id()?),
+            Param::BaudRate => V::BaudRate(self.get_baud_rate()?),
+        })
+    }
+
+    /// Write `param` with its typed setter.
+    ///
+    /// A value of the wrong type for `param`, or any value for a parameter
+    /// that isn't [`Param::writable`], is rejected with
+    /// [`Error::UnexpectedValue`] before anything is sent. Id goes through
+    /// [`Syl2381::change_unit_id`], so the driver follows the controller to
+    /// its new ID.
+    pub fn set_param(&mut self, param: Param, val: ParamValue) -> crate::Result<(), UART> {
+        use ParamValue as V;
+
+        match (param, val) {
+            (Param::Autotune, V::Flag(true)) => self.start_autotune(),
+            (Param::Autotune, V::Flag(false)) => self.abort_autotune(),
+            (Param::ManualMode, V::Flag(val)) => self.set_manual_mode(val),
+            (Param::Sv, V::Temp(val)) => self.set_sv(val),
+            (Param::J1OnTemp, V::Temp(val)) => self.set_j1_on_temp(val),
+            (Param::J1OffTemp, V::Temp(val)) => self.set_j1_off_temp(val),
+            (Param::Cv, V::Flag(val)) => self.set_cv(val),
+            (Param::P, V::Number(val)) => self.set_p(val),
+            (Param::I, V::Seconds(val)) => self.set_i(val),
+            (Param::D, V::Seconds(val)) => self.set_d(val),
+            (Param::Bb, V::Temp(val)) => self.set_bb(val),
+            (Param::Souf, V::Percent(val)) => self.set_souf(val),
+            (Param::ControlCycle, V::Seconds(val)) => self.set_control_cycle(val),
+            (Param::Filter, V::Filter(val)) => self.set_filter(val),
+            (Param::InputSensorType, V::InputType(val)) => self.set_input_sensor_type(val),
+            (Param::OutputMode, V::OutputMode(val)) => self.set_output_mode(val),
+            (Param::OutputType, V::OutputType(val)) => self.set_output_type(val),
+            (Param::Hysteresis, V::Temp(val)) => self.set_hysteresis(val),
+            (Param::InputOffset, V::Temp(val)) => self.set_intput_offset(val),
+            (Param::ControlDirection, V::ControlDirection(val)) => self.set_control_direction(val),
+            (Param::DisplayUnit, V::DisplayUnit(val)) => self.set_display_unit(val),
+            (Param::UnitId, V::UnitId(val)) => self.change_unit_id(val, false).map(|_| ()),
+            (_, val) => Err(Error::UnexpectedValue(val.into())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockSerial;
+
+    #[test]
+    fn every_register_has_a_param() {
+        assert_eq!(Param::ALL.len(), regs::TABLE.len());
+        for (param, reg) in Param::ALL.iter().zip(regs::TABLE) {
+            assert_eq!(param.reg().addr, reg.addr);
+            assert_eq!(param.reg().bank, reg.bank);
+        }
+    }
+
+    #[test]
+    fn meta_is_indexed_by_param() {
+        for (i, param) in Param::ALL.into_iter().enumerate() {
+            assert_eq!(META[i].param, param);
+            assert!(META[i].min < META[i].max);
+        }
+        assert_eq!(Param::Souf.meta().code, "SouF");
+        assert_eq!(Param::I.meta().max, 1999.0);
+    }
+
+    #[test]
+    fn displays_to_the_front_panel_resolution() {
+        assert_eq!(ParamValue::Temp(TempRaw(65.0)).to_string(), "65.0°");
+        assert_eq!(ParamValue::Temp(TempRaw(62.46)).to_string(), "62.5°");
+        assert_eq!(ParamValue::Number(10.0).to_string(), "10.0");
+        assert_eq!(ParamValue::Seconds(Seconds(120)).to_string(), "120 s");
+        assert_eq!(ParamValue::Percent(Percent(0.254)).to_string(), "25%");
+        assert_eq!(ParamValue::UnitId(5).to_string(), "5");
+
+        for meta in META {
+            let val = ParamValue::from_stored(meta.param, meta.max).unwrap();
+            assert_eq!(val.decimals(), meta.decimals as usize, "{}", meta.param);
+        }
+    }
+
+    #[test]
+    fn looks_up_by_code() {
+        assert_eq!("sv".parse(), Ok(Param::Sv));
+        assert_eq!("SOUF".parse(), Ok(Param::Souf));
+        assert_eq!("a/m".parse(), Ok(Param::ManualMode));
+        assert_eq!("setpoint".parse::<Param>(), Err(()));
+    }
+
+    #[test]
+    fn stored_values_round_trip() {
+        for meta in META {
+            let val = ParamValue::from_stored(meta.param, meta.max).unwrap();
+            assert_eq!(f32::from(val), meta.max, "{}", meta.param);
+        }
+        assert!(ParamValue::from_stored(Param::Filter, 3.0).is_none());
+        assert!(ParamValue::from_stored(Param::UnitId, 65.0).is_none());
+        assert!(ParamValue::from_stored(Param::UnitId, 7.5).is_none());
+    }
+
+    #[test]
+    fn reads_and_writes_by_param() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 6.0);
+        port.respond_f32(1, 0.0); // CorF, for the K-type range check
+        port.ack_f32(1, regs::SV.addr);
+        let mut pid = Syl2381::new(1, port);
+
+        let inty = pid.get_param(Param::InputSensorType).unwrap();
+        assert_eq!(inty.to_string(), "K");
+        assert!(pid
+            .set_param(Param::Sv, ParamValue::Temp(TempRaw(65.0)))
+            .is_ok());
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn rejects_mismatched_and_read_only() {
+        let mut pid = Syl2381::new(1, MockSerial::new());
+
+        assert!(matches!(
+            pid.set_param(Param::Sv, ParamValue::Seconds(Seconds(30))),
+            Err(Error::UnexpectedValue(v)) if v == 30.0
+        ));
+        assert!(matches!(
+            pid.set_param(Param::Pv, ParamValue::Temp(TempRaw(20.0))),
+            Err(Error::UnexpectedValue(_))
+        ));
+        assert!(matches!(
+            pid.set_param(Param::BaudRate, ParamValue::BaudRate(BaudRate::Baud4800)),
+            Err(Error::UnexpectedValue(_))
+        ));
+        assert!(pid.port.tx.is_empty());
+    }
+
+    #[test]
+    fn follows_a_new_unit_id() {
+        let mut port = MockSerial::new();
+        port.ack_f32(1, regs::ID.addr);
+        port.respond_f32(7, 7.0);
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(pid.set_param(Param::UnitId, ParamValue::UnitId(7)).is_ok());
+        assert!(matches!(
+            pid.get_param(Param::UnitId),
+            Ok(ParamValue::UnitId(7))
+        ));
+        assert!(pid.port.rx.is_empty());
+    }
+}
@@ -0,0 +1,283 @@
+//! Plausibility checks for process values.
+//!
+//! A broken or miswired probe doesn't always make the controller report an
+//! error; it can just as well produce a confident-looking reading that no
+//! sensor of the configured type could ever produce (a K-type thermocouple at
+//! 2000 °C, say). [`Plausibility`] rejects readings outside the sensor's
+//! measuring range so they never reach control or logging code.
+//!
+//! A corrupted frame can also slip past the CRC and produce a reading that is
+//! in range but nowhere near the previous one. [`JumpGuard`] catches these by
+//! confirming large jumps with a second read.
+//!
+//! When the sensor is open or miswired the controller does notice, and sets
+//! its anomaly flag, but PV still reads as a number. [`Syl2381::read_pv_checked`]
+//! looks at the flag too.
+//!
+//! The same bounds keep SV and the J1 alarm temperatures within what the
+//! sensor can measure; the driver reads INTY the first time it checks.
+
+use core::fmt;
+
+use crate::clock::DriverClock;
+use crate::transport::Transport;
+use crate::{DisplayUnit, Error, InputType, Syl2381, TempRaw};
+
+/// Bounds on the readings a sensor can physically produce.
+#[derive(Clone, Copy, fmt::Debug)]
+pub struct Plausibility {
+    min: f32,
+    max: f32,
+}
+
+impl Plausibility {
+    /// Bounds for `input`, expressed in the controller's display `unit`.
+    pub fn new(input: InputType, unit: DisplayUnit) -> Self {
+        let (min, max) = input.range_celsius();
+        Plausibility {
+            min: unit.from_celsius(min),
+            max: unit.from_celsius(max),
+        }
+    }
+
+    /// Widen the bounds by `margin` (in display units) on either side.
+    ///
+    /// Controllers typically read a little past the nominal range before
+    /// flagging a fault themselves, so a small margin avoids false positives
+    /// at the edges.
+    pub fn with_margin(self, margin: f32) -> Self {
+        Plausibility {
+            min: self.min - margin,
+            max: self.max + margin,
+        }
+    }
+
+    pub fn bounds(&self) -> (TempRaw, TempRaw) {
+        (TempRaw(self.min), TempRaw(self.max))
+    }
+
+    /// Whether `val` could have come from a working sensor.
+    pub fn is_plausible(&self, val: TempRaw) -> bool {
+        (self.min..=self.max).contains(&val.0)
+    }
+
+    /// Reject a setting of `val` with [`Error::OutOfRange`] if the sensor
+    /// can't reach it.
+    pub(crate) fn check<E>(&self, val: f32) -> Result<(), Error<E>> {
+        if !self.is_plausible(TempRaw(val)) {
+            return Err(Error::OutOfRange {
+                value: val,
+                min: self.min,
+                max: self.max,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Confirms process value jumps larger than a threshold with a second read.
+#[derive(Clone, Copy, fmt::Debug)]
+pub struct JumpGuard {
+    threshold: f32,
+    last: Option<f32>,
+}
+
+impl JumpGuard {
+    /// Confirm any change larger than `threshold` (in display units) between
+    /// consecutive reads.
+    pub fn new(threshold: f32) -> Self {
+        JumpGuard {
+            threshold,
+            last: None,
+        }
+    }
+
+    /// Forget the previous reading, e.g. after a long gap in polling.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// NaN readings count as jumps.
+    fn is_jump(&self, from: f32, to: f32) -> bool {
+        let delta = (to - from).abs();
+        delta > self.threshold || delta.is_nan()
+    }
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    /// Get the process value (PV), rejecting readings outside `bounds` with
+    /// [`Error::SensorFault`].
+    pub fn get_pv_plausible(&mut self, bounds: &Plausibility) -> crate::Result<TempRaw, UART> {
+        let pv = self.get_pv()?;
+        if !bounds.is_plausible(pv) {
+            return Err(Error::SensorFault(pv.0));
+        }
+        Ok(pv)
+    }
+
+    /// Get the process value (PV), failing with [`Error::SensorFault`] if the
+    /// controller's anomaly flag is set.
+    pub fn read_pv_checked(&mut self) -> crate::Result<TempRaw, UART> {
+        let pv = self.get_pv()?;
+        if self.get_status()?.anomaly() {
+            return Err(Error::SensorFault(pv.0));
+        }
+        Ok(pv)
+    }
+
+    /// Get the process value (PV), re-reading once if it jumped by more than
+    /// the guard's threshold since the last call.
+    ///
+    /// The second read is returned if it agrees with either the jump (a real
+    /// change) or the previous value (the first read was corrupted). If it
+    /// agrees with neither, the reading is rejected with
+    /// [`Error::UnexpectedValue`]. At most one extra read is made per call.
+    pub fn get_pv_confirmed(&mut self, guard: &mut JumpGuard) -> crate::Result<TempRaw, UART> {
+        let TempRaw(pv) = self.get_pv()?;
+        let last = match guard.last {
+            Some(last) if guard.is_jump(last, pv) => last,
+            _ => {
+                guard.last = Some(pv);
+                return Ok(TempRaw(pv));
+            }
+        };
+
+        let TempRaw(confirm) = self.get_pv()?;
+        if guard.is_jump(pv, confirm) && guard.is_jump(last, confirm) {
+            return Err(Error::UnexpectedValue(confirm));
+        }
+
+        guard.last = Some(confirm);
+        Ok(TempRaw(confirm))
+    }
+
+    /// Reject a temperature setting the sensor can't measure, reading INTY
+    /// and CorF the first time they're needed.
+    pub(crate) fn check_sensor_range(&mut self, val: f32) -> crate::Result<(), UART> {
+        let input = self.get_input_type_cached()?;
+        let unit = self.get_display_unit_cached()?;
+        Plausibility::new(input, unit).check(val)
+    }
+
+    /// As [`Syl2381::check_sensor_range`], for `val` in `unit` rather than the
+    /// unit the controller displays.
+    pub(crate) fn check_sensor_range_in(
+        &mut self,
+        val: f32,
+        unit: DisplayUnit,
+    ) -> crate::Result<(), UART> {
+        let input = self.get_input_type_cached()?;
+        Plausibility::new(input, unit).check(val)
+    }
+
+    /// The sensor type, read the first time it's needed.
+    fn get_input_type_cached(&mut self) -> crate::Result<InputType, UART> {
+        match self.live.input_type {
+            Some(input) => Ok(input),
+            None => self.get_input_sensor_type(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockSerial;
+
+    #[test]
+    fn bounds_follow_sensor_and_unit() {
+        let k = Plausibility::new(InputType::K, DisplayUnit::Celsius);
+        assert!(k.is_plausible(TempRaw(1250.0)));
+        assert!(!k.is_plausible(TempRaw(2000.0)));
+        assert!(!k.is_plausible(TempRaw(f32::NAN)));
+
+        let k = Plausibility::new(InputType::K, DisplayUnit::Fahrenheit);
+        assert_eq!(k.bounds(), (TempRaw(-328.0), TempRaw(2372.0)));
+        assert!(k.is_plausible(TempRaw(2000.0)));
+    }
+
+    #[test]
+    fn implausible_pv_is_a_sensor_fault() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 2000.0);
+        port.respond_f32(1, 151.0);
+        let mut pid = Syl2381::new(1, port);
+
+        let k = Plausibility::new(InputType::K, DisplayUnit::Celsius);
+        assert!(matches!(
+            pid.get_pv_plausible(&k),
+            Err(Error::SensorFault(v)) if v == 2000.0
+        ));
+
+        let cu50 = Plausibility::new(InputType::CU50, DisplayUnit::Celsius).with_margin(2.0);
+        assert!(matches!(pid.get_pv_plausible(&cu50), Ok(TempRaw(v)) if v == 151.0));
+    }
+
+    #[test]
+    fn anomaly_flag_is_a_sensor_fault() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 1372.0);
+        port.respond(&[1, 0x01, 1, 0b1_0000]);
+        port.respond_f32(1, 64.5);
+        port.respond(&[1, 0x01, 1, 0b10_0000]);
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(matches!(
+            pid.read_pv_checked(),
+            Err(Error::SensorFault(v)) if v == 1372.0
+        ));
+        assert!(matches!(pid.read_pv_checked(), Ok(TempRaw(v)) if v == 64.5));
+    }
+
+    #[test]
+    fn setpoints_stay_within_the_sensor_range() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 10.0); // INTY: CU50
+        port.respond_f32(1, 0.0); // CorF: Celsius
+        port.ack_f32(1, crate::regs::SV.addr);
+        let mut pid = Syl2381::new(1, port);
+
+        // INTY and CorF are read on the first check only.
+        let err = pid.set_sv(TempRaw(200.0)).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::OutOfRange { value, min, max } if value == 200.0 && min == -50.0 && max == 150.0
+        ));
+        assert_eq!(err.to_string(), "200 outside sensor range -50 to 150");
+        assert!(pid.set_sv(TempRaw(120.0)).is_ok());
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn jumps_are_confirmed() {
+        let mut port = MockSerial::new();
+        // steady
+        port.respond_f32(1, 60.0);
+        port.respond_f32(1, 60.5);
+        // corrupted read, re-read agrees with previous value
+        port.respond_f32(1, 600.5);
+        port.respond_f32(1, 60.7);
+        // real jump, re-read confirms it
+        port.respond_f32(1, 80.0);
+        port.respond_f32(1, 80.2);
+        // re-read agrees with neither
+        port.respond_f32(1, 20.0);
+        port.respond_f32(1, 400.0);
+        let mut pid = Syl2381::new(1, port);
+        let mut guard = JumpGuard::new(5.0);
+
+        assert!(matches!(pid.get_pv_confirmed(&mut guard), Ok(TempRaw(v)) if v == 60.0));
+        assert!(matches!(pid.get_pv_confirmed(&mut guard), Ok(TempRaw(v)) if v == 60.5));
+        assert!(matches!(pid.get_pv_confirmed(&mut guard), Ok(TempRaw(v)) if v == 60.7));
+        assert!(matches!(pid.get_pv_confirmed(&mut guard), Ok(TempRaw(v)) if v == 80.2));
+        assert!(matches!(
+            pid.get_pv_confirmed(&mut guard),
+            Err(Error::UnexpectedValue(v)) if v == 400.0
+        ));
+        assert!(pid.port.rx.is_empty());
+    }
+}
@@ -0,0 +1,85 @@
+//! The live values, in as few requests as possible.
+//!
+//! PV and OUT sit next to each other in the process bank, so one holding
+//! register request covers both, and one coil request covers every status
+//! flag. [`Syl2381::poll`] makes those two requests instead of one each.
+
+use core::fmt;
+
+use crate::clock::DriverClock;
+use crate::transport::Transport;
+use crate::{
+    regs, values_to_f32, Context, Error, Frame, Operation, Percent, Status, Syl2381, TempRaw,
+};
+
+/// The values a dashboard refreshes.
+#[derive(Clone, Copy, fmt::Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProcessValues {
+    pub pv: TempRaw,
+    pub out: Percent,
+    pub status: Status,
+}
+
+/// `PV 65.2°, OUT 40%`
+impl fmt::Display for ProcessValues {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PV {}, OUT {}", self.pv, self.out)
+    }
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    /// Read PV, OUT and the status flags in two requests.
+    pub fn poll(&mut self) -> crate::Result<ProcessValues, UART> {
+        let (pv, out) = self.get_pv_out()?;
+        Ok(ProcessValues {
+            pv,
+            out,
+            status: self.get_status()?,
+        })
+    }
+
+    /// Read PV and OUT in one request.
+    pub(crate) fn get_pv_out(&mut self) -> crate::Result<(TempRaw, Percent), UART> {
+        const WORDS: u16 = regs::OUT.addr + regs::OUT.width() - regs::PV.addr;
+
+        let ctx = Context::new(Operation::ReadHolding, regs::PV);
+        let fail = |kind| Error::modbus(ctx, kind);
+        let mut frame = Frame::get_words(self.unit_id, regs::PV.addr, WORDS).map_err(fail)?;
+        self.transact(ctx, &mut frame)?;
+        let words: heapless::Vec<u16, { WORDS as usize }> = frame.parse_words().map_err(fail)?;
+        let [pv0, pv1, out0, out1] = words[..] else {
+            return Err(fail(rmodbus::ErrorKind::FrameBroken));
+        };
+
+        Ok((
+            TempRaw(self.transforms.read(regs::PV, values_to_f32(pv0, pv1))),
+            Percent(self.transforms.read(regs::OUT, values_to_f32(out0, out1))),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{frame, MockSerial};
+
+    #[test]
+    fn reads_pv_and_out_together() {
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x03, 8, 0x42, 0x82, 0x66, 0x66, 0x3E, 0xCC, 0xCC, 0xCD]);
+        port.respond(&[1, 0x01, 1, 0b10_0000]);
+        let mut pid = Syl2381::new(1, port);
+
+        let vals = pid.poll().unwrap();
+        assert_eq!(vals.to_string(), "PV 65.2°, OUT 40%");
+        assert!(vals.status.alarm1());
+        assert_eq!(pid.port.tx[..8], frame(&[1, 0x03, 0x01, 0x64, 0x00, 0x04]));
+        assert_eq!(pid.port.tx.len(), 16);
+    }
+}
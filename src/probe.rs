@@ -0,0 +1,180 @@
+//! Checking there's really a SYL-2381 on the other end.
+//!
+//! A wrong unit ID or a different device on the bus can still answer Modbus
+//! reads. [`Syl2381::probe`] reads a few registers whose values only make
+//! sense on this controller before anything is written to it.
+//!
+//! Once it's known to be there, [`Syl2381::ping`] is the cheap way for a
+//! supervisory loop to check that it still is, and answering promptly.
+
+use core::fmt;
+use core::time::Duration;
+
+use crate::clock::{Clock, DriverClock};
+use crate::transport::Transport;
+use crate::{regs, BaudRate, Context, DisplayUnit, Error, InputType, Operation, Status, Syl2381};
+
+/// What [`Syl2381::probe`] found.
+#[derive(Clone, Copy, fmt::Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Identity {
+    pub unit_id: u8,
+    pub baud_rate: BaudRate,
+    pub input_sensor_type: InputType,
+    pub display_unit: DisplayUnit,
+    pub status: Status,
+}
+
+/// `unit 1: K, Celsius, Baud9600`
+impl fmt::Display for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unit {}: {}, {}, {}",
+            self.unit_id, self.input_sensor_type, self.display_unit, self.baud_rate
+        )
+    }
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    /// Check that a SYL-2381 answers at this driver's unit ID.
+    ///
+    /// Id must read back as the driver's unit ID, and bAud, INTY and CorF
+    /// must hold values the controller can take; anything else is reported
+    /// as [`Error::VerificationFailed`] or [`Error::UnexpectedValue`].
+    pub fn probe(&mut self) -> crate::Result<Identity, UART> {
+        let unit_id = self.get_unit_id()?;
+        if unit_id != self.unit_id {
+            return Err(Error::VerificationFailed {
+                expected: self.unit_id as f32,
+                actual: unit_id as f32,
+            });
+        }
+
+        Ok(Identity {
+            unit_id,
+            baud_rate: self.get_baud_rate()?,
+            input_sensor_type: self.get_input_sensor_type()?,
+            display_unit: self.get_display_unit()?,
+            status: self.get_status()?,
+        })
+    }
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: Clock,
+{
+    /// Check that the controller is still answering, returning how long it
+    /// took by the driver's clock.
+    ///
+    /// This reads a single status coil, the shortest exchange the controller
+    /// has. An answer that takes longer than `timeout`, or that the transport
+    /// gives up waiting for, is reported as [`Error::Timeout`].
+    pub fn ping(&mut self, timeout: Duration) -> crate::Result<Duration, UART> {
+        let start = self.clock().now();
+        self.get_coils(regs::AT, 1)?;
+        let took = self.clock().now().saturating_sub(start);
+        if took > timeout {
+            return Err(Error::Timeout(Context::new(Operation::ReadCoils, regs::AT)));
+        }
+        Ok(took)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::VirtualClock;
+    use crate::mock::{frame, MockSerial};
+
+    #[test]
+    fn identifies_the_controller() {
+        let mut port = MockSerial::new();
+        port.respond_f32(5, 5.0); // Id
+        port.respond_f32(5, 3.0); // bAud
+        port.respond_f32(5, 6.0); // INTY
+        port.respond_f32(5, 0.0); // CorF
+        port.respond(&[5, 0x01, 1, 0x00]); // AT
+        let mut pid = Syl2381::new(5, port);
+
+        let id = pid.probe().unwrap();
+        assert!(pid.port.rx.is_empty());
+        assert_eq!(id.to_string(), "unit 5: K, Celsius, Baud9600");
+    }
+
+    #[test]
+    fn rejects_a_different_id() {
+        let mut port = MockSerial::new();
+        port.respond_f32(5, 12.0);
+        let mut pid = Syl2381::new(5, port);
+
+        assert!(matches!(
+            pid.probe(),
+            Err(Error::VerificationFailed { expected, actual }) if expected == 5.0 && actual == 12.0
+        ));
+    }
+
+    #[test]
+    fn rejects_impossible_settings() {
+        let mut port = MockSerial::new();
+        port.respond_f32(5, 5.0);
+        port.respond_f32(5, 1234.0);
+        let mut pid = Syl2381::new(5, port);
+
+        assert!(matches!(pid.probe(), Err(Error::UnexpectedValue(v)) if v == 1234.0));
+    }
+
+    /// Takes 15 ms to answer.
+    struct Slow<'a> {
+        port: MockSerial,
+        clock: &'a VirtualClock,
+    }
+
+    impl Transport for Slow<'_> {
+        type Error = ();
+
+        fn write_frame(&mut self, frame: &[u8]) -> Result<(), ()> {
+            self.clock.advance(Duration::from_millis(15));
+            self.port.write_frame(frame)
+        }
+
+        fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), ()> {
+            self.port.read_exact(buf)
+        }
+    }
+
+    #[test]
+    fn pings() {
+        let clock = VirtualClock::new();
+        let mut port = MockSerial::new();
+        port.respond(&[5, 0x01, 1, 0x01]);
+        port.respond(&[5, 0x01, 1, 0x01]);
+        let mut pid = Syl2381::new(
+            5,
+            Slow {
+                port,
+                clock: &clock,
+            },
+        )
+        .with_clock(&clock);
+
+        let timeout = Duration::from_millis(20);
+        assert_eq!(pid.ping(timeout).unwrap(), Duration::from_millis(15));
+        assert_eq!(pid.port.port.tx, frame(&[5, 0x01, 0x00, 0x00, 0x00, 0x01]));
+        assert!(matches!(
+            pid.ping(Duration::from_millis(10)),
+            Err(Error::Timeout(ctx)) if ctx.op == Operation::ReadCoils
+        ));
+
+        let mut gone =
+            Syl2381::new(2, crate::simulator::Simulator::new(1, &clock)).with_clock(&clock);
+        assert!(matches!(gone.ping(timeout), Err(Error::Timeout(_))));
+    }
+}
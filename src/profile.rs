@@ -0,0 +1,255 @@
+//! Configuration profiles on disk.
+//!
+//! Keeps a [`Config`](crate::Config) or [`DeviceSnapshot`](crate::DeviceSnapshot)
+//! in a TOML or JSON file, picked by the file's extension, so profiles can
+//! live in version control. Pushing one to a controller is a [`load`]
+//! followed by [`Syl2381::apply`](crate::Syl2381::apply).
+//!
+//! [`load_from`] and [`save_to`] keep profiles in any [`Storage`] instead,
+//! under a key with the same extensions; [`load`] and [`save`] are those
+//! over a [`FileStorage`] in the file's directory.
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::string::String;
+use std::vec;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::storage::{FileStorage, Storage};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Toml,
+    Json,
+}
+
+impl Format {
+    /// The format for a `.toml` or `.json` path.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "toml" => Some(Format::Toml),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+pub fn to_string<T: Serialize>(format: Format, value: &T) -> io::Result<String> {
+    match format {
+        Format::Toml => toml::to_string_pretty(value).map_err(invalid_data),
+        Format::Json => serde_json::to_string_pretty(value).map_err(invalid_data),
+    }
+}
+
+pub fn from_str<T: DeserializeOwned>(format: Format, s: &str) -> io::Result<T> {
+    match format {
+        Format::Toml => toml::from_str(s).map_err(invalid_data),
+        Format::Json => serde_json::from_str(s).map_err(invalid_data),
+    }
+}
+
+/// Why a profile couldn't be loaded from or saved to a [`Storage`].
+#[derive(Debug)]
+pub enum ProfileError<E> {
+    Storage(E),
+
+    /// Nothing is stored under the key.
+    Missing,
+
+    /// The key isn't a `.toml` or `.json` name, or what's stored under it
+    /// isn't a profile.
+    Invalid(io::Error),
+}
+
+impl<E> From<E> for ProfileError<E> {
+    fn from(err: E) -> Self {
+        ProfileError::Storage(err)
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for ProfileError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileError::Storage(err) => err.fmt(f),
+            ProfileError::Missing => f.write_str("no profile stored under that key"),
+            ProfileError::Invalid(err) => err.fmt(f),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for ProfileError<E> {}
+
+impl From<ProfileError<io::Error>> for io::Error {
+    fn from(err: ProfileError<io::Error>) -> Self {
+        match err {
+            ProfileError::Storage(err) | ProfileError::Invalid(err) => err,
+            ProfileError::Missing => io::ErrorKind::NotFound.into(),
+        }
+    }
+}
+
+/// Read the profile stored under `key`, in the format given by the key's
+/// extension.
+pub fn load_from<T, S>(storage: &mut S, key: &str) -> Result<T, ProfileError<S::Error>>
+where
+    T: DeserializeOwned,
+    S: Storage,
+{
+    let format = format_of(Path::new(key)).map_err(ProfileError::Invalid)?;
+    let len = storage.read(key, &mut [])?.ok_or(ProfileError::Missing)?;
+    let mut blob = vec![0; len];
+    storage.read(key, &mut blob)?;
+    let text = String::from_utf8(blob).map_err(|err| ProfileError::Invalid(invalid_data(err)))?;
+    from_str(format, &text).map_err(ProfileError::Invalid)
+}
+
+/// Store `value` under `key`, in the format given by the key's extension.
+pub fn save_to<T, S>(storage: &mut S, key: &str, value: &T) -> Result<(), ProfileError<S::Error>>
+where
+    T: Serialize,
+    S: Storage,
+{
+    let format = format_of(Path::new(key)).map_err(ProfileError::Invalid)?;
+    let text = to_string(format, value).map_err(ProfileError::Invalid)?;
+    Ok(storage.write(key, text.as_bytes())?)
+}
+
+/// Read a profile, in the format given by the extension.
+pub fn load<T: DeserializeOwned>(path: impl AsRef<Path>) -> io::Result<T> {
+    let (mut storage, key) = file_storage(path.as_ref())?;
+    Ok(load_from(&mut storage, key)?)
+}
+
+/// Write a profile, in the format given by the extension.
+///
+/// The file is replaced in one step, so a crash mid-write leaves the old
+/// profile in place.
+pub fn save<T: Serialize>(path: impl AsRef<Path>, value: &T) -> io::Result<()> {
+    let (mut storage, key) = file_storage(path.as_ref())?;
+    Ok(save_to(&mut storage, key, value)?)
+}
+
+/// A [`FileStorage`] in `path`'s directory, and `path`'s file name as the key.
+fn file_storage(path: &Path) -> io::Result<(FileStorage, &str)> {
+    let key = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "profile path must end in a file name",
+            )
+        })?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    Ok((FileStorage::new(dir), key))
+}
+
+fn format_of(path: &Path) -> io::Result<Format> {
+    Format::from_path(path).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "profile must be a .toml or .json file",
+        )
+    })
+}
+
+fn invalid_data<E>(err: E) -> io::Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, InputType, Seconds, TempRaw};
+
+    #[test]
+    fn round_trips_through_files() {
+        let cfg = Config {
+            input_sensor_type: Some(InputType::K),
+            i: Some(Seconds(120)),
+            sv: Some(TempRaw(65.0)),
+            ..Config::default()
+        };
+        let dir = std::env::temp_dir();
+
+        for name in ["syl2381-profile.toml", "syl2381-profile.json"] {
+            let path = dir.join(name);
+            save(&path, &cfg).unwrap();
+            let loaded: Config = load(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+
+            assert!(matches!(loaded.input_sensor_type, Some(InputType::K)));
+            assert!(matches!(loaded.i, Some(Seconds(120))));
+            assert!(matches!(loaded.sv, Some(TempRaw(v)) if v == 65.0));
+            assert!(loaded.p.is_none());
+        }
+    }
+
+    #[test]
+    fn reads_hand_written_toml() {
+        let cfg: Config = from_str(Format::Toml, "sv = 20.5\nfilter = \"Weak\"\n").unwrap();
+        assert!(matches!(cfg.sv, Some(TempRaw(v)) if v == 20.5));
+        assert!(matches!(cfg.filter, Some(crate::Filter::Weak)));
+
+        let err = from_str::<Config>(Format::Toml, "sv = \"hot\"").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    /// Blobs kept in memory.
+    #[derive(Default)]
+    struct Memory(std::collections::HashMap<String, std::vec::Vec<u8>>);
+
+    impl Storage for Memory {
+        type Error = ();
+
+        fn read(&mut self, key: &str, buf: &mut [u8]) -> Result<Option<usize>, ()> {
+            Ok(self.0.get(key).map(|blob| {
+                let n = blob.len().min(buf.len());
+                buf[..n].copy_from_slice(&blob[..n]);
+                blob.len()
+            }))
+        }
+
+        fn write(&mut self, key: &str, blob: &[u8]) -> Result<(), ()> {
+            self.0.insert(key.into(), blob.into());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn round_trips_through_any_storage() {
+        let cfg = Config {
+            sv: Some(TempRaw(62.5)),
+            ..Config::default()
+        };
+        let mut storage = Memory::default();
+
+        save_to(&mut storage, "profiles/sous-vide.json", &cfg).unwrap();
+        let loaded: Config = load_from(&mut storage, "profiles/sous-vide.json").unwrap();
+        assert!(matches!(loaded.sv, Some(TempRaw(v)) if v == 62.5));
+
+        assert!(matches!(
+            load_from::<Config, _>(&mut storage, "profiles/mash.toml"),
+            Err(ProfileError::Missing)
+        ));
+        storage
+            .write("profiles/mash.toml", b"sv = \"hot\"")
+            .unwrap();
+        assert!(matches!(
+            load_from::<Config, _>(&mut storage, "profiles/mash.toml"),
+            Err(ProfileError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn needs_a_known_extension() {
+        let err = save("profile.yaml", &Config::default()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
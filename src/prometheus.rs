@@ -0,0 +1,305 @@
+//! Prometheus metrics.
+//!
+//! A gateway polling a few controllers can be scraped by an existing
+//! Prometheus and Grafana setup directly. Keep a [`Metrics`] per controller,
+//! [`update`](Metrics::update) it on each poll, and serve [`render`]'s output
+//! (text exposition format 0.0.4) on the scrape endpoint. Each controller's
+//! [`Labels`] become its metric labels, so give them distinct names.
+
+use core::fmt::{self, Write};
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+
+use crate::clock::{Clock, DriverClock};
+use crate::logger::Record;
+use crate::stats::Stats;
+use crate::transport::Transport;
+use crate::{Labels, Syl2381};
+
+/// The latest reading of a controller, and how polling it has gone.
+#[derive(Clone, Copy, Default, fmt::Debug)]
+pub struct Metrics {
+    reads: u64,
+    errors: u64,
+    up: bool,
+    last: Option<Record>,
+    stats: Option<Stats>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics::default()
+    }
+
+    /// [`Record::read`] at `clock`'s current time, and account for it along
+    /// with the driver's [`Stats`].
+    pub fn update<UART, CLOCK>(
+        &mut self,
+        pid: &mut Syl2381<UART, CLOCK>,
+        clock: &impl Clock,
+    ) -> crate::Result<Record, UART>
+    where
+        UART: Transport,
+        CLOCK: DriverClock,
+    {
+        let res = Record::read(pid, clock.now());
+        self.observe(&res);
+        self.stats = Some(pid.stats());
+        res
+    }
+
+    /// Account for a reading taken some other way.
+    pub fn observe<E>(&mut self, res: &Result<Record, E>) {
+        self.reads += 1;
+        self.up = res.is_ok();
+        match res {
+            Ok(record) => self.last = Some(*record),
+            Err(_) => self.errors += 1,
+        }
+    }
+
+    /// The last successful reading.
+    pub fn last(&self) -> Option<&Record> {
+        self.last.as_ref()
+    }
+
+    /// Whether the latest reading succeeded.
+    pub fn up(&self) -> bool {
+        self.up
+    }
+
+    /// The latest reading, if it succeeded.
+    fn current(&self) -> Option<&Record> {
+        self.last.as_ref().filter(|_| self.up)
+    }
+
+    pub fn reads(&self) -> u64 {
+        self.reads
+    }
+
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    /// The driver's counts as of the last [`update`](Metrics::update).
+    pub fn stats(&self) -> Option<&Stats> {
+        self.stats.as_ref()
+    }
+}
+
+type Value = fn(&Metrics) -> Option<f64>;
+
+/// Name, type, help text and value of each metric.
+const FAMILIES: [(&str, &str, &str, Value); 14] = [
+    (
+        "syl2381_pv",
+        "gauge",
+        "Process value (PV), in the controller's display unit.",
+        |m| m.current().map(|r| r.pv.0 as f64),
+    ),
+    (
+        "syl2381_sv",
+        "gauge",
+        "Set value (SV), in the controller's display unit.",
+        |m| m.current().map(|r| r.sv.0 as f64),
+    ),
+    (
+        "syl2381_out_ratio",
+        "gauge",
+        "Output power (OUT), from 0 to 1.",
+        |m| m.current().map(|r| r.out.0 as f64),
+    ),
+    (
+        "syl2381_alarm",
+        "gauge",
+        "Whether the J1 alarm (AL1_STA) is on.",
+        |m| m.current().map(|r| r.status.alarm1() as u8 as f64),
+    ),
+    (
+        "syl2381_sensor_fault",
+        "gauge",
+        "Whether the controller flags an input anomaly.",
+        |m| m.current().map(|r| r.status.anomaly() as u8 as f64),
+    ),
+    (
+        "syl2381_up",
+        "gauge",
+        "Whether the latest reading succeeded.",
+        |m| (m.reads > 0).then_some(m.up as u8 as f64),
+    ),
+    (
+        "syl2381_reads_total",
+        "counter",
+        "Readings attempted.",
+        |m| Some(m.reads as f64),
+    ),
+    (
+        "syl2381_read_errors_total",
+        "counter",
+        "Readings that failed.",
+        |m| Some(m.errors as f64),
+    ),
+    (
+        "syl2381_requests_total",
+        "counter",
+        "Request frames sent.",
+        |m| m.stats.map(|s| s.requests as f64),
+    ),
+    (
+        "syl2381_crc_errors_total",
+        "counter",
+        "Responses that failed their CRC check.",
+        |m| m.stats.map(|s| s.crc_errors as f64),
+    ),
+    (
+        "syl2381_timeouts_total",
+        "counter",
+        "Requests that got no complete response in time.",
+        |m| m.stats.map(|s| s.timeouts as f64),
+    ),
+    (
+        "syl2381_retries_total",
+        "counter",
+        "Requests sent again straight after they failed.",
+        |m| m.stats.map(|s| s.retries as f64),
+    ),
+    (
+        "syl2381_exceptions_total",
+        "counter",
+        "Exception responses.",
+        |m| m.stats.map(|s| s.exceptions as f64),
+    ),
+    (
+        "syl2381_bad_frames_total",
+        "counter",
+        "Responses that made no sense: endless noise, or a header or length that doesn't fit the request.",
+        |m| m.stats.map(|s| s.bad_frames as f64),
+    ),
+];
+
+/// Write every metric of every controller to `out`.
+///
+/// Readings are left out for a controller whose latest read failed, so a
+/// dead controller doesn't keep reporting its last values; `syl2381_up` says
+/// which.
+pub fn render(out: &mut impl Write, controllers: &[(&Labels, &Metrics)]) -> fmt::Result {
+    for (name, kind, help, value) in FAMILIES {
+        writeln!(out, "# HELP {name} {help}")?;
+        writeln!(out, "# TYPE {name} {kind}")?;
+        for (labels, metrics) in controllers {
+            if let Some(val) = value(metrics) {
+                write!(out, "{name}")?;
+                write_labels(out, labels)?;
+                writeln!(out, " {val}")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// [`render`] into a new `String`, ready to serve.
+#[cfg(feature = "alloc")]
+pub fn render_to_string(controllers: &[(&Labels, &Metrics)]) -> String {
+    let mut out = String::new();
+    // Writing to a String can't fail.
+    let _ = render(&mut out, controllers);
+    out
+}
+
+fn write_labels(out: &mut impl Write, labels: &Labels) -> fmt::Result {
+    let pairs = [
+        ("name", &labels.name),
+        ("location", &labels.location),
+        ("probe", &labels.probe),
+    ];
+    let mut sep = '{';
+    for (key, val) in pairs {
+        if val.is_empty() {
+            continue;
+        }
+        write!(out, "{sep}{key}=\"")?;
+        for c in val.chars() {
+            match c {
+                '\\' => out.write_str("\\\\")?,
+                '"' => out.write_str("\\\"")?,
+                '\n' => out.write_str("\\n")?,
+                c => out.write_char(c)?,
+            }
+        }
+        out.write_char('"')?;
+        sep = ',';
+    }
+    if sep == ',' {
+        out.write_char('}')?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::VirtualClock;
+    use crate::mock::MockSerial;
+
+    #[test]
+    fn renders_the_exposition_format() {
+        let clock = VirtualClock::new();
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x03, 8, 0x42, 0x70, 0, 0, 0x3E, 0x80, 0, 0]);
+        port.respond_f32(1, 65.0);
+        port.respond(&[1, 0x01, 1, 0b10_0000]);
+        let mut pid = Syl2381::new(1, port);
+        let mut kiln = Metrics::new();
+        assert!(kiln.update(&mut pid, &clock).is_ok());
+        let mut oven = kiln;
+        assert!(oven.update(&mut pid, &clock).is_err());
+        let smoker = Metrics::new();
+
+        let kiln_labels = Labels::new("Kiln \"2\"").with_location("Shed");
+        let oven_labels = Labels::new("Oven");
+        let smoker_labels = Labels::new("Smoker");
+        let mut text = String::new();
+        render(
+            &mut text,
+            &[
+                (&kiln_labels, &kiln),
+                (&oven_labels, &oven),
+                (&smoker_labels, &smoker),
+            ],
+        )
+        .unwrap();
+
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(
+            lines[..3],
+            [
+                "# HELP syl2381_pv Process value (PV), in the controller's display unit.",
+                "# TYPE syl2381_pv gauge",
+                "syl2381_pv{name=\"Kiln \\\"2\\\"\",location=\"Shed\"} 60",
+            ]
+        );
+        assert!(lines.contains(&"syl2381_alarm{name=\"Kiln \\\"2\\\"\",location=\"Shed\"} 1"));
+        assert!(lines.contains(&"syl2381_up{name=\"Kiln \\\"2\\\"\",location=\"Shed\"} 1"));
+        assert!(lines.contains(&"syl2381_up{name=\"Oven\"} 0"));
+        assert!(lines.contains(&"syl2381_read_errors_total{name=\"Oven\"} 1"));
+        assert!(
+            lines.contains(&"syl2381_requests_total{name=\"Kiln \\\"2\\\"\",location=\"Shed\"} 3")
+        );
+        assert!(lines.contains(&"syl2381_requests_total{name=\"Oven\"} 4"));
+        assert!(lines.contains(&"syl2381_crc_errors_total{name=\"Oven\"} 0"));
+        assert!(!lines
+            .iter()
+            .any(|l| l.starts_with("syl2381_pv{name=\"Oven\"")));
+        assert!(!lines
+            .iter()
+            .any(|l| l.starts_with("syl2381_up{name=\"Smoker\"")));
+        assert!(lines.contains(&"syl2381_reads_total{name=\"Smoker\"} 0"));
+        assert!(!lines
+            .iter()
+            .any(|l| l.starts_with("syl2381_requests_total{name=\"Smoker\"")));
+        assert!(!lines
+            .iter()
+            .any(|l| l.starts_with("syl2381_pv{name=\"Smoker\"")));
+    }
+}
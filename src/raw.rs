@@ -0,0 +1,156 @@
+//! Registers the driver doesn't know about.
+//!
+//! Everything else in the crate goes through the register map in
+//! [`regs`](crate::regs). These go straight to an address, for poking at
+//! registers that newer firmware may add before the crate catches up.
+
+use crate::clock::DriverClock;
+use crate::regs;
+use crate::transport::Transport;
+use crate::{AckValue, Context, Error, Frame, Operation, Syl2381};
+
+/// Most holding registers one request can read.
+pub const MAX_READ_WORDS: usize = 125;
+
+/// Most holding registers one request can write.
+pub const MAX_WRITE_WORDS: usize = 123;
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    /// Read `count` holding registers starting at `addr`, as they are on the
+    /// wire.
+    pub fn read_holding_raw(
+        &mut self,
+        addr: u16,
+        count: u16,
+    ) -> crate::Result<heapless::Vec<u16, MAX_READ_WORDS>, UART> {
+        if count == 0 || count as usize > MAX_READ_WORDS {
+            return Err(Error::UnexpectedValue(count as f32));
+        }
+
+        let ctx = Context {
+            op: Operation::ReadHolding,
+            addr,
+        };
+        let fail = |kind| Error::modbus(ctx, kind);
+        let mut frame = Frame::get_words(self.unit_id, addr, count).map_err(fail)?;
+        self.transact(ctx, &mut frame)?;
+        let words = frame.parse_words().map_err(fail)?;
+        if words.len() != count as usize {
+            return Err(fail(rmodbus::ErrorKind::FrameBroken));
+        }
+
+        Ok(words)
+    }
+
+    /// Write `values` to consecutive holding registers starting at `addr`.
+    ///
+    /// Nothing is range checked, any cached readings are dropped, and a
+    /// [`WriteGuard`](crate::WriteGuard) doesn't apply.
+    pub fn write_holding_raw(&mut self, addr: u16, values: &[u16]) -> crate::Result<(), UART> {
+        if values.is_empty() || values.len() > MAX_WRITE_WORDS {
+            return Err(Error::UnexpectedValue(values.len() as f32));
+        }
+        let ctx = Context {
+            op: Operation::WriteHolding,
+            addr,
+        };
+        self.check_lockout(ctx)?;
+        self.live.clear();
+        self.writes.forget();
+        self.statics.clear();
+        let fail = |kind| Error::modbus(ctx, kind);
+        let mut frame = Frame::set_words(self.unit_id, addr, values).map_err(fail)?;
+        self.transact(ctx, &mut frame)?;
+        frame.parse_ok().map_err(fail)?;
+        for (addr, &word) in (addr..).zip(values) {
+            self.acknowledged(addr, AckValue::Word(word));
+        }
+
+        Ok(())
+    }
+
+    /// Read the coil at `addr`.
+    pub fn read_coil_raw(&mut self, addr: u16) -> crate::Result<bool, UART> {
+        let val = self.read_coils_raw(addr, 1)?;
+        Ok(val & 1 == 1)
+    }
+
+    /// Read `count` (at most 8) coils starting at `addr`, the first in bit 0.
+    pub fn read_coils_raw(&mut self, addr: u16, count: u8) -> crate::Result<u8, UART> {
+        if count == 0 || count > 8 {
+            return Err(Error::UnexpectedValue(count as f32));
+        }
+
+        let ctx = Context {
+            op: Operation::ReadCoils,
+            addr,
+        };
+        let fail = |kind| Error::modbus(ctx, kind);
+        let mut frame = Frame::get_coils(self.unit_id, addr, count).map_err(fail)?;
+        self.transact(ctx, &mut frame)?;
+        let val = frame.parse_coils().map_err(fail)?;
+
+        Ok(val)
+    }
+
+    /// Switch the coil at `addr` on or off, without reading it back.
+    pub fn write_coil_raw(&mut self, addr: u16, on: bool) -> crate::Result<(), UART> {
+        let ctx = Context {
+            op: Operation::WriteCoil,
+            addr,
+        };
+        self.check_lockout(ctx)?;
+        self.live.invalidate(regs::Reg { addr, ..regs::AT });
+        let fail = |kind| Error::modbus(ctx, kind);
+        let mut frame = Frame::set_coil(self.unit_id, addr, on).map_err(fail)?;
+        self.transact(ctx, &mut frame)?;
+        frame.parse_ok().map_err(fail)?;
+        self.acknowledged(addr, AckValue::Coil(on));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{frame, MockSerial};
+
+    #[test]
+    fn reads_and_writes_any_address() {
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x03, 6, 0x00, 0x01, 0x00, 0x02, 0x00, 0x03]);
+        port.respond(&[1, 0x10, 0x30, 0x00, 0, 1]);
+        port.respond(&[1, 0x01, 1, 0x01]);
+        let mut pid = Syl2381::new(1, port);
+
+        let words = pid.read_holding_raw(0x3000, 3).unwrap();
+        assert_eq!(words[..], [1, 2, 3]);
+        assert!(pid.write_holding_raw(0x3000, &[0xBEEF]).is_ok());
+        assert!(matches!(pid.read_coil_raw(0x0040), Ok(true)));
+        assert_eq!(
+            pid.port.tx[8..19],
+            frame(&[1, 0x10, 0x30, 0x00, 0, 1, 2, 0xBE, 0xEF])
+        );
+        assert_eq!(pid.port.tx[19..], frame(&[1, 0x01, 0x00, 0x40, 0, 1]));
+    }
+
+    #[test]
+    fn rejects_oversized_requests() {
+        let mut pid = Syl2381::new(1, MockSerial::new());
+
+        assert!(matches!(
+            pid.read_holding_raw(0x3000, 126),
+            Err(Error::UnexpectedValue(v)) if v == 126.0
+        ));
+        assert!(matches!(
+            pid.write_holding_raw(0x3000, &[]),
+            Err(Error::UnexpectedValue(_))
+        ));
+        assert!(pid.port.tx.is_empty());
+    }
+}
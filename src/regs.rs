@@ -0,0 +1,154 @@
+//! Register map.
+//!
+//! Every register the driver touches is declared once here and listed in
+//! [`TABLE`]. The table is checked at compile time: a register must sit inside
+//! the address bank it claims, and no two registers of the same kind may
+//! overlap (holding parameters are f32s spanning two words).
+
+use core::fmt;
+
+/// The Modbus address space a register lives in.
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Kind {
+    /// A holding parameter; an f32 spanning two consecutive registers.
+    Holding,
+
+    /// A single coil.
+    Coil,
+}
+
+/// The block of addresses a register belongs to.
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Bank {
+    /// Status flags (coils 0x0000-0x00FF).
+    Status,
+
+    /// SV and the J1 alarm temperatures (0x0000-0x00FF).
+    Setpoint,
+
+    /// Live process values and the OUT control flag (0x0100-0x01FF).
+    Process,
+
+    /// PID tuning parameters (0x1000-0x10FF).
+    Pid,
+
+    /// Input, output and communication settings (0x2000-0x20FF).
+    System,
+}
+
+impl Bank {
+    pub const fn kind(self) -> Kind {
+        match self {
+            Bank::Status => Kind::Coil,
+            _ => Kind::Holding,
+        }
+    }
+
+    /// First address of the bank.
+    pub const fn start(self) -> u16 {
+        match self {
+            Bank::Status | Bank::Setpoint => 0x0000,
+            Bank::Process => 0x0100,
+            Bank::Pid => 0x1000,
+            Bank::System => 0x2000,
+        }
+    }
+
+    /// Number of addresses in the bank.
+    pub(crate) const fn len(self) -> u16 {
+        0x0100
+    }
+}
+
+#[derive(Clone, Copy, fmt::Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Reg {
+    pub addr: u16,
+    pub bank: Bank,
+}
+
+impl Reg {
+    const fn new(addr: u16, bank: Bank) -> Self {
+        Reg { addr, bank }
+    }
+
+    pub const fn kind(self) -> Kind {
+        self.bank.kind()
+    }
+
+    /// Number of addresses occupied.
+    pub const fn width(self) -> u16 {
+        match self.kind() {
+            Kind::Holding => 2,
+            Kind::Coil => 1,
+        }
+    }
+}
+
+pub const AT: Reg = Reg::new(0x0000, Bank::Status);
+pub const AM: Reg = Reg::new(0x0001, Bank::Status);
+pub const COOL: Reg = Reg::new(0x0002, Bank::Status);
+pub const SETTING: Reg = Reg::new(0x0003, Bank::Status);
+pub const ANOMALY: Reg = Reg::new(0x0004, Bank::Status);
+pub const AL1_STA: Reg = Reg::new(0x0005, Bank::Status);
+
+pub const SV: Reg = Reg::new(0x0000, Bank::Setpoint);
+pub const AH1: Reg = Reg::new(0x0002, Bank::Setpoint);
+pub const AL1: Reg = Reg::new(0x0004, Bank::Setpoint);
+
+pub const PV: Reg = Reg::new(0x0164, Bank::Process);
+pub const OUT: Reg = Reg::new(0x0166, Bank::Process);
+pub const CV: Reg = Reg::new(0x016C, Bank::Process);
+
+pub const P: Reg = Reg::new(0x1000, Bank::Pid);
+pub const I: Reg = Reg::new(0x1002, Bank::Pid);
+pub const D: Reg = Reg::new(0x1004, Bank::Pid);
+pub const BB: Reg = Reg::new(0x1006, Bank::Pid);
+pub const SOUF: Reg = Reg::new(0x1008, Bank::Pid);
+pub const OT: Reg = Reg::new(0x100A, Bank::Pid);
+pub const FILT: Reg = Reg::new(0x100C, Bank::Pid);
+
+pub const INTY: Reg = Reg::new(0x2000, Bank::System);
+pub const OUTY: Reg = Reg::new(0x2002, Bank::System);
+pub const COTY: Reg = Reg::new(0x2004, Bank::System);
+pub const HY: Reg = Reg::new(0x2006, Bank::System);
+pub const PSB: Reg = Reg::new(0x2008, Bank::System);
+pub const RD: Reg = Reg::new(0x200A, Bank::System);
+pub const CORF: Reg = Reg::new(0x200C, Bank::System);
+pub const ID: Reg = Reg::new(0x200E, Bank::System);
+pub const BAUD: Reg = Reg::new(0x2010, Bank::System);
+
+/// Every register known to the driver.
+pub const TABLE: &[Reg] = &[
+    AT, AM, COOL, SETTING, ANOMALY, AL1_STA, SV, AH1, AL1, PV, OUT, CV, P, I, D, BB, SOUF, OT,
+    FILT, INTY, OUTY, COTY, HY, PSB, RD, CORF, ID, BAUD,
+];
+
+const _: () = check(TABLE);
+
+const fn check(table: &[Reg]) {
+    let mut i = 0;
+    while i < table.len() {
+        let a = table[i];
+        let start = a.bank.start();
+        assert!(
+            a.addr >= start && a.addr + a.width() <= start + a.bank.len(),
+            "register outside its bank"
+        );
+
+        let mut j = i + 1;
+        while j < table.len() {
+            let b = table[j];
+            let same_space = matches!(
+                (a.kind(), b.kind()),
+                (Kind::Holding, Kind::Holding) | (Kind::Coil, Kind::Coil)
+            );
+            let overlap = a.addr < b.addr + b.width() && b.addr < a.addr + a.width();
+            assert!(!(same_space && overlap), "overlapping registers");
+            j += 1;
+        }
+        i += 1;
+    }
+}
@@ -0,0 +1,404 @@
+//! Fixed-rate sampling.
+//!
+//! Logging and plotting want readings on a regular grid, and want the grid to
+//! survive the odd timeout. A [`Sampler`] waits for each slot on its clock,
+//! takes a reading, and schedules the next slot from the previous one rather
+//! than from when the reading finished, so the period doesn't drift. A failed
+//! reading is returned for that slot alone; sampling carries on at the next.
+//! Slots that passed while the caller was busy or a reading overran are
+//! skipped, not made up in a burst.
+//!
+//! Each sample carries its [`Provenance`]: how many requests it took, whether
+//! any of them repeated one that had just failed, and how long the reading
+//! took. After a noisy spell on the bus, a data-quality filter downstream can
+//! use it to drop the points that are suspect.
+//!
+//! With an [`AsyncClock`], [`Sampler::next_sample_async`] does the same
+//! against the async driver, so a dashboard task can loop on it without
+//! blocking its executor.
+
+use core::fmt;
+use core::time::Duration;
+
+#[cfg(feature = "async")]
+use crate::asynch::AsyncResult;
+#[cfg(feature = "async")]
+use crate::client::ModbusClient;
+#[cfg(feature = "async")]
+use crate::clock::AsyncClock;
+use crate::clock::{Clock, DriverClock, NoClock};
+use crate::transport::Transport;
+#[cfg(feature = "async")]
+use crate::Syl2381Async;
+use crate::{Percent, Stats, Status, Syl2381, TempRaw};
+
+/// One reading.
+#[derive(Clone, Copy, fmt::Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sample {
+    /// The slot the reading was taken for, on the sampler's clock.
+    pub at: Duration,
+    pub pv: TempRaw,
+
+    /// Only read with [`Sampler::with_out`].
+    pub out: Option<Percent>,
+
+    /// Only read with [`Sampler::with_status`].
+    pub status: Option<Status>,
+
+    pub provenance: Provenance,
+}
+
+/// How a reading was taken.
+#[derive(Clone, Copy, Default, fmt::Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Provenance {
+    /// Whether PV and OUT came back in one request, and so belong to the same
+    /// instant.
+    pub bulk: bool,
+
+    /// Requests sent for the reading.
+    pub requests: u32,
+
+    /// Of those, requests sent again straight after they failed, as counted
+    /// in [`Stats::retries`]. The async driver keeps no stats, so its readings
+    /// always show none.
+    pub retries: u32,
+
+    /// From sending the first request to receiving the last response, on the
+    /// sampler's or logger's clock.
+    pub latency: Duration,
+}
+
+impl Provenance {
+    /// The requests made between two readings of a driver's stats.
+    pub(crate) fn between(before: Stats, after: Stats, bulk: bool) -> Self {
+        Provenance {
+            bulk,
+            requests: after.requests.wrapping_sub(before.requests),
+            retries: after.retries.wrapping_sub(before.retries),
+            latency: Duration::ZERO,
+        }
+    }
+
+    /// Whether the reading needed a retry or took longer than `max_latency`,
+    /// as readings do while the bus is noisy.
+    pub fn is_suspect(&self, max_latency: Duration) -> bool {
+        self.retries > 0 || self.latency > max_latency
+    }
+}
+
+/// `PV 65.2°, OUT 40%`
+impl fmt::Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PV {}", self.pv)?;
+        if let Some(out) = self.out {
+            write!(f, ", OUT {}", out)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Sampler<C> {
+    clock: C,
+    period: Duration,
+    out: bool,
+    status: bool,
+    next: Option<Duration>,
+    errors: u32,
+    missed: u32,
+}
+
+impl<C> Sampler<C> {
+    /// Sample PV every `period`, which is at least 1 ms.
+    pub fn new(clock: C, period: Duration) -> Self {
+        Sampler {
+            clock,
+            period: period.max(Duration::from_millis(1)),
+            out: false,
+            status: false,
+            next: None,
+            errors: 0,
+            missed: 0,
+        }
+    }
+
+    /// Read OUT along with PV, in the same request on the blocking driver.
+    pub fn with_out(mut self) -> Self {
+        self.out = true;
+        self
+    }
+
+    /// Read the status flags too, in a request of their own.
+    pub fn with_status(mut self) -> Self {
+        self.status = true;
+        self
+    }
+
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Readings that failed so far.
+    pub fn errors(&self) -> u32 {
+        self.errors
+    }
+
+    /// Slots skipped because they had passed before sampling got to them.
+    pub fn missed(&self) -> u32 {
+        self.missed
+    }
+
+    /// The slot to sample next, given the time is `now`.
+    fn due(&mut self, now: Duration) -> Duration {
+        let mut slot = self.next.unwrap_or(now);
+        while slot + self.period <= now {
+            slot += self.period;
+            self.missed = self.missed.saturating_add(1);
+        }
+        slot
+    }
+
+    /// Count a reading for the slot `at` and schedule the next one.
+    fn taken<T, E>(&mut self, at: Duration, res: &core::result::Result<T, E>) {
+        if res.is_err() {
+            self.errors = self.errors.saturating_add(1);
+        }
+        self.next = Some(at + self.period);
+    }
+}
+
+impl<C> Sampler<C>
+where
+    C: Clock,
+{
+    /// Wait for the next slot and take a reading.
+    ///
+    /// The first call samples straight away.
+    pub fn next_sample<UART, CLOCK>(
+        &mut self,
+        pid: &mut Syl2381<UART, CLOCK>,
+    ) -> crate::Result<Sample, UART>
+    where
+        UART: Transport,
+        CLOCK: DriverClock,
+    {
+        let now = self.clock.now();
+        let at = self.due(now);
+        if at > now {
+            self.clock.sleep(at - now);
+        }
+
+        let res = self.read(pid, at);
+        self.taken(at, &res);
+        res
+    }
+
+    /// An endless iterator over readings of `pid`, one per slot.
+    pub fn samples<'a, UART, CLOCK>(
+        &'a mut self,
+        pid: &'a mut Syl2381<UART, CLOCK>,
+    ) -> Samples<'a, C, UART, CLOCK>
+    where
+        UART: Transport,
+        CLOCK: DriverClock,
+    {
+        Samples { sampler: self, pid }
+    }
+
+    fn read<UART, CLOCK>(
+        &self,
+        pid: &mut Syl2381<UART, CLOCK>,
+        at: Duration,
+    ) -> crate::Result<Sample, UART>
+    where
+        UART: Transport,
+        CLOCK: DriverClock,
+    {
+        let before = pid.stats();
+        let start = self.clock.now();
+        let (pv, out) = if self.out {
+            let (pv, out) = pid.get_pv_out()?;
+            (pv, Some(out))
+        } else {
+            (pid.get_pv()?, None)
+        };
+        let status = match self.status {
+            true => Some(pid.get_status()?),
+            false => None,
+        };
+
+        let mut provenance = Provenance::between(before, pid.stats(), self.out);
+        provenance.latency = self.clock.now().saturating_sub(start);
+        Ok(Sample {
+            at,
+            pv,
+            out,
+            status,
+            provenance,
+        })
+    }
+}
+
+#[cfg(feature = "async")]
+impl<C> Sampler<C>
+where
+    C: AsyncClock,
+{
+    /// Wait for the next slot and take a reading, as
+    /// [`Sampler::next_sample`] does. OUT takes a request of its own.
+    pub async fn next_sample_async<UART>(
+        &mut self,
+        pid: &mut Syl2381Async<UART>,
+    ) -> AsyncResult<Sample, UART>
+    where
+        UART: ModbusClient,
+    {
+        let now = self.clock.now();
+        let at = self.due(now);
+        if at > now {
+            self.clock.sleep_async(at - now).await;
+        }
+
+        let res = self.read_async(pid, at).await;
+        self.taken(at, &res);
+        res
+    }
+
+    async fn read_async<UART>(
+        &self,
+        pid: &mut Syl2381Async<UART>,
+        at: Duration,
+    ) -> AsyncResult<Sample, UART>
+    where
+        UART: ModbusClient,
+    {
+        let start = self.clock.now();
+        let pv = pid.get_pv().await?;
+        let out = match self.out {
+            true => Some(pid.get_out().await?),
+            false => None,
+        };
+        let status = match self.status {
+            true => Some(pid.get_status().await?),
+            false => None,
+        };
+
+        let provenance = Provenance {
+            bulk: false,
+            requests: 1 + self.out as u32 + self.status as u32,
+            retries: 0,
+            latency: self.clock.now().saturating_sub(start),
+        };
+        Ok(Sample {
+            at,
+            pv,
+            out,
+            status,
+            provenance,
+        })
+    }
+}
+
+/// See [`Sampler::samples`].
+pub struct Samples<'a, C, UART, CLOCK = NoClock> {
+    sampler: &'a mut Sampler<C>,
+    pid: &'a mut Syl2381<UART, CLOCK>,
+}
+
+impl<C, UART, CLOCK> Iterator for Samples<'_, C, UART, CLOCK>
+where
+    C: Clock,
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    type Item = crate::Result<Sample, UART>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.sampler.next_sample(self.pid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::VirtualClock;
+    use crate::mock::MockSerial;
+
+    #[test]
+    fn samples_on_a_fixed_grid() {
+        let clock = VirtualClock::new();
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 60.0);
+        port.respond(&[1, 0x83, 0x04]); // slave failure
+        port.respond_f32(1, 61.0);
+        let mut pid = Syl2381::new(1, port);
+        let mut sampler = Sampler::new(&clock, Duration::from_secs(1));
+
+        let mut samples = sampler.samples(&mut pid);
+        assert!(matches!(samples.next(), Some(Ok(s)) if s.pv.0 == 60.0));
+        assert!(matches!(samples.next(), Some(Err(_))));
+        let last = samples.next().unwrap().unwrap();
+        assert_eq!(last.at, Duration::from_secs(2));
+        assert_eq!(last.to_string(), "PV 61°");
+        assert_eq!(sampler.errors(), 1);
+        assert_eq!(last.provenance.retries, 1);
+        assert!(last.provenance.is_suspect(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn skips_slots_that_passed() {
+        let clock = VirtualClock::new();
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x03, 8, 0x42, 0x70, 0, 0, 0x3E, 0x80, 0, 0]);
+        port.respond(&[1, 0x01, 1, 0x00]);
+        port.respond(&[1, 0x03, 8, 0x42, 0x70, 0, 0, 0x3E, 0x80, 0, 0]);
+        port.respond(&[1, 0x01, 1, 0x00]);
+        let mut pid = Syl2381::new(1, port);
+        let mut sampler = Sampler::new(&clock, Duration::from_secs(1))
+            .with_out()
+            .with_status();
+
+        let first = sampler.next_sample(&mut pid).unwrap();
+        assert_eq!(first.to_string(), "PV 60°, OUT 25%");
+        assert!(first.status.is_some());
+        assert_eq!(
+            first.provenance,
+            Provenance {
+                bulk: true,
+                requests: 2,
+                retries: 0,
+                latency: Duration::ZERO,
+            }
+        );
+        assert!(!first.provenance.is_suspect(Duration::from_millis(100)));
+        clock.advance(Duration::from_millis(2500));
+        let second = sampler.next_sample(&mut pid).unwrap();
+        assert_eq!(second.at, Duration::from_secs(2));
+        assert_eq!(sampler.missed(), 1);
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn samples_the_async_driver() {
+        use crate::mock::block_on;
+
+        let clock = VirtualClock::new();
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 60.0);
+        port.respond_f32(1, 0.25);
+        port.respond_f32(1, 61.0);
+        port.respond_f32(1, 0.5);
+        let mut pid = Syl2381Async::new(1, port);
+        let mut sampler = Sampler::new(&clock, Duration::from_secs(1)).with_out();
+
+        assert!(block_on(sampler.next_sample_async(&mut pid)).is_ok());
+        let second = block_on(sampler.next_sample_async(&mut pid)).unwrap();
+        assert_eq!(second.at, Duration::from_secs(1));
+        assert_eq!(second.to_string(), "PV 61°, OUT 50%");
+        assert_eq!(clock.now(), Duration::from_secs(1));
+    }
+}
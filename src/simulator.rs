@@ -0,0 +1,681 @@
+//! An in-memory SYL-2381.
+//!
+//! A [`Simulator`] holds a controller's registers and status coils and answers
+//! Modbus RTU requests the way the controller does, so it can stand in for the
+//! serial port: `Syl2381::new(1, Simulator::new(1, clock))`. It checks the CRC
+//! and unit ID of every request, keeps quiet when either is wrong, and answers
+//! requests it can't carry out with exception responses.
+//!
+//! Behind the registers sits a first-order thermal [`Plant`]. Outside manual
+//! mode (and unless CV is set), OUT is proportional to how far PV is short of
+//! SV across the BB band, full output at SV - BB and none at SV. The J1 relay
+//! follows AH1 and AL1 for the alarm output modes, and OUT for the others.
+//! Setting AT autotunes for four of the plant's time constants, after which
+//! AT clears and P, I and D suit the plant.
+//! Time comes from a [`Clock`]; the plant catches up with it on each request,
+//! so with a [`VirtualClock`](crate::clock::VirtualClock) an hour of heating
+//! passes as soon as the clock is advanced.
+//!
+//! To see how code copes with a bad line, [`Simulator::set_fault`] spoils
+//! every response with a [`Fault`], and [`Simulator::inject`] spoils just the
+//! next one.
+//!
+//! With the `simulator-pty` feature, the `syl2381-sim` binary serves a
+//! simulator on a pseudo-terminal, for developing against a virtual serial
+//! port with no controller attached.
+
+use core::time::Duration;
+
+use crate::clock::Clock;
+use crate::frame::crc16;
+use crate::param::{ValueKind, META};
+use crate::regs::{self, Reg};
+use crate::transport::{TimeoutError, Transport};
+use crate::{f32_to_values, limits, values_to_f32};
+
+const READ_COILS: u8 = 0x01;
+const READ_HOLDINGS: u8 = 0x03;
+const WRITE_COIL: u8 = 0x05;
+const WRITE_HOLDING: u8 = 0x06;
+const WRITE_HOLDINGS: u8 = 0x10;
+
+const ILLEGAL_FUNCTION: u8 = 0x01;
+const ILLEGAL_DATA_ADDRESS: u8 = 0x02;
+const ILLEGAL_DATA_VALUE: u8 = 0x03;
+
+/// How many of the plant's time constants autotuning takes.
+const AUTOTUNE_TAUS: u32 = 4;
+
+/// The holding parameters, in register order.
+const HOLDING: [Reg; 22] = [
+    regs::SV,
+    regs::AH1,
+    regs::AL1,
+    regs::PV,
+    regs::OUT,
+    regs::CV,
+    regs::P,
+    regs::I,
+    regs::D,
+    regs::BB,
+    regs::SOUF,
+    regs::OT,
+    regs::FILT,
+    regs::INTY,
+    regs::OUTY,
+    regs::COTY,
+    regs::HY,
+    regs::PSB,
+    regs::RD,
+    regs::CORF,
+    regs::ID,
+    regs::BAUD,
+];
+
+/// Starting values, in [`HOLDING`] order: a K-type heating setup in Celsius.
+const DEFAULTS: [f32; 22] = [
+    100.0, 110.0, 105.0, 20.0, 0.0, 0.0, 10.0, 240.0, 60.0, 30.0, 0.2, 2.0, 1.0, 6.0, 0.0, 0.0,
+    0.3, 0.0, 0.0, 0.0, 1.0, 3.0,
+];
+
+/// What the controller heats (or cools).
+#[derive(Clone, Copy, Debug)]
+pub struct Plant {
+    /// Where PV settles with the output off.
+    pub ambient: f32,
+
+    /// How far from ambient PV settles at full output.
+    pub gain: f32,
+
+    /// How long PV takes to cover about two thirds of the way to where it's
+    /// heading.
+    pub tau: Duration,
+}
+
+impl Default for Plant {
+    /// A small oven: up to 220° in a room at 20°, with a two minute lag.
+    fn default() -> Self {
+        Plant {
+            ambient: 20.0,
+            gain: 200.0,
+            tau: Duration::from_secs(120),
+        }
+    }
+}
+
+/// Something wrong with a response.
+///
+/// Faults only spoil responses the controller would have sent; a request it
+/// ignores stays unanswered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// Send the response with a broken CRC.
+    CorruptCrc,
+
+    /// Send only the first this many bytes of the response.
+    Truncate(usize),
+
+    /// Send the response this late. Reads time out until then, and the
+    /// controller ignores requests while it's still busy; a request after
+    /// that finds the late response still on the line ahead of its own.
+    Delay(Duration),
+
+    /// Answer with this exception code instead of carrying out the request,
+    /// e.g. 0x06 for busy.
+    Exception(u8),
+
+    /// Answer as this unit ID.
+    WrongUnit(u8),
+}
+
+pub struct Simulator<C> {
+    clock: C,
+    plant: Plant,
+    values: [f32; 22],
+    coils: u8,
+    /// When the plant was last brought up to date.
+    last: Duration,
+    /// How long AT has been set.
+    tuning: Duration,
+    fault: Option<Fault>,
+    next_fault: Option<Fault>,
+    response: heapless::Vec<u8, 512>,
+    pos: usize,
+    /// When a delayed response can be read, and how much of `response` (left
+    /// over from before) can be read sooner.
+    ready: Duration,
+    on_time: usize,
+    delayed: bool,
+}
+
+impl<C> Simulator<C>
+where
+    C: Clock,
+{
+    /// A controller at unit ID `unit_id`, sitting at ambient.
+    pub fn new(unit_id: u8, clock: C) -> Self {
+        let last = clock.now();
+        let mut sim = Simulator {
+            clock,
+            plant: Plant::default(),
+            values: DEFAULTS,
+            coils: 0,
+            last,
+            tuning: Duration::ZERO,
+            fault: None,
+            next_fault: None,
+            response: heapless::Vec::new(),
+            pos: 0,
+            ready: Duration::ZERO,
+            on_time: 0,
+            delayed: false,
+        };
+        sim.set_holding(regs::ID, unit_id as f32);
+        sim
+    }
+
+    /// Heat `plant` instead, starting from its ambient.
+    pub fn with_plant(mut self, plant: Plant) -> Self {
+        self.plant = plant;
+        self.set_holding(regs::PV, plant.ambient);
+        self
+    }
+
+    /// Spoil every response with `fault`, or none with `None`.
+    pub fn set_fault(&mut self, fault: Option<Fault>) {
+        self.fault = fault;
+    }
+
+    /// Spoil only the next response with `fault`, ahead of any set with
+    /// [`Simulator::set_fault`].
+    pub fn inject(&mut self, fault: Fault) {
+        self.next_fault = Some(fault);
+    }
+
+    /// The holding parameter at `reg`.
+    pub fn holding(&self, reg: Reg) -> f32 {
+        self.values[index(reg.addr).expect("not a holding parameter")]
+    }
+
+    /// Set a holding parameter directly, as from the front panel.
+    pub fn set_holding(&mut self, reg: Reg, val: f32) {
+        self.values[index(reg.addr).expect("not a holding parameter")] = val;
+    }
+
+    /// The status coil at `reg`.
+    pub fn coil(&self, reg: Reg) -> bool {
+        let coils = self.coils();
+        (coils >> reg.addr) & 1 == 1
+    }
+
+    /// Set a status coil directly, for example to open the setting menu.
+    pub fn set_coil(&mut self, reg: Reg, on: bool) {
+        if on {
+            self.coils |= 1 << reg.addr;
+        } else {
+            self.coils &= !(1 << reg.addr);
+        }
+    }
+
+    /// The coils, with COOL following rd.
+    fn coils(&self) -> u8 {
+        let cool = (self.holding(regs::RD) == 1.0) as u8;
+        (self.coils & !(1 << regs::COOL.addr)) | (cool << regs::COOL.addr)
+    }
+
+    /// Run the plant and the control loop up to the clock's time, a second
+    /// at a time.
+    fn advance(&mut self) {
+        const STEP: Duration = Duration::from_secs(1);
+
+        let now = self.clock.now();
+        while self.last < now {
+            let step = STEP.min(now - self.last);
+            self.last += step;
+            self.tune(step);
+            self.step(step.as_secs_f32());
+        }
+    }
+
+    /// Keep autotuning while AT is set, until it's been long enough to settle
+    /// on gains for the plant.
+    fn tune(&mut self, step: Duration) {
+        if !self.coil(regs::AT) {
+            self.tuning = Duration::ZERO;
+            return;
+        }
+        self.tuning += step;
+        if self.tuning < self.plant.tau * AUTOTUNE_TAUS {
+            return;
+        }
+
+        let tau = self.plant.tau.as_secs_f32();
+        let p = (self.plant.gain.abs() / 2.0).round() / 10.0;
+        let (i, d) = ((2.0 * tau).round(), (tau / 2.0).round());
+        self.set_holding(regs::P, p.clamp(*limits::P.start(), *limits::P.end()));
+        self.set_holding(
+            regs::I,
+            i.clamp(*limits::I.start() as f32, *limits::I.end() as f32),
+        );
+        self.set_holding(
+            regs::D,
+            d.clamp(*limits::D.start() as f32, *limits::D.end() as f32),
+        );
+        self.set_coil(regs::AT, false);
+    }
+
+    fn step(&mut self, secs: f32) {
+        let pv = self.holding(regs::PV);
+        let sv = self.holding(regs::SV);
+        let cooling = self.holding(regs::RD) == 1.0;
+        let held = self.coil(regs::AM) || self.holding(regs::CV) == 1.0;
+
+        let out = if held {
+            self.holding(regs::OUT)
+        } else {
+            let short = if cooling { pv - sv } else { sv - pv };
+            let out = (short / self.holding(regs::BB).max(f32::EPSILON)).clamp(0.0, 1.0);
+            self.set_holding(regs::OUT, out);
+            out
+        };
+
+        let reach = if cooling {
+            -self.plant.gain
+        } else {
+            self.plant.gain
+        };
+        let target = self.plant.ambient + reach * out;
+        let rate = (secs / self.plant.tau.as_secs_f32().max(secs)).min(1.0);
+        let pv = pv + (target - pv) * rate;
+        self.set_holding(regs::PV, pv);
+
+        let (on, off) = match self.holding(regs::OUTY) as u8 {
+            0 | 4 => (self.holding(regs::AH1), self.holding(regs::AL1)),
+            1 => (sv + self.holding(regs::AH1), sv + self.holding(regs::AL1)),
+            _ => {
+                self.set_coil(regs::AL1_STA, out > 0.0);
+                return;
+            }
+        };
+        if pv >= on {
+            self.set_coil(regs::AL1_STA, true);
+        } else if pv <= off {
+            self.set_coil(regs::AL1_STA, false);
+        }
+    }
+
+    /// Answer a complete request frame, for serving the simulator over a
+    /// real (or virtual) serial line.
+    ///
+    /// The response is empty when the controller would stay silent. A
+    /// [`Fault::Delay`] is served by sleeping on the clock before returning.
+    pub fn respond(&mut self, request: &[u8]) -> &[u8] {
+        self.handle(request);
+        self.clock
+            .sleep(self.ready.saturating_sub(self.clock.now()));
+        self.delayed = false;
+        &self.response
+    }
+
+    /// Answer `request`, leaving the response to be read.
+    fn handle(&mut self, request: &[u8]) {
+        if self.clock.now() < self.ready {
+            return;
+        }
+        let mut response = heapless::Vec::new();
+        if self.delayed {
+            let _ = response.extend_from_slice(&self.response[self.pos..]);
+            self.delayed = false;
+        }
+        self.on_time = response.len();
+        let _ = response.extend_from_slice(&self.reply(request));
+        self.response = response;
+        self.pos = 0;
+    }
+
+    /// The response to `request`, spoilt by any fault; empty if there is
+    /// none.
+    fn reply(&mut self, request: &[u8]) -> heapless::Vec<u8, 256> {
+        let mut out = heapless::Vec::<u8, 256>::new();
+        if request.len() < 4 {
+            return out;
+        }
+        let (body, crc) = request.split_at(request.len() - 2);
+        if crc16(body).to_le_bytes() != crc || body[0] != self.holding(regs::ID) as u8 {
+            return out;
+        }
+        let (unit_id, func, data) = (body[0], body[1], &body[2..]);
+
+        self.advance();
+        let fault = self.next_fault.take().or(self.fault);
+        let _ = out.extend_from_slice(&[unit_id, func]);
+        let answered = match fault {
+            Some(Fault::Exception(code)) => Err(code),
+            _ => self.answer(func, data, &mut out),
+        };
+        if let Err(code) = answered {
+            out.truncate(1);
+            let _ = out.extend_from_slice(&[func | 0x80, code]);
+        }
+        if let Some(Fault::WrongUnit(id)) = fault {
+            out[0] = id;
+        }
+        let crc = crc16(&out).to_le_bytes();
+        let _ = out.extend_from_slice(&crc);
+
+        match fault {
+            Some(Fault::CorruptCrc) => {
+                let last = out.len() - 1;
+                out[last] = !out[last];
+            }
+            Some(Fault::Truncate(len)) => out.truncate(len),
+            Some(Fault::Delay(delay)) => {
+                self.ready = self.clock.now() + delay;
+                self.delayed = true;
+            }
+            _ => {}
+        }
+        out
+    }
+
+    fn answer(
+        &mut self,
+        func: u8,
+        data: &[u8],
+        out: &mut heapless::Vec<u8, 256>,
+    ) -> Result<(), u8> {
+        let word = |i: usize| {
+            data.get(i..i + 2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]))
+                .ok_or(ILLEGAL_DATA_VALUE)
+        };
+
+        match func {
+            READ_COILS => {
+                let (addr, count) = (word(0)?, word(2)?);
+                let end = addr.checked_add(count).ok_or(ILLEGAL_DATA_ADDRESS)?;
+                if !(1..=8).contains(&count) || end > 8 {
+                    return Err(ILLEGAL_DATA_ADDRESS);
+                }
+                let mask = ((1u16 << count) - 1) as u8;
+                let _ = out.extend_from_slice(&[1, (self.coils() >> addr) & mask]);
+            }
+            READ_HOLDINGS => {
+                let (addr, count) = (word(0)?, word(2)?);
+                if !(1..=125).contains(&count) {
+                    return Err(ILLEGAL_DATA_VALUE);
+                }
+                let end = addr.checked_add(count).ok_or(ILLEGAL_DATA_ADDRESS)?;
+                let _ = out.push(count as u8 * 2);
+                for addr in addr..end {
+                    let word = self.word(addr).ok_or(ILLEGAL_DATA_ADDRESS)?;
+                    let _ = out.extend_from_slice(&word.to_be_bytes());
+                }
+            }
+            WRITE_COIL => {
+                let (addr, val) = (word(0)?, word(2)?);
+                let on = match val {
+                    0xFF00 => true,
+                    0x0000 => false,
+                    _ => return Err(ILLEGAL_DATA_VALUE),
+                };
+                if addr != regs::AT.addr && addr != regs::AM.addr {
+                    return Err(ILLEGAL_DATA_ADDRESS);
+                }
+                self.set_coil(Reg { addr, ..regs::AT }, on);
+                let _ = out.extend_from_slice(&data[..4]);
+            }
+            WRITE_HOLDING => {
+                let (addr, val) = (word(0)?, word(2)?);
+                self.write_words(addr, &[val])?;
+                let _ = out.extend_from_slice(&data[..4]);
+            }
+            WRITE_HOLDINGS => {
+                let (addr, count) = (word(0)?, word(2)?);
+                let bytes = data.get(5..).unwrap_or_default();
+                if data.get(4).map(|&n| n as usize) != Some(bytes.len())
+                    || bytes.len() != count as usize * 2
+                    || count > 123
+                {
+                    return Err(ILLEGAL_DATA_VALUE);
+                }
+                let mut words = heapless::Vec::<u16, 123>::new();
+                for b in bytes.chunks_exact(2) {
+                    let _ = words.push(u16::from_be_bytes([b[0], b[1]]));
+                }
+                self.write_words(addr, &words)?;
+                let _ = out.extend_from_slice(&data[..4]);
+            }
+            _ => return Err(ILLEGAL_FUNCTION),
+        }
+        Ok(())
+    }
+
+    /// The register word at `addr`, if a holding parameter covers it.
+    fn word(&self, addr: u16) -> Option<u16> {
+        let i = index(addr)?;
+        Some(f32_to_values(self.values[i])[(addr - HOLDING[i].addr) as usize])
+    }
+
+    /// Write consecutive words, checking the whole write before changing
+    /// anything.
+    fn write_words(&mut self, addr: u16, words: &[u16]) -> Result<(), u8> {
+        let end = addr
+            .checked_add(words.len() as u16)
+            .ok_or(ILLEGAL_DATA_ADDRESS)?;
+        let mut values = self.values;
+        for (addr, &word) in (addr..end).zip(words) {
+            let i = index(addr).ok_or(ILLEGAL_DATA_ADDRESS)?;
+            let reg = HOLDING[i];
+            let held = self.coil(regs::AM) || values[index(regs::CV.addr).unwrap()] == 1.0;
+            if reg.addr == regs::PV.addr || (reg.addr == regs::OUT.addr && !held) {
+                return Err(ILLEGAL_DATA_ADDRESS);
+            }
+            let mut halves = f32_to_values(values[i]);
+            halves[(addr - reg.addr) as usize] = word;
+            values[i] = values_to_f32(halves[0], halves[1]);
+        }
+        for (i, &val) in values.iter().enumerate() {
+            if val != self.values[i] && !in_range(HOLDING[i], val) {
+                return Err(ILLEGAL_DATA_VALUE);
+            }
+        }
+        self.values = values;
+        Ok(())
+    }
+}
+
+/// Index into [`HOLDING`] of the parameter covering `addr`.
+fn index(addr: u16) -> Option<usize> {
+    HOLDING
+        .iter()
+        .position(|reg| (reg.addr..reg.addr + reg.width()).contains(&addr))
+}
+
+/// Whether the controller takes `val` for `reg`.
+fn in_range(reg: Reg, val: f32) -> bool {
+    META.iter()
+        .find(|meta| meta.reg.addr == reg.addr && meta.reg.bank == reg.bank)
+        .is_some_and(|meta| {
+            let whole = meta.kind == ValueKind::Float || val == val as i32 as f32;
+            whole && (meta.min..=meta.max).contains(&val)
+        })
+}
+
+/// Reading past the response, or a request that got none, times out.
+impl<C> Transport for Simulator<C>
+where
+    C: Clock,
+{
+    type Error = TimeoutError<core::convert::Infallible>;
+
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        self.handle(frame);
+        Ok(())
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Self::Error> {
+        let end = if self.clock.now() < self.ready {
+            self.on_time
+        } else {
+            self.response.len()
+        };
+        let Some(bytes) = self.response[..end].get(self.pos..self.pos + buf.len()) else {
+            self.pos = end;
+            return Err(TimeoutError::Timeout);
+        };
+        buf.copy_from_slice(bytes);
+        self.pos += buf.len();
+        Ok(())
+    }
+
+    fn is_timeout(err: &Self::Error) -> bool {
+        matches!(err, TimeoutError::Timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::VirtualClock;
+    use crate::param::Unit;
+    use crate::{Error, Param, ParamValue, Syl2381, TempRaw};
+
+    #[test]
+    fn every_accessor_round_trips() {
+        let clock = VirtualClock::new();
+        let mut pid = Syl2381::new(1, Simulator::new(1, &clock));
+
+        for param in Param::ALL {
+            assert!(pid.get_param(param).is_ok(), "{}", param);
+        }
+        for param in Param::ALL {
+            if !param.writable() {
+                continue;
+            }
+            let meta = param.meta();
+            let stored = match meta.kind {
+                ValueKind::Bool => 1.0,
+                _ if meta.unit == Unit::Temperature => 42.0,
+                _ => meta.max,
+            };
+            let val = ParamValue::from_stored(param, stored).unwrap();
+            assert!(pid.set_param(param, val).is_ok(), "{}", param);
+            let read = pid.get_param(param).unwrap();
+            assert_eq!(f32::from(read), stored, "{}", param);
+        }
+    }
+
+    #[test]
+    fn heats_toward_the_setpoint() {
+        let clock = VirtualClock::new();
+        let mut pid = Syl2381::new(1, Simulator::new(1, &clock));
+
+        assert!(matches!(pid.get_pv(), Ok(TempRaw(v)) if v == 20.0));
+        clock.advance(Duration::from_secs(30 * 60));
+        let vals = pid.poll().unwrap();
+        assert!((85.0..100.0).contains(&vals.pv.0), "{}", vals.pv);
+        assert!(vals.out.0 > 0.0);
+        assert!(!vals.status.alarm1());
+
+        assert!(pid.set_sv(TempRaw(200.0)).is_ok());
+        clock.advance(Duration::from_secs(30 * 60));
+        assert!(pid.poll().unwrap().status.alarm1());
+    }
+
+    #[test]
+    fn autotunes_to_suit_the_plant() {
+        let clock = VirtualClock::new();
+        let plant = Plant {
+            tau: Duration::from_secs(60),
+            ..Plant::default()
+        };
+        let mut pid = Syl2381::new(1, Simulator::new(1, &clock).with_plant(plant));
+
+        assert!(pid.start_autotune().is_ok());
+        let gains = pid
+            .wait_autotune_complete(&clock, Duration::from_secs(5), Duration::from_secs(3600))
+            .unwrap();
+        assert_eq!(gains.p, 10.0);
+        assert_eq!(gains.i, crate::Seconds(120));
+        assert_eq!(gains.d, crate::Seconds(30));
+        assert!(clock.now() >= Duration::from_secs(240));
+        assert!(!pid.get_autotune().unwrap());
+    }
+
+    #[test]
+    fn rejects_like_the_controller() {
+        let clock = VirtualClock::new();
+        let mut pid = Syl2381::new(1, Simulator::new(1, &clock));
+
+        assert!(matches!(
+            pid.write_holding_raw(regs::PV.addr, &[0, 0]),
+            Err(Error::IllegalDataAddress(_))
+        ));
+        assert!(matches!(
+            pid.read_holding_raw(0x3000, 2),
+            Err(Error::IllegalDataAddress(_))
+        ));
+        assert!(matches!(
+            pid.write_holding_raw(regs::BAUD.addr, &f32_to_values(7.0)),
+            Err(Error::IllegalDataValue(_))
+        ));
+        assert!(matches!(
+            pid.read_holding_raw(0xFFFF, 2),
+            Err(Error::IllegalDataAddress(_))
+        ));
+        assert!(matches!(
+            pid.write_holding_raw(0xFFFF, &[0, 0]),
+            Err(Error::IllegalDataAddress(_))
+        ));
+        assert!(matches!(
+            pid.read_coils_raw(0xFFFF, 2),
+            Err(Error::IllegalDataAddress(_))
+        ));
+
+        let mut other = Syl2381::new(2, Simulator::new(1, &clock));
+        assert!(matches!(other.get_pv(), Err(Error::Timeout(_))));
+    }
+
+    #[test]
+    fn spoils_responses_on_request() {
+        let clock = VirtualClock::new();
+        let mut pid = Syl2381::new(1, Simulator::new(1, &clock));
+
+        pid.port.inject(Fault::CorruptCrc);
+        assert!(matches!(pid.get_sv(), Err(Error::ModbusError(..))));
+        pid.port.inject(Fault::Truncate(5));
+        assert!(matches!(pid.get_sv(), Err(Error::Timeout(_))));
+        pid.port.inject(Fault::Exception(0x06));
+        assert!(matches!(
+            pid.set_sv(TempRaw(50.0)),
+            Err(Error::DeviceBusy(_))
+        ));
+        assert_eq!(pid.port.holding(regs::SV), 100.0);
+        pid.port.inject(Fault::WrongUnit(2));
+        assert!(pid.get_sv().is_err());
+        assert!(matches!(pid.get_sv(), Ok(TempRaw(v)) if v == 100.0));
+
+        pid.port.set_fault(Some(Fault::Exception(0x04)));
+        assert!(matches!(pid.get_pv(), Err(Error::DeviceFailure(_))));
+        assert!(matches!(pid.get_pv(), Err(Error::DeviceFailure(_))));
+        pid.port.set_fault(None);
+        assert!(pid.get_pv().is_ok());
+    }
+
+    #[test]
+    fn answers_late() {
+        let clock = VirtualClock::new();
+        let mut pid = Syl2381::new(1, Simulator::new(1, &clock));
+
+        pid.port.inject(Fault::Delay(Duration::from_secs(2)));
+        assert!(matches!(pid.get_pv(), Err(Error::Timeout(_))));
+        clock.advance(Duration::from_secs(1));
+        assert!(matches!(pid.get_pv(), Err(Error::Timeout(_))));
+
+        // The late reading is taken as the answer to the next request.
+        clock.advance(Duration::from_secs(1));
+        assert!(matches!(pid.get_pv(), Ok(TempRaw(v)) if v == 20.0));
+        assert!(matches!(pid.get_pv(), Ok(TempRaw(v)) if v > 20.0));
+    }
+}
@@ -0,0 +1,181 @@
+//! Every parameter the controller documents, read in one call.
+
+use core::fmt;
+
+use crate::clock::DriverClock;
+use crate::param::ParamValue;
+use crate::transport::Transport;
+use crate::{
+    BaudRate, ControlDirection, DisplayUnit, Filter, InputType, OutputMode, OutputType, Percent,
+    Seconds, Status, Syl2381, TempRaw,
+};
+
+#[derive(Clone, Copy, fmt::Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceSnapshot {
+    pub pv: TempRaw,
+    pub out: Percent,
+    pub j1_status: bool,
+    pub cv: bool,
+    pub status: Status,
+    pub sv: TempRaw,
+    pub j1_on_temp: TempRaw,
+    pub j1_off_temp: TempRaw,
+    pub p: f32,
+    pub i: Seconds,
+    pub d: Seconds,
+    pub bb: TempRaw,
+    pub souf: Percent,
+    pub control_cycle: Seconds,
+    pub filter: Filter,
+    pub input_sensor_type: InputType,
+    pub output_mode: OutputMode,
+    pub output_type: OutputType,
+    pub hysteresis: TempRaw,
+    pub input_offset: TempRaw,
+    pub control_direction: ControlDirection,
+    pub display_unit: DisplayUnit,
+    pub unit_id: u8,
+    pub baud_rate: BaudRate,
+}
+
+/// One `name = value` line per parameter, numbers to the front panel's
+/// resolution as [`ParamValue`] shows them.
+impl fmt::Display for DeviceSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        macro_rules! lines {
+            ($($field:ident $(as $variant:ident)?),*) => {
+                $(lines!(@line $field $(as $variant)?);)*
+            };
+            (@line $field:ident) => {
+                writeln!(f, "{: >19} = {}", stringify!($field), self.$field)?
+            };
+            (@line $field:ident as $variant:ident) => {
+                writeln!(
+                    f,
+                    "{: >19} = {}",
+                    stringify!($field),
+                    ParamValue::$variant(self.$field)
+                )?
+            };
+        }
+
+        lines!(
+            pv as Temp,
+            out as Percent,
+            j1_status,
+            cv,
+            status,
+            sv as Temp,
+            j1_on_temp as Temp,
+            j1_off_temp as Temp,
+            p as Number,
+            i,
+            d,
+            bb as Temp,
+            souf as Percent,
+            control_cycle,
+            filter,
+            input_sensor_type,
+            output_mode,
+            output_type,
+            hysteresis as Temp,
+            input_offset as Temp,
+            control_direction,
+            display_unit,
+            unit_id,
+            baud_rate
+        );
+        Ok(())
+    }
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    /// Read every parameter, stopping at the first failure.
+    pub fn read_snapshot(&mut self) -> crate::Result<DeviceSnapshot, UART> {
+        Ok(DeviceSnapshot {
+            pv: self.get_pv()?,
+            out: self.get_out()?,
+            j1_status: self.get_j1_status()?,
+            cv: self.get_cv()?,
+            status: self.get_status()?,
+            sv: self.get_sv()?,
+            j1_on_temp: self.get_j1_on_temp()?,
+            j1_off_temp: self.get_j1_off_temp()?,
+            p: self.get_p()?,
+            i: self.get_i()?,
+            d: self.get_d()?,
+            bb: self.get_bb()?,
+            souf: self.get_souf()?,
+            control_cycle: self.get_control_cycle()?,
+            filter: self.get_filter()?,
+            input_sensor_type: self.get_input_sensor_type()?,
+            output_mode: self.get_output_mode()?,
+            output_type: self.get_output_type()?,
+            hysteresis: self.get_hysteresis()?,
+            input_offset: self.get_input_offset()?,
+            control_direction: self.get_control_direction()?,
+            display_unit: self.get_display_unit()?,
+            unit_id: self.get_unit_id()?,
+            baud_rate: self.get_baud_rate()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockSerial;
+    use crate::{regs, Error};
+
+    #[test]
+    fn reads_every_parameter() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 25.0); // PV
+        port.respond_f32(1, 0.5); // OUT
+        port.respond(&[1, 0x01, 1, 0x01]); // AL1_STA
+        port.respond_f32(1, 0.0); // CV
+        port.respond(&[1, 0x01, 1, 0x20]); // AT
+        for val in [65.0, 70.0, 68.0, 10.0, 120.0, 30.0, 5.0, 0.8, 2.0, 1.0] {
+            port.respond_f32(1, val); // SV ..= FILT
+        }
+        for val in [0.0, 2.0, 0.0, 0.5, -1.0, 0.0, 0.0, 1.0, 3.0] {
+            port.respond_f32(1, val); // INTY ..= bAud
+        }
+        let mut pid = Syl2381::new(1, port);
+
+        let snap = pid.read_snapshot().unwrap();
+        assert!(pid.port.rx.is_empty());
+        assert!(snap.j1_status && !snap.cv && snap.status.alarm1());
+        assert_eq!(
+            (snap.sv, snap.p, snap.i),
+            (TempRaw(65.0), 10.0, Seconds(120))
+        );
+        assert!(matches!(snap.input_sensor_type, InputType::T));
+        assert!(matches!(snap.baud_rate, BaudRate::Baud9600));
+        assert_eq!(snap.unit_id, 1);
+
+        let text = snap.to_string();
+        assert_eq!(text.lines().count(), 24);
+        assert!(text.starts_with("                 pv = 25.0°\n"));
+        assert!(text.contains("                  p = 10.0\n"));
+        assert!(text.contains("                  i = 120 s\n"));
+        assert!(text.contains("               souf = 80%\n"));
+    }
+
+    #[test]
+    fn stops_at_first_failure() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 25.0);
+        let mut pid = Syl2381::new(1, port);
+
+        let err = pid.read_snapshot().unwrap_err();
+        assert_eq!(err.context().map(|ctx| ctx.addr), Some(regs::OUT.addr));
+        assert!(matches!(err, Error::SerialError(..)));
+    }
+}
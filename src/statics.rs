@@ -0,0 +1,162 @@
+//! Caching of system parameters.
+//!
+//! INTY, OUTY, CorF and the rest of the system bank are set once at
+//! commissioning, yet generic code (parameter dumps, unit conversion, range
+//! checks) reads them over and over, at some 20 ms a register. With a
+//! [`StaticCache`] the driver keeps what it reads from that bank and serves it
+//! from memory until it's written, [`Syl2381::invalidate_static_cache`] is
+//! called, or it's older than `max_age`. Id and bAud are always read from the
+//! controller.
+
+use core::fmt;
+use core::time::Duration;
+
+use crate::clock::{Clock, DriverClock};
+use crate::regs::{self, Bank};
+use crate::transport::Transport;
+use crate::Syl2381;
+
+/// How long values read from the system bank are kept.
+///
+/// Ages are measured on the driver's clock; see
+/// [`Syl2381::with_clock`].
+#[derive(Clone, Copy, fmt::Debug)]
+pub struct StaticCache {
+    /// Age past which a value is read again; `None` keeps it until
+    /// invalidated. Set this if someone may change settings from the front
+    /// panel.
+    pub max_age: Option<Duration>,
+}
+
+/// Cached system parameters, with when they were read.
+#[derive(Default)]
+pub(crate) struct Statics {
+    cache: Option<StaticCache>,
+    regs: heapless::LinearMap<u16, (Duration, f32), 8>,
+}
+
+impl Statics {
+    /// A cached value of `reg` that's still fresh at `now`.
+    pub(crate) fn get(&self, reg: regs::Reg, now: Option<Duration>) -> Option<f32> {
+        let (cache, now) = (self.cache?, now?);
+        let &(at, val) = self.regs.get(&reg.addr)?;
+        match cache.max_age {
+            Some(max_age) if now.saturating_sub(at) > max_age => None,
+            _ => Some(val),
+        }
+    }
+
+    /// Keep a value read from `reg` at `now`.
+    pub(crate) fn read(&mut self, reg: regs::Reg, val: f32, now: Option<Duration>) {
+        let (Some(_), Some(now)) = (self.cache, now) else {
+            return;
+        };
+        if cacheable(reg) {
+            // There are only seven cacheable registers, so this can't overflow.
+            let _ = self.regs.insert(reg.addr, (now, val));
+        }
+    }
+
+    /// Drop any cached copy of `reg`, e.g. before writing it.
+    pub(crate) fn invalidate(&mut self, reg: regs::Reg) {
+        self.regs.remove(&reg.addr);
+    }
+
+    /// Drop everything cached.
+    pub(crate) fn clear(&mut self) {
+        self.regs.clear();
+    }
+}
+
+fn cacheable(reg: regs::Reg) -> bool {
+    reg.bank == Bank::System && reg.addr != regs::ID.addr && reg.addr != regs::BAUD.addr
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    pub fn static_cache(&self) -> Option<StaticCache> {
+        self.statics.cache
+    }
+
+    /// Drop every cached system parameter, so the next read of each goes to
+    /// the controller.
+    pub fn invalidate_static_cache(&mut self) {
+        self.statics.clear();
+    }
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: Clock,
+{
+    /// Cache system parameters as set out by `cache`, or stop caching them
+    /// with `None`. Either way, anything already cached is dropped.
+    pub fn set_static_cache(&mut self, cache: Option<StaticCache>) {
+        self.statics = Statics {
+            cache,
+            ..Statics::default()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::VirtualClock;
+    use crate::mock::MockSerial;
+    use crate::{InputType, OutputMode, TempRaw};
+
+    #[test]
+    fn serves_system_parameters_from_memory() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 6.0); // INTY: K
+        port.respond_f32(1, 60.0); // SV
+        port.respond_f32(1, 61.0); // SV
+        port.respond_f32(1, 5.0); // Id
+        port.respond_f32(1, 5.0); // Id
+        port.respond_f32(1, 6.0); // INTY, once stale
+        let clock = VirtualClock::new();
+        let mut pid = Syl2381::new(1, port).with_clock(&clock);
+        pid.set_static_cache(Some(StaticCache {
+            max_age: Some(Duration::from_secs(60)),
+        }));
+
+        assert!(matches!(pid.get_input_sensor_type(), Ok(InputType::K)));
+        assert!(matches!(pid.get_input_sensor_type(), Ok(InputType::K)));
+        assert!(matches!(pid.get_sv(), Ok(TempRaw(v)) if v == 60.0));
+        assert!(matches!(pid.get_sv(), Ok(TempRaw(v)) if v == 61.0));
+        assert!(matches!(pid.get_unit_id(), Ok(5)));
+        assert!(matches!(pid.get_unit_id(), Ok(5)));
+
+        clock.advance(Duration::from_secs(61));
+        assert!(matches!(pid.get_input_sensor_type(), Ok(InputType::K)));
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn writes_and_invalidation_drop_values() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 0.0); // OUTY: absolute alarm
+        port.ack_f32(1, regs::OUTY.addr);
+        port.respond_f32(1, 1.0); // OUTY: derivation alarm
+        port.respond_f32(1, 0.0); // OUTY: absolute alarm
+        let mut pid = Syl2381::new(1, port).with_clock(VirtualClock::new());
+        pid.set_static_cache(Some(StaticCache { max_age: None }));
+
+        use OutputMode::J1RelayAsAbsoluteAlarmOutputSsrPortAsPidControlOutput as Absolute;
+        use OutputMode::J1RelayAsDerivationAlarmOutputSsrPortAsPidControlOutput as Derivation;
+
+        assert!(matches!(pid.get_output_mode(), Ok(Absolute)));
+        assert!(pid.set_output_mode(Derivation).is_ok());
+        assert!(matches!(pid.get_output_mode(), Ok(Derivation)));
+        assert!(matches!(pid.get_output_mode(), Ok(Derivation)));
+
+        pid.invalidate_static_cache();
+        assert!(matches!(pid.get_output_mode(), Ok(Absolute)));
+        assert!(pid.port.rx.is_empty());
+    }
+}
@@ -0,0 +1,171 @@
+//! Counting how the line is behaving.
+//!
+//! When a controller is "flaky", the first question is how: are requests
+//! going unanswered, are responses arriving garbled, or is the controller
+//! answering with exceptions? The driver counts each outcome as it goes.
+//! [`Syl2381::stats`] reads the counts, and [`Syl2381::reset_stats`] starts
+//! them again from zero.
+//!
+//! The driver doesn't retry on its own, so a retry is a request sent again,
+//! byte for byte, straight after it failed, by whatever sits on top.
+
+use core::fmt;
+
+use rmodbus::ErrorKind;
+
+use crate::clock::DriverClock;
+use crate::frame::Frame;
+use crate::transport::Transport;
+use crate::{Error, Syl2381};
+
+/// Counts since the driver was created or the counts were last reset.
+#[derive(Clone, Copy, Default, fmt::Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stats {
+    /// Request frames sent.
+    pub requests: u32,
+
+    /// Intact responses received, exceptions included.
+    pub responses: u32,
+
+    /// Responses that failed their CRC check.
+    pub crc_errors: u32,
+
+    /// Requests that got no complete response in time.
+    pub timeouts: u32,
+
+    /// Requests sent again straight after they failed.
+    pub retries: u32,
+
+    /// Exception responses.
+    pub exceptions: u32,
+
+    /// Responses that made no sense: endless noise, or a header or length
+    /// that doesn't fit the request.
+    pub bad_frames: u32,
+}
+
+#[derive(Default)]
+pub(crate) struct Tally {
+    stats: Stats,
+    /// CRC of the request in flight, standing in for the whole frame.
+    current: u16,
+    /// CRC of the last request, if it failed.
+    failed: Option<u16>,
+}
+
+impl Tally {
+    /// Count `request` going out.
+    pub(crate) fn sent(&mut self, request: &[u8]) {
+        let crc = match request {
+            [.., lo, hi] => u16::from_le_bytes([*lo, *hi]),
+            _ => 0,
+        };
+        self.stats.requests += 1;
+        if self.failed == Some(crc) {
+            self.stats.retries += 1;
+        }
+        self.current = crc;
+    }
+
+    /// Count how the request in flight went, given what receiving the
+    /// response into `frame` returned.
+    pub(crate) fn received<E>(&mut self, received: &Result<(), Error<E>>, frame: &Frame) {
+        let stats = &mut self.stats;
+        let ok = match received {
+            Ok(()) => match frame.parse_ok() {
+                Ok(()) => {
+                    stats.responses += 1;
+                    true
+                }
+                Err(ErrorKind::FrameCRCError) => {
+                    stats.crc_errors += 1;
+                    false
+                }
+                Err(_) if frame.is_exception() => {
+                    stats.responses += 1;
+                    stats.exceptions += 1;
+                    false
+                }
+                Err(_) => {
+                    stats.bad_frames += 1;
+                    false
+                }
+            },
+            Err(Error::Timeout(_)) => {
+                stats.timeouts += 1;
+                false
+            }
+            Err(Error::ModbusError(..)) => {
+                stats.bad_frames += 1;
+                false
+            }
+            Err(_) => false,
+        };
+        self.failed = if ok { None } else { Some(self.current) };
+    }
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    pub fn stats(&self) -> Stats {
+        self.tally.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.tally = Tally::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockSerial;
+    use crate::TempRaw;
+
+    #[test]
+    fn counts_each_outcome() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 65.0);
+        port.respond(&[1, 0x83, 0x02]);
+        port.rx.extend([1, 0x03, 4, 0x42, 0x82, 0, 0, 0, 0]);
+        port.rx.extend([0xFF; 32]);
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(matches!(pid.get_sv(), Ok(TempRaw(v)) if v == 65.0));
+        assert!(matches!(pid.get_sv(), Err(Error::IllegalDataAddress(_))));
+        assert!(pid.get_sv().is_err());
+        assert!(matches!(pid.get_sv(), Err(Error::ModbusError(..))));
+        assert!(matches!(pid.get_pv(), Err(Error::SerialError(..))));
+        assert_eq!(
+            pid.stats(),
+            Stats {
+                requests: 5,
+                responses: 2,
+                crc_errors: 1,
+                timeouts: 0,
+                retries: 2,
+                exceptions: 1,
+                bad_frames: 1,
+            }
+        );
+
+        pid.reset_stats();
+        assert_eq!(pid.stats(), Stats::default());
+    }
+
+    #[test]
+    fn counts_timeouts() {
+        let clock = crate::clock::VirtualClock::new();
+        let mut pid = Syl2381::new(2, crate::simulator::Simulator::new(1, &clock));
+
+        assert!(pid.get_pv().is_err());
+        assert!(pid.get_pv().is_err());
+        let stats = pid.stats();
+        assert_eq!((stats.requests, stats.timeouts, stats.retries), (2, 2, 1));
+    }
+}
@@ -0,0 +1,107 @@
+//! Keyed blob storage for state that outlives the process.
+//!
+//! Subsystems that keep state across restarts, such as the audit log
+//! (`audit::Log`) and configuration profiles (`profile::load_from`), read and
+//! write it through [`Storage`] rather than a filesystem, so the same code
+//! persists to files on a host ([`FileStorage`], with `std`) and to a flash
+//! page on an MCU. Keys are short `/`-separated paths like `audit/last`;
+//! what's stored under them is opaque to the storage.
+//!
+//! `examples/flash_log.rs` implements `Storage` over a simulated flash
+//! sector with nothing but `core`.
+
+#[cfg(feature = "std")]
+use std::{fs, io, path::PathBuf};
+
+/// Somewhere to keep blobs by key.
+pub trait Storage {
+    type Error;
+
+    /// Copy the blob stored under `key` into the start of `buf`, returning its
+    /// full length, or `None` if there's nothing stored there. A blob longer
+    /// than `buf` is cut short, so compare the length against `buf.len()`.
+    fn read(&mut self, key: &str, buf: &mut [u8]) -> Result<Option<usize>, Self::Error>;
+
+    /// Store `blob` under `key`, replacing what was there.
+    fn write(&mut self, key: &str, blob: &[u8]) -> Result<(), Self::Error>;
+}
+
+impl<S: Storage + ?Sized> Storage for &mut S {
+    type Error = S::Error;
+
+    fn read(&mut self, key: &str, buf: &mut [u8]) -> Result<Option<usize>, Self::Error> {
+        S::read(self, key, buf)
+    }
+
+    fn write(&mut self, key: &str, blob: &[u8]) -> Result<(), Self::Error> {
+        S::write(self, key, blob)
+    }
+}
+
+/// One file per key, under a root directory.
+///
+/// Writes go to a temporary file that is then renamed over the old one, so a
+/// crash mid-write leaves the previous blob in place.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct FileStorage {
+    root: PathBuf,
+}
+
+#[cfg(feature = "std")]
+impl FileStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        FileStorage { root: root.into() }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Storage for FileStorage {
+    type Error = io::Error;
+
+    fn read(&mut self, key: &str, buf: &mut [u8]) -> io::Result<Option<usize>> {
+        let blob = match fs::read(self.root.join(key)) {
+            Ok(blob) => blob,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let n = blob.len().min(buf.len());
+        buf[..n].copy_from_slice(&blob[..n]);
+        Ok(Some(blob.len()))
+    }
+
+    fn write(&mut self, key: &str, blob: &[u8]) -> io::Result<()> {
+        let path = self.root.join(key);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, blob)?;
+        fs::rename(&tmp, &path)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_round_trip() {
+        let root =
+            std::env::temp_dir().join(std::format!("syl2381-storage-{}", std::process::id()));
+        let mut storage = FileStorage::new(&root);
+        let mut buf = [0; 4];
+
+        assert_eq!(storage.read("audit/last", &mut buf).unwrap(), None);
+        storage.write("audit/last", b"first").unwrap();
+        storage.write("audit/last", b"abc").unwrap();
+        assert_eq!(storage.read("audit/last", &mut buf).unwrap(), Some(3));
+        assert_eq!(&buf[..3], b"abc");
+
+        storage.write("audit/last", b"too long").unwrap();
+        assert_eq!(storage.read("audit/last", &mut buf).unwrap(), Some(8));
+        assert_eq!(&buf, b"too ");
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,122 @@
+//! Compact encoding for profiles kept in MCU flash.
+//!
+//! Field devices often have no filesystem, only a flash page or two. This
+//! encodes a [`Config`](crate::Config) (or any other serde type of the
+//! driver's) with postcard into a caller-provided buffer, behind a version
+//! byte. The layout is postcard's stable wire format over the types' fields
+//! in declaration order, with enums stored as variant indices, so the tests
+//! pin the exact bytes: reordering fields or variants must bump [`VERSION`].
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub use postcard::Error;
+
+/// Written ahead of every encoding and checked on decode.
+pub const VERSION: u8 = 1;
+
+/// Longest encoded [`Config`](crate::Config), version byte included.
+pub const CONFIG_LEN: usize = 65;
+
+/// Encode `value` into the start of `buf`, returning the bytes used.
+pub fn to_slice<'a, T: Serialize>(value: &T, buf: &'a mut [u8]) -> Result<&'a mut [u8], Error> {
+    let (version, rest) = buf.split_first_mut().ok_or(Error::SerializeBufferFull)?;
+    *version = VERSION;
+    let len = postcard::to_slice(value, rest)?.len();
+    Ok(&mut buf[..1 + len])
+}
+
+/// Decode a value written by [`to_slice`]; trailing bytes (erased flash,
+/// say) are ignored.
+pub fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Error> {
+    match bytes.split_first() {
+        Some((&VERSION, rest)) => Ok(postcard::take_from_bytes(rest)?.0),
+        Some(_) => Err(Error::DeserializeBadEncoding),
+        None => Err(Error::DeserializeUnexpectedEnd),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Config, ControlDirection, DisplayUnit, Filter, InputType, Labels, OutputMode, OutputType,
+        Percent, Seconds, TempRaw,
+    };
+
+    #[test]
+    fn encodes_a_stable_layout() {
+        let cfg = Config {
+            input_sensor_type: Some(InputType::K),
+            i: Some(Seconds(300)),
+            sv: Some(TempRaw(65.0)),
+            ..Config::default()
+        };
+        let mut buf = [0xFF; CONFIG_LEN];
+        let bytes = to_slice(&cfg, &mut buf).unwrap();
+        assert_eq!(
+            bytes,
+            [
+                VERSION, //
+                1, 6, // INTY
+                0, 0, 0, 0, 0, 0, 0, // CorF ..= P
+                1, 0xAC, 0x02, // I
+                0, 0, 0, 0, 0, // D ..= FILT
+                1, 0x00, 0x00, 0x82, 0x42, // SV
+                0, 0, // AH1, AL1
+            ]
+        );
+
+        let back: Config = from_bytes(&buf).unwrap();
+        assert!(matches!(back.input_sensor_type, Some(InputType::K)));
+        assert!(matches!(back.i, Some(Seconds(300))));
+        assert!(matches!(back.sv, Some(TempRaw(v)) if v == 65.0));
+        assert!(back.p.is_none() && back.j1_off_temp.is_none());
+    }
+
+    #[test]
+    fn full_config_fits_config_len() {
+        let cfg = Config {
+            input_sensor_type: Some(InputType::CU50),
+            display_unit: Some(DisplayUnit::Fahrenheit),
+            output_mode: Some(OutputMode::J1RelayAsAbsoluteAlarmOutputSsrPortAsPidControlOutput),
+            output_type: Some(OutputType::SSR),
+            control_direction: Some(ControlDirection::Cooling),
+            hysteresis: Some(TempRaw(1.0)),
+            input_offset: Some(TempRaw(-0.5)),
+            p: Some(10.0),
+            i: Some(Seconds(u16::MAX)),
+            d: Some(Seconds(u16::MAX)),
+            bb: Some(TempRaw(30.0)),
+            souf: Some(Percent(0.2)),
+            control_cycle: Some(Seconds(u16::MAX)),
+            filter: Some(Filter::Strong),
+            sv: Some(TempRaw(65.0)),
+            j1_on_temp: Some(TempRaw(100.0)),
+            j1_off_temp: Some(TempRaw(95.0)),
+        };
+        let mut buf = [0; CONFIG_LEN];
+        assert_eq!(to_slice(&cfg, &mut buf).unwrap().len(), CONFIG_LEN);
+        assert!(to_slice(&cfg, &mut [0; CONFIG_LEN - 1]).is_err());
+    }
+
+    #[test]
+    fn round_trips_labels() {
+        let labels = Labels::new("Mash tun").with_probe("PT100");
+        let mut buf = [0; 128];
+        let bytes = to_slice(&labels, &mut buf).unwrap();
+        assert_eq!(from_bytes::<Labels>(bytes).unwrap(), labels);
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        assert_eq!(
+            from_bytes::<Config>(&[VERSION + 1, 0]).unwrap_err(),
+            Error::DeserializeBadEncoding
+        );
+        assert_eq!(
+            from_bytes::<Config>(&[]).unwrap_err(),
+            Error::DeserializeUnexpectedEnd
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! How holding parameters are written.
+//!
+//! Each parameter is an f32 spanning two registers, normally written in one
+//! request with function 0x10 (write multiple registers). Some RS-485
+//! gateways mishandle 0x10; [`WriteStrategy::Single`] writes the two halves
+//! with function 0x06 (write single register) instead.
+//!
+//! Two writes aren't atomic. Between them, and for good if the second one
+//! fails, the controller holds the new high word with the old low word. The
+//! high word goes first since it carries the sign, exponent and top of the
+//! mantissa, so the mixed value lands close to the new one, but it is still a
+//! value nobody asked for.
+
+use core::fmt;
+
+use crate::clock::DriverClock;
+use crate::transport::Transport;
+use crate::{f32_to_values, regs, AckValue, Context, Error, Frame, Syl2381};
+
+#[derive(Clone, Copy, fmt::Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WriteStrategy {
+    /// One 0x10 request per parameter.
+    #[default]
+    Multiple,
+
+    /// Two 0x06 requests per parameter, high word first.
+    Single,
+
+    /// 0x10, switching to [`WriteStrategy::Single`] for good the first time
+    /// the controller (or a gateway) rejects it as an illegal function.
+    Fallback,
+}
+
+impl fmt::Display for WriteStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <Self as fmt::Debug>::fmt(self, f)
+    }
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    /// The current strategy; [`WriteStrategy::Fallback`] reads as `Single`
+    /// once it has fallen back.
+    pub fn write_strategy(&self) -> WriteStrategy {
+        self.strategy
+    }
+
+    pub fn set_write_strategy(&mut self, strategy: WriteStrategy) {
+        self.strategy = strategy;
+    }
+
+    /// Write the f32 at `reg` as the strategy says.
+    pub(crate) fn write_f32(
+        &mut self,
+        ctx: Context,
+        reg: regs::Reg,
+        val: f32,
+    ) -> crate::Result<(), UART> {
+        let fail = |kind| Error::modbus(ctx, kind);
+        if self.strategy == WriteStrategy::Single {
+            let [high, low] = f32_to_values(val);
+            self.write_word(ctx, reg.addr, high)?;
+            if let Err(err) = self.write_word(ctx, reg.addr + 1, low) {
+                // The high word has taken on its own.
+                self.acknowledged(reg.addr, AckValue::Word(high));
+                return Err(err);
+            }
+            self.acknowledged(reg.addr, AckValue::Holding(val));
+            return Ok(());
+        }
+
+        let mut frame = Frame::set_holding(self.unit_id, reg, val).map_err(fail)?;
+        self.transact(ctx, &mut frame)?;
+        match frame.parse_ok().map_err(fail) {
+            Err(Error::IllegalFunction(_)) if self.strategy == WriteStrategy::Fallback => {
+                self.strategy = WriteStrategy::Single;
+                return self.write_f32(ctx, reg, val);
+            }
+            res => res?,
+        }
+        self.acknowledged(reg.addr, AckValue::Holding(val));
+        Ok(())
+    }
+
+    /// Write one register with 0x06.
+    fn write_word(&mut self, ctx: Context, addr: u16, word: u16) -> crate::Result<(), UART> {
+        let fail = |kind| Error::modbus(ctx, kind);
+        let mut frame = Frame::set_word(self.unit_id, addr, word).map_err(fail)?;
+        self.transact(ctx, &mut frame)?;
+        frame.parse_ok().map_err(fail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{frame, MockSerial};
+    use crate::TempRaw;
+
+    #[test]
+    fn writes_halves_with_0x06() {
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x06, 0x00, 0x00, 0x42, 0x82]);
+        port.respond(&[1, 0x06, 0x00, 0x01, 0x00, 0x00]);
+        let mut pid = Syl2381::new(1, port);
+        pid.live
+            .assume_sensor(crate::InputType::K, crate::DisplayUnit::Celsius);
+        pid.set_write_strategy(WriteStrategy::Single);
+
+        assert!(pid.set_sv(TempRaw(65.0)).is_ok());
+        assert_eq!(pid.port.tx[..8], frame(&[1, 0x06, 0x00, 0x00, 0x42, 0x82]));
+        assert_eq!(pid.port.tx[8..], frame(&[1, 0x06, 0x00, 0x01, 0x00, 0x00]));
+    }
+
+    #[test]
+    fn falls_back_when_0x10_is_rejected() {
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x90, 0x01]);
+        port.respond(&[1, 0x06, 0x00, 0x00, 0x42, 0x82]);
+        port.respond(&[1, 0x06, 0x00, 0x01, 0x00, 0x00]);
+        let mut pid = Syl2381::new(1, port);
+        pid.live
+            .assume_sensor(crate::InputType::K, crate::DisplayUnit::Celsius);
+        pid.set_write_strategy(WriteStrategy::Fallback);
+
+        assert!(pid.set_sv(TempRaw(65.0)).is_ok());
+        assert_eq!(pid.write_strategy(), WriteStrategy::Single);
+        assert!(pid.port.rx.is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+//! Temperatures with a unit attached.
+//!
+//! [`TempRaw`] is whatever the controller displays, which depends on its CorF
+//! setting. A [`Temperature`] says which unit it's in, and is converted to the
+//! controller's unit on the way out. The driver reads CorF the first time it
+//! needs it and remembers it until CorF is written.
+//!
+//! [`Syl2381::set_temperature`] is the whole job of setting SV in one call:
+//! it converts, checks the value against the sensor's range, and writes.
+
+use core::fmt;
+
+use crate::clock::DriverClock;
+use crate::transport::Transport;
+use crate::{DisplayUnit, Syl2381, TempRaw};
+
+/// A temperature in a known unit.
+#[derive(Clone, Copy, fmt::Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Temperature {
+    value: f32,
+    unit: DisplayUnit,
+}
+
+impl Temperature {
+    pub const fn new(value: f32, unit: DisplayUnit) -> Self {
+        Temperature { value, unit }
+    }
+
+    pub const fn celsius(value: f32) -> Self {
+        Temperature::new(value, DisplayUnit::Celsius)
+    }
+
+    pub const fn fahrenheit(value: f32) -> Self {
+        Temperature::new(value, DisplayUnit::Fahrenheit)
+    }
+
+    pub fn value(self) -> f32 {
+        self.value
+    }
+
+    pub fn unit(self) -> DisplayUnit {
+        self.unit
+    }
+
+    /// The value in `unit`.
+    pub fn to(self, unit: DisplayUnit) -> f32 {
+        unit.from_celsius(self.unit.to_celsius(self.value))
+    }
+}
+
+/// `75 °C`
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = match self.unit {
+            DisplayUnit::Celsius => "°C",
+            DisplayUnit::Fahrenheit => "°F",
+        };
+        write!(f, "{} {}", self.value, unit)
+    }
+}
+
+/// A temperature setting, either as the controller displays it or in a
+/// stated unit.
+#[derive(Clone, Copy, fmt::Debug)]
+pub enum AnyTemp {
+    Raw(TempRaw),
+    Unit(Temperature),
+}
+
+impl From<TempRaw> for AnyTemp {
+    fn from(val: TempRaw) -> Self {
+        AnyTemp::Raw(val)
+    }
+}
+
+impl From<Temperature> for AnyTemp {
+    fn from(val: Temperature) -> Self {
+        AnyTemp::Unit(val)
+    }
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    /// Attach the controller's display unit (CorF) to a reading.
+    pub fn temperature(&mut self, val: TempRaw) -> crate::Result<Temperature, UART> {
+        let unit = self.get_display_unit_cached()?;
+        Ok(Temperature::new(val.0, unit))
+    }
+
+    /// Set the set value (SV) to `value` in `unit`.
+    ///
+    /// Unlike [`Syl2381::set_sv`], this reads the input sensor type (INTY)
+    /// if the driver doesn't know it yet, so the value is always checked
+    /// against what the sensor can measure.
+    pub fn set_temperature(&mut self, value: f32, unit: DisplayUnit) -> crate::Result<(), UART> {
+        if self.live.input_type.is_none() {
+            self.get_input_sensor_type()?;
+        }
+        self.set_sv(Temperature::new(value, unit))
+    }
+
+    /// `val` in the controller's display unit.
+    pub(crate) fn raw_temp(&mut self, val: AnyTemp) -> crate::Result<f32, UART> {
+        match val {
+            AnyTemp::Raw(TempRaw(val)) => Ok(val),
+            AnyTemp::Unit(val) => Ok(val.to(self.get_display_unit_cached()?)),
+        }
+    }
+
+    pub(crate) fn get_display_unit_cached(&mut self) -> crate::Result<DisplayUnit, UART> {
+        match self.live.display_unit {
+            Some(unit) => Ok(unit),
+            None => self.get_display_unit(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockSerial;
+    use crate::{regs, Error};
+
+    #[test]
+    fn converts_to_the_display_unit() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 1.0); // CorF: Fahrenheit
+        port.respond_f32(1, 6.0); // INTY: K
+        port.ack_f32(1, regs::SV.addr);
+        port.ack_f32(1, regs::AH1.addr);
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(pid.set_sv(Temperature::celsius(75.0)).is_ok());
+        assert!(pid.set_j1_on_temp(Temperature::fahrenheit(180.0)).is_ok());
+        assert_eq!(pid.port.tx[16 + 7..16 + 11], 167f32.to_be_bytes());
+        assert_eq!(pid.port.tx[16 + 13 + 7..16 + 13 + 11], 180f32.to_be_bytes());
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn remembers_corf_until_written() {
+        let mut port = MockSerial::new();
+        port.ack_f32(1, regs::CORF.addr);
+        port.respond(&[1, 0x10, 0x20, 0x0C, 0, 2]);
+        port.respond_f32(1, 0.0);
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(pid.set_display_unit(DisplayUnit::Fahrenheit).is_ok());
+        let t = pid.temperature(TempRaw(212.0)).unwrap();
+        assert_eq!(t.to_string(), "212 °F");
+        assert_eq!(t.to(DisplayUnit::Celsius), 100.0);
+        assert_eq!(t.to(DisplayUnit::Fahrenheit), 212.0);
+        assert_eq!(
+            Temperature::celsius(-40.0).to(DisplayUnit::Fahrenheit),
+            -40.0
+        );
+
+        assert!(pid.write_holding_raw(regs::CORF.addr, &[0, 0]).is_ok());
+        let t = pid.temperature(TempRaw(20.0)).unwrap();
+        assert_eq!(t.unit(), DisplayUnit::Celsius);
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn sets_sv_within_the_sensor_range() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 6.0); // INTY: K
+        port.respond_f32(1, 1.0); // CorF: Fahrenheit
+        port.ack_f32(1, regs::SV.addr);
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(pid.set_temperature(75.0, DisplayUnit::Celsius).is_ok());
+        assert_eq!(pid.port.tx[8 + 8 + 7..8 + 8 + 11], 167f32.to_be_bytes());
+        assert!(matches!(
+            pid.set_temperature(1500.0, DisplayUnit::Celsius),
+            Err(Error::OutOfRange { value, .. }) if value == 2732.0
+        ));
+        assert!(pid.port.rx.is_empty());
+    }
+}
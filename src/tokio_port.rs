@@ -0,0 +1,114 @@
+//! Run [`Syl2381Async`](crate::Syl2381Async) on tokio.
+//!
+//! [`TokioPort`] adapts anything implementing tokio's `AsyncRead` and
+//! `AsyncWrite`, such as a `tokio_serial::SerialStream`, to the
+//! `embedded-io-async` traits the async driver is built on. It also enforces a
+//! per-request timeout, so a controller that never answers can't hang a task.
+
+use std::io;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::time::{timeout_at, Instant};
+
+pub struct TokioPort<T> {
+    inner: T,
+    timeout: Duration,
+    deadline: Option<Instant>,
+}
+
+impl<T> TokioPort<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Wrap `inner`, failing any request whose response hasn't fully arrived
+    /// within `timeout` of it being sent.
+    pub fn new(inner: T, timeout: Duration) -> Self {
+        TokioPort {
+            inner,
+            timeout,
+            deadline: None,
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn deadline(&mut self) -> Instant {
+        *self
+            .deadline
+            .get_or_insert_with(|| Instant::now() + self.timeout)
+    }
+}
+
+fn timed_out<T>(_: tokio::time::error::Elapsed) -> io::Result<T> {
+    Err(io::ErrorKind::TimedOut.into())
+}
+
+impl<T> embedded_io_async::ErrorType for TokioPort<T> {
+    type Error = io::Error;
+}
+
+impl<T> embedded_io_async::Read for TokioPort<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    async fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let deadline = self.deadline();
+        timeout_at(deadline, self.inner.read(buf))
+            .await
+            .unwrap_or_else(timed_out)
+    }
+}
+
+impl<T> embedded_io_async::Write for TokioPort<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Writing starts a new request, and with it a new deadline.
+    async fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let deadline = Instant::now() + self.timeout;
+        self.deadline = Some(deadline);
+        timeout_at(deadline, self.inner.write(buf))
+            .await
+            .unwrap_or_else(timed_out)
+    }
+
+    async fn flush(&mut self) -> io::Result<()> {
+        let deadline = self.deadline();
+        timeout_at(deadline, self.inner.flush())
+            .await
+            .unwrap_or_else(timed_out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::frame;
+    use crate::{Error, Syl2381Async, TempRaw};
+
+    #[tokio::test(start_paused = true)]
+    async fn reads_over_a_tokio_stream() {
+        let (client, mut server) = tokio::io::duplex(64);
+        let mut pid = Syl2381Async::new(1, TokioPort::new(client, Duration::from_secs(1)));
+
+        let response = frame(&[0x01, 0x03, 0x04, 0x42, 0x48, 0x00, 0x00]);
+        server.write_all(&response).await.unwrap();
+
+        assert!(matches!(pid.get_pv().await, Ok(TempRaw(v)) if v == 50.0));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn times_out_when_nothing_answers() {
+        let (client, _server) = tokio::io::duplex(64);
+        let mut pid = Syl2381Async::new(1, TokioPort::new(client, Duration::from_secs(1)));
+
+        assert!(matches!(pid.get_pv().await, Err(Error::Timeout(_))));
+    }
+}
@@ -0,0 +1,132 @@
+//! Per-parameter value transforms.
+//!
+//! Integrators often need a small site-specific adjustment, such as a probe
+//! correction on PV or a clamp on SV, applied everywhere a parameter is used.
+//! A [`Transform`] registered for a holding register runs inside the blocking
+//! driver: `read` on every value read from it and `write` on every value about
+//! to be written to it, after the setter has range checked the caller's value.
+//!
+//! Register images are raw and bypass transforms.
+
+use core::fmt;
+
+use crate::clock::DriverClock;
+use crate::regs;
+use crate::transport::Transport;
+use crate::Syl2381;
+
+/// A pair of conversions between the controller's value and the caller's.
+///
+/// `read` and `write` should be inverses, or read-back verification will
+/// report a mismatch.
+#[derive(Clone, Copy, fmt::Debug)]
+pub struct Transform {
+    /// Controller value → caller value.
+    pub read: fn(f32) -> f32,
+
+    /// Caller value → controller value.
+    pub write: fn(f32) -> f32,
+}
+
+/// Registered transforms, keyed by register address.
+#[derive(Default)]
+pub(crate) struct Transforms {
+    map: heapless::LinearMap<u16, Transform, 8>,
+}
+
+impl Transforms {
+    pub(crate) fn read(&self, reg: regs::Reg, val: f32) -> f32 {
+        match self.map.get(&reg.addr) {
+            Some(t) => (t.read)(val),
+            None => val,
+        }
+    }
+
+    pub(crate) fn write(&self, reg: regs::Reg, val: f32) -> f32 {
+        match self.map.get(&reg.addr) {
+            Some(t) => (t.write)(val),
+            None => val,
+        }
+    }
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    /// Apply `transform` to every read and write of the holding register
+    /// `reg`, replacing any transform already set for it.
+    ///
+    /// Returns `false` if `reg` isn't a holding register or 8 transforms are
+    /// already registered.
+    pub fn set_transform(&mut self, reg: regs::Reg, transform: Transform) -> bool {
+        if reg.kind() != regs::Kind::Holding {
+            return false;
+        }
+        self.live.invalidate(reg);
+        self.transforms.map.insert(reg.addr, transform).is_ok()
+    }
+
+    /// Remove the transform for `reg`, if any.
+    pub fn clear_transform(&mut self, reg: regs::Reg) {
+        self.live.invalidate(reg);
+        self.transforms.map.remove(&reg.addr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockSerial;
+    use crate::{image::RegisterImage, TempRaw};
+
+    /// The probe reads 1.5° high.
+    const PROBE: Transform = Transform {
+        read: |v| v - 1.5,
+        write: |v| v + 1.5,
+    };
+
+    #[test]
+    fn transforms_reads_and_writes() {
+        let mut port = MockSerial::new();
+        port.respond_f32(1, 66.5);
+        port.ack_f32(1, regs::SV.addr);
+        port.respond_f32(1, 66.5);
+        let mut pid = Syl2381::new(1, port);
+        pid.live
+            .assume_sensor(crate::InputType::K, crate::DisplayUnit::Celsius);
+        assert!(pid.set_transform(regs::PV, PROBE));
+        assert!(pid.set_transform(regs::SV, PROBE));
+
+        assert!(matches!(pid.get_pv(), Ok(TempRaw(v)) if v == 65.0));
+        assert!(pid.set_sv(TempRaw(65.0)).is_ok());
+        // value on the wire: 66.5
+        assert_eq!(pid.port.tx[15..19], 66.5f32.to_be_bytes());
+
+        pid.clear_transform(regs::PV);
+        assert!(matches!(pid.get_pv(), Ok(TempRaw(v)) if v == 66.5));
+    }
+
+    #[test]
+    fn register_images_bypass_transforms() {
+        let mut image = RegisterImage::new();
+        let [d0, d1] = crate::f32_to_values(65.0);
+        image.insert(regs::SV.addr, d0);
+        image.insert(regs::SV.addr + 1, d1);
+
+        let mut port = MockSerial::new();
+        port.ack_f32(1, regs::SV.addr);
+        let mut pid = Syl2381::new(1, port);
+        assert!(pid.set_transform(regs::SV, PROBE));
+
+        assert!(pid.write_register_image(&image).is_ok());
+        assert_eq!(pid.port.tx[7..11], 65.0f32.to_be_bytes());
+    }
+
+    #[test]
+    fn coils_cant_be_transformed() {
+        let mut pid = Syl2381::new(1, MockSerial::new());
+        assert!(!pid.set_transform(regs::AT, PROBE));
+    }
+}
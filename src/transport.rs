@@ -0,0 +1,669 @@
+//! The byte transport the driver talks through.
+//!
+//! [`Syl2381`](crate::Syl2381) only needs to send a request frame and read the
+//! response back. Implement [`Transport`] to run it over anything that can do
+//! that: a DMA driver, a TCP serial bridge, a shared-bus adapter, or a mock in
+//! tests.
+//!
+//! Adapters for the usual traits and setups are behind features:
+//!
+//! `EmbeddedIo` (`embedded-io`) runs over an `embedded-io` stream, and
+//! `SplitIo` over the separate receive and transmit halves of a split UART.
+//! `Timed` adds a deadline for ports whose reads would otherwise block
+//! forever.
+//!
+//! `IoTransport` (`std`) runs over a blocking `std::io` stream such as a
+//! serial port.
+//!
+//! With `legacy-alpha`, an `embedded-hal-nb` 1.0.0-alpha.3 serial port is a
+//! transport as it is.
+//!
+//! `Tunnel` (`tunnel`) carries frames to a remote serial bridge over an
+//! encrypted link.
+//!
+//! A [`Bus`](crate::Bus) shares one transport between the controllers on a
+//! multi-drop line.
+//!
+//! `Simulator` (`simulator`) is a whole controller in memory.
+//!
+//! `Recorder` and `Replay` (`std`, in `fixture`) capture real traffic and play
+//! it back in tests.
+
+use core::fmt;
+
+pub trait Transport {
+    type Error;
+
+    /// Send a complete request frame.
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), Self::Error>;
+
+    /// Fill `buf` with the next `buf.len()` bytes of the response.
+    ///
+    /// The driver reads the response in pieces: first the header, then the
+    /// remainder once the header says how long the frame is.
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Wait until everything written has actually been sent.
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Whether `err` means the response didn't arrive in time, which the
+    /// driver reports as [`Error::Timeout`](crate::Error::Timeout).
+    fn is_timeout(_err: &Self::Error) -> bool {
+        false
+    }
+
+    /// Told about each write once the controller has acknowledged it, for
+    /// keeping a record of writes; see `Audited` (`audit`). Transports that
+    /// wrap another should pass this on.
+    fn acknowledged(&mut self, _ack: &Ack) {}
+}
+
+/// A write the controller acknowledged.
+#[derive(Clone, Copy, fmt::Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Ack {
+    pub unit_id: u8,
+
+    /// Holding register or coil address.
+    pub addr: u16,
+
+    pub value: AckValue,
+}
+
+/// What an acknowledged write stored.
+#[derive(Clone, Copy, fmt::Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AckValue {
+    /// A holding parameter, both of its registers.
+    Holding(f32),
+
+    /// A single register, from a raw write or half a parameter.
+    Word(u16),
+
+    /// A status coil.
+    Coil(bool),
+}
+
+/// A byte-at-a-time `embedded-hal-nb` 1.0.0-alpha.3 serial port, blocking on
+/// each byte.
+#[cfg(feature = "legacy-alpha")]
+impl<S> Transport for S
+where
+    S: eh_nb_1_0_alpha::serial::Read<u8> + eh_nb_1_0_alpha::serial::Write<u8>,
+{
+    type Error = S::Error;
+
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        for &b in frame {
+            nb::block!(self.write(b))?;
+        }
+        Ok(())
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Self::Error> {
+        for b in buf.iter_mut() {
+            *b = nb::block!(self.read())?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        nb::block!(eh_nb_1_0_alpha::serial::Write::flush(self))
+    }
+}
+
+/// A blocking `embedded-io` stream, as implemented by most current HAL UARTs.
+///
+/// A port that runs dry part way through a response reports
+/// `ReadExactError::UnexpectedEof`.
+#[cfg(feature = "embedded-io")]
+pub struct EmbeddedIo<T> {
+    inner: T,
+}
+
+#[cfg(feature = "embedded-io")]
+impl<T> EmbeddedIo<T>
+where
+    T: embedded_io::Read + embedded_io::Write,
+{
+    pub fn new(inner: T) -> Self {
+        EmbeddedIo { inner }
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<T> Transport for EmbeddedIo<T>
+where
+    T: embedded_io::Read + embedded_io::Write,
+{
+    type Error = embedded_io::ReadExactError<T::Error>;
+
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        self.inner
+            .write_all(frame)
+            .map_err(embedded_io::ReadExactError::Other)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.inner.read_exact(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner
+            .flush()
+            .map_err(embedded_io::ReadExactError::Other)
+    }
+
+    fn is_timeout(err: &Self::Error) -> bool {
+        timed_out(err)
+    }
+}
+
+/// Separate `embedded-io` receive and transmit halves, as HALs like embassy
+/// and esp-hal hand out after splitting a UART.
+#[cfg(feature = "embedded-io")]
+pub struct SplitIo<R, W> {
+    rx: R,
+    tx: W,
+}
+
+#[cfg(feature = "embedded-io")]
+impl<R, W> SplitIo<R, W> {
+    pub fn new(rx: R, tx: W) -> Self {
+        SplitIo { rx, tx }
+    }
+
+    pub fn into_inner(self) -> (R, W) {
+        (self.rx, self.tx)
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<R, W> Transport for SplitIo<R, W>
+where
+    R: embedded_io::Read,
+    W: embedded_io::Write<Error = R::Error>,
+{
+    type Error = embedded_io::ReadExactError<R::Error>;
+
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        self.tx
+            .write_all(frame)
+            .map_err(embedded_io::ReadExactError::Other)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.rx.read_exact(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.tx.flush().map_err(embedded_io::ReadExactError::Other)
+    }
+
+    fn is_timeout(err: &Self::Error) -> bool {
+        timed_out(err)
+    }
+}
+
+/// Whether an `embedded-io` port gave up waiting for the response.
+#[cfg(feature = "embedded-io")]
+fn timed_out<E>(err: &embedded_io::ReadExactError<E>) -> bool
+where
+    E: embedded_io::Error,
+{
+    matches!(err, embedded_io::ReadExactError::Other(err) if err.kind() == embedded_io::ErrorKind::TimedOut)
+}
+
+/// Lets [`Syl2381Async`](crate::Syl2381Async) run over split async halves too.
+#[cfg(all(feature = "embedded-io", feature = "async"))]
+impl<R, W> embedded_io_async::ErrorType for SplitIo<R, W>
+where
+    R: embedded_io_async::ErrorType,
+{
+    type Error = R::Error;
+}
+
+#[cfg(all(feature = "embedded-io", feature = "async"))]
+impl<R, W> embedded_io_async::Read for SplitIo<R, W>
+where
+    R: embedded_io_async::Read,
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.rx.read(buf).await
+    }
+}
+
+#[cfg(all(feature = "embedded-io", feature = "async"))]
+impl<R, W> embedded_io_async::Write for SplitIo<R, W>
+where
+    R: embedded_io_async::ErrorType,
+    W: embedded_io_async::Write<Error = R::Error>,
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.tx.write(buf).await
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.tx.flush().await
+    }
+}
+
+/// A transport error, or a response that didn't arrive in time.
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TimeoutError<E> {
+    Timeout,
+
+    /// The port reached end of stream mid-response: it was closed, not slow.
+    Closed,
+
+    Other(E),
+}
+
+/// An `embedded-io` port polled until a deadline, for targets where a blocking
+/// read would hang forever on a disconnected controller.
+///
+/// The port must report `ReadReady`. Each request gets `timeout` from when it
+/// is sent until its response has been read in full.
+#[cfg(feature = "embedded-io")]
+pub struct Timed<T, C> {
+    inner: T,
+    clock: C,
+    timeout: core::time::Duration,
+    deadline: core::time::Duration,
+}
+
+#[cfg(feature = "embedded-io")]
+impl<T, C> Timed<T, C>
+where
+    T: embedded_io::Read + embedded_io::ReadReady + embedded_io::Write,
+    C: crate::Clock,
+{
+    pub fn new(inner: T, clock: C, timeout: core::time::Duration) -> Self {
+        Timed {
+            inner,
+            clock,
+            timeout,
+            deadline: core::time::Duration::ZERO,
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<T, C> Transport for Timed<T, C>
+where
+    T: embedded_io::Read + embedded_io::ReadReady + embedded_io::Write,
+    C: crate::Clock,
+{
+    type Error = TimeoutError<T::Error>;
+
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        self.deadline = self.clock.now() + self.timeout;
+        self.inner.write_all(frame).map_err(TimeoutError::Other)
+    }
+
+    fn read_exact(&mut self, mut buf: &mut [u8]) -> Result<(), Self::Error> {
+        while !buf.is_empty() {
+            if !self.inner.read_ready().map_err(TimeoutError::Other)? {
+                if self.clock.now() >= self.deadline {
+                    return Err(TimeoutError::Timeout);
+                }
+                // About a tenth of a character time at 9600 baud.
+                self.clock.sleep(core::time::Duration::from_micros(100));
+                continue;
+            }
+            match self.inner.read(buf).map_err(TimeoutError::Other)? {
+                0 => return Err(TimeoutError::Closed),
+                n => buf = &mut buf[n..],
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner.flush().map_err(TimeoutError::Other)
+    }
+
+    fn is_timeout(err: &Self::Error) -> bool {
+        matches!(err, TimeoutError::Timeout)
+    }
+}
+
+/// A blocking `std::io` stream, such as a `serialport::SerialPort`.
+///
+/// A read that times out surfaces as an `io::ErrorKind::TimedOut` error.
+#[cfg(feature = "std")]
+pub struct IoTransport<T> {
+    inner: T,
+}
+
+#[cfg(feature = "std")]
+impl<T> IoTransport<T>
+where
+    T: std::io::Read + std::io::Write,
+{
+    pub fn new(inner: T) -> Self {
+        IoTransport { inner }
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Transport for IoTransport<T>
+where
+    T: std::io::Read + std::io::Write,
+{
+    type Error = std::io::Error;
+
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        self.inner.write_all(frame)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.inner.read_exact(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner.flush()
+    }
+
+    fn is_timeout(err: &Self::Error) -> bool {
+        matches!(
+            err.kind(),
+            std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::frame;
+    use crate::{Syl2381, TempRaw};
+
+    /// Answers every request with one canned frame.
+    struct Canned {
+        response: Vec<u8>,
+        sent: Vec<Vec<u8>>,
+    }
+
+    impl Transport for Canned {
+        type Error = ();
+
+        fn write_frame(&mut self, frame: &[u8]) -> Result<(), ()> {
+            self.sent.push(frame.to_vec());
+            Ok(())
+        }
+
+        fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), ()> {
+            if buf.len() > self.response.len() {
+                return Err(());
+            }
+            let rest = self.response.split_off(buf.len());
+            buf.copy_from_slice(&self.response);
+            self.response = rest;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn drives_a_custom_transport() {
+        let port = Canned {
+            response: frame(&[0x01, 0x03, 0x04, 0x42, 0x48, 0x00, 0x00]),
+            sent: Vec::new(),
+        };
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(matches!(pid.get_pv(), Ok(TempRaw(v)) if v == 50.0));
+        assert_eq!(pid.port.sent.len(), 1);
+        assert_eq!(pid.port.sent[0][..2], [0x01, 0x03]);
+    }
+
+    /// Reads come from `rx`, writes land in `tx`.
+    #[cfg(feature = "std")]
+    struct Duplex {
+        rx: std::io::Cursor<Vec<u8>>,
+        tx: Vec<u8>,
+    }
+
+    #[cfg(feature = "std")]
+    impl std::io::Read for Duplex {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.rx.read(buf)
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::io::Write for Duplex {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.tx.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn drives_an_io_stream() {
+        let port = IoTransport::new(Duplex {
+            rx: std::io::Cursor::new(frame(&[0x01, 0x03, 0x04, 0x42, 0x48, 0x00, 0x00])),
+            tx: Vec::new(),
+        });
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(matches!(pid.get_pv(), Ok(TempRaw(v)) if v == 50.0));
+        assert_eq!(pid.port.get_mut().tx[..2], [0x01, 0x03]);
+        assert!(matches!(
+            pid.get_pv(),
+            Err(crate::Error::SerialError(_, e)) if e.kind() == std::io::ErrorKind::UnexpectedEof
+        ));
+    }
+
+    /// `Duplex` again, over `embedded-io`.
+    #[cfg(feature = "embedded-io")]
+    struct EioDuplex {
+        rx: Vec<u8>,
+        tx: Vec<u8>,
+    }
+
+    #[cfg(feature = "embedded-io")]
+    impl embedded_io::ErrorType for EioDuplex {
+        type Error = embedded_io::ErrorKind;
+    }
+
+    #[cfg(feature = "embedded-io")]
+    impl embedded_io::Read for EioDuplex {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            let n = buf.len().min(self.rx.len());
+            buf[..n].copy_from_slice(&self.rx[..n]);
+            self.rx.drain(..n);
+            Ok(n)
+        }
+    }
+
+    #[cfg(feature = "embedded-io")]
+    impl embedded_io::Write for EioDuplex {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.tx.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "embedded-io")]
+    #[test]
+    fn drives_an_embedded_io_stream() {
+        let port = EmbeddedIo::new(EioDuplex {
+            rx: frame(&[0x01, 0x03, 0x04, 0x42, 0x48, 0x00, 0x00]),
+            tx: Vec::new(),
+        });
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(matches!(pid.get_pv(), Ok(TempRaw(v)) if v == 50.0));
+        assert_eq!(pid.port.get_mut().tx[..2], [0x01, 0x03]);
+        assert!(matches!(
+            pid.get_pv(),
+            Err(crate::Error::SerialError(
+                _,
+                embedded_io::ReadExactError::UnexpectedEof
+            ))
+        ));
+    }
+
+    #[cfg(feature = "embedded-io")]
+    #[test]
+    fn drives_split_halves() {
+        let rx = EioDuplex {
+            rx: frame(&[0x01, 0x03, 0x04, 0x42, 0x48, 0x00, 0x00]),
+            tx: Vec::new(),
+        };
+        let tx = EioDuplex {
+            rx: Vec::new(),
+            tx: Vec::new(),
+        };
+        let mut pid = Syl2381::new(1, SplitIo::new(rx, tx));
+
+        assert!(matches!(pid.get_pv(), Ok(TempRaw(v)) if v == 50.0));
+        let (rx, tx) = pid.port.into_inner();
+        assert!(rx.tx.is_empty());
+        assert_eq!(tx.tx[..2], [0x01, 0x03]);
+    }
+
+    /// A port with a read timeout of its own, and nothing to read.
+    #[cfg(feature = "embedded-io")]
+    struct Silent;
+
+    #[cfg(feature = "embedded-io")]
+    impl embedded_io::ErrorType for Silent {
+        type Error = embedded_io::ErrorKind;
+    }
+
+    #[cfg(feature = "embedded-io")]
+    impl embedded_io::Read for Silent {
+        fn read(&mut self, _: &mut [u8]) -> Result<usize, Self::Error> {
+            Err(embedded_io::ErrorKind::TimedOut)
+        }
+    }
+
+    #[cfg(feature = "embedded-io")]
+    impl embedded_io::Write for Silent {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "embedded-io")]
+    #[test]
+    fn reports_port_timeouts() {
+        let mut pid = Syl2381::new(1, EmbeddedIo::new(Silent));
+        assert!(matches!(pid.get_pv(), Err(crate::Error::Timeout(_))));
+
+        let mut pid = Syl2381::new(1, SplitIo::new(Silent, Silent));
+        assert!(matches!(pid.get_pv(), Err(crate::Error::Timeout(_))));
+    }
+
+    #[cfg(feature = "embedded-io")]
+    impl embedded_io::ReadReady for EioDuplex {
+        fn read_ready(&mut self) -> Result<bool, Self::Error> {
+            Ok(!self.rx.is_empty())
+        }
+    }
+
+    #[cfg(feature = "embedded-io")]
+    #[test]
+    fn times_out_a_silent_port() {
+        let port = EioDuplex {
+            rx: frame(&[0x01, 0x03, 0x04, 0x42, 0x48, 0x00, 0x00]),
+            tx: Vec::new(),
+        };
+        let clock = crate::clock::VirtualClock::new();
+        let timeout = core::time::Duration::from_millis(500);
+        let mut pid = Syl2381::new(1, Timed::new(port, &clock, timeout));
+
+        assert!(matches!(pid.get_pv(), Ok(TempRaw(v)) if v == 50.0));
+        let sent = crate::Clock::now(&clock);
+        assert!(matches!(pid.get_pv(), Err(crate::Error::Timeout(_))));
+        assert!(crate::Clock::now(&clock) - sent >= timeout);
+    }
+
+    /// A port that has been closed: always ready, with nothing left to read.
+    #[cfg(feature = "embedded-io")]
+    struct Closed;
+
+    #[cfg(feature = "embedded-io")]
+    impl embedded_io::ErrorType for Closed {
+        type Error = embedded_io::ErrorKind;
+    }
+
+    #[cfg(feature = "embedded-io")]
+    impl embedded_io::Read for Closed {
+        fn read(&mut self, _: &mut [u8]) -> Result<usize, Self::Error> {
+            Ok(0)
+        }
+    }
+
+    #[cfg(feature = "embedded-io")]
+    impl embedded_io::ReadReady for Closed {
+        fn read_ready(&mut self) -> Result<bool, Self::Error> {
+            Ok(true)
+        }
+    }
+
+    #[cfg(feature = "embedded-io")]
+    impl embedded_io::Write for Closed {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "embedded-io")]
+    #[test]
+    fn a_closed_port_is_not_a_timeout() {
+        let clock = crate::clock::VirtualClock::new();
+        let timeout = core::time::Duration::from_millis(500);
+        let mut pid = Syl2381::new(1, Timed::new(Closed, &clock, timeout));
+
+        assert!(matches!(
+            pid.get_pv(),
+            Err(crate::Error::SerialError(_, TimeoutError::Closed))
+        ));
+    }
+}
@@ -0,0 +1,345 @@
+//! PID tuning.
+//!
+//! Setting the AT coil makes the controller work out P, I and D for itself,
+//! which takes anywhere from a few minutes to well over an hour depending on
+//! the process. [`Syl2381::wait_autotune_complete`] polls until it's done and
+//! hands back the result.
+//!
+//! P, I and D sit next to each other in the PID bank, so
+//! [`Syl2381::get_pid`] and [`Syl2381::set_pid`] move all three in one
+//! request.
+
+use core::fmt;
+use core::time::Duration;
+
+use crate::clock::{Clock, DriverClock};
+use crate::transport::Transport;
+use crate::{
+    f32_to_values, limits, regs, try_from_f32, values_to_f32, AckValue, Context, Error, Frame,
+    Operation, Seconds, Syl2381, WriteStrategy,
+};
+
+/// Registers from P through D.
+const WORDS: u16 = regs::D.addr + regs::D.width() - regs::P.addr;
+
+/// Proportional band (P), integral time (I) and derivative time (D).
+#[derive(Clone, Copy, fmt::Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pid {
+    pub p: f32,
+    pub i: Seconds,
+    pub d: Seconds,
+}
+
+/// `P 3.5, I 120s, D 30s`
+impl fmt::Display for Pid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "P {}, I {}s, D {}s", self.p, self.i.0, self.d.0)
+    }
+}
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    /// Start autotuning (set AT).
+    pub fn start_autotune(&mut self) -> crate::Result<(), UART> {
+        self.set_coil(regs::AT, true)
+    }
+
+    /// Abort autotuning (clear AT), leaving P, I and D as they were.
+    pub fn abort_autotune(&mut self) -> crate::Result<(), UART> {
+        self.set_coil(regs::AT, false)
+    }
+
+    /// Poll AT every `poll_every` until autotuning finishes, then read the
+    /// P, I and D it settled on.
+    ///
+    /// Gives up with [`Error::Timeout`] once `timeout` has passed; tuning
+    /// carries on regardless, see [`Syl2381::abort_autotune`].
+    pub fn wait_autotune_complete(
+        &mut self,
+        clock: &impl Clock,
+        poll_every: Duration,
+        timeout: Duration,
+    ) -> crate::Result<Pid, UART> {
+        let start = clock.now();
+        while self.get_status()?.autotune_mode() {
+            if clock.now().saturating_sub(start) >= timeout {
+                return Err(Error::Timeout(Context::new(Operation::ReadCoils, regs::AT)));
+            }
+            clock.sleep(poll_every);
+        }
+
+        self.get_pid()
+    }
+
+    /// Get P, I and D in one request.
+    pub fn get_pid(&mut self) -> crate::Result<Pid, UART> {
+        let [p, i, d] = self.get_pid_raw()?;
+        Ok(Pid {
+            p: self.transforms.read(regs::P, p),
+            i: try_from_f32(self.transforms.read(regs::I, i))?,
+            d: try_from_f32(self.transforms.read(regs::D, d))?,
+        })
+    }
+
+    /// Set P, I and D in one request.
+    ///
+    /// All three are range checked before anything is written. The write
+    /// guard, lockout and read-back verification apply as for
+    /// [`Syl2381::set_p`] and friends; with [`WriteStrategy::Single`] it takes
+    /// six requests. If the write guard knows some of the three already hold
+    /// their new value, only the others are written, one at a time.
+    pub fn set_pid(&mut self, pid: Pid) -> crate::Result<(), UART> {
+        if !limits::P.contains(&pid.p) {
+            return Err(Error::UnexpectedValue(pid.p));
+        }
+        for (Seconds(val), range) in [(pid.i, limits::I), (pid.d, limits::D)] {
+            if !range.contains(&val) {
+                return Err(Error::UnexpectedValue(val as f32));
+            }
+        }
+        let vals = [
+            (regs::P, self.transforms.write(regs::P, pid.p)),
+            (regs::I, self.transforms.write(regs::I, pid.i.0 as f32)),
+            (regs::D, self.transforms.write(regs::D, pid.d.0 as f32)),
+        ];
+
+        let mut changed = [false; 3];
+        for (changed, (reg, val)) in changed.iter_mut().zip(vals) {
+            let ctx = Context::new(Operation::WriteHolding, reg);
+            *changed = self.guard_write(ctx, reg, val)?;
+        }
+        if !changed.contains(&true) {
+            return Ok(());
+        }
+        let ctx = Context::new(Operation::WriteHolding, regs::P);
+        self.check_lockout(ctx)?;
+
+        if changed.contains(&false) {
+            for (_, (reg, val)) in changed.into_iter().zip(vals).filter(|&(c, _)| c) {
+                self.write_f32(Context::new(Operation::WriteHolding, reg), reg, val)?;
+                self.wrote(reg, val);
+            }
+        } else {
+            self.write_pid(ctx, vals)?;
+            for (reg, val) in vals {
+                self.wrote(reg, val);
+            }
+        }
+
+        if self.verify {
+            let actual = self.get_pid_raw()?;
+            for ((_, expected), actual) in vals.into_iter().zip(actual) {
+                if actual != expected {
+                    return Err(Error::VerificationFailed { expected, actual });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn get_pid_raw(&mut self) -> crate::Result<[f32; 3], UART> {
+        let ctx = Context::new(Operation::ReadHolding, regs::P);
+        let fail = |kind| Error::modbus(ctx, kind);
+        let mut frame = Frame::get_words(self.unit_id, regs::P.addr, WORDS).map_err(fail)?;
+        self.transact(ctx, &mut frame)?;
+        let words: heapless::Vec<u16, { WORDS as usize }> = frame.parse_words().map_err(fail)?;
+        let [p0, p1, i0, i1, d0, d1] = words[..] else {
+            return Err(fail(rmodbus::ErrorKind::FrameBroken));
+        };
+
+        let vals = [
+            values_to_f32(p0, p1),
+            values_to_f32(i0, i1),
+            values_to_f32(d0, d1),
+        ];
+        for (reg, val) in [regs::P, regs::I, regs::D].into_iter().zip(vals) {
+            self.writes.read(reg, val);
+        }
+        Ok(vals)
+    }
+
+    /// Write P, I and D as the write strategy says.
+    fn write_pid(&mut self, ctx: Context, vals: [(regs::Reg, f32); 3]) -> crate::Result<(), UART> {
+        if self.strategy != WriteStrategy::Single {
+            let mut words = [0; WORDS as usize];
+            for (pair, (_, val)) in words.chunks_exact_mut(2).zip(vals) {
+                pair.copy_from_slice(&f32_to_values(val));
+            }
+
+            let fail = |kind| Error::modbus(ctx, kind);
+            let mut frame = Frame::set_words(self.unit_id, regs::P.addr, &words).map_err(fail)?;
+            self.transact(ctx, &mut frame)?;
+            match frame.parse_ok().map_err(fail) {
+                Err(Error::IllegalFunction(_)) if self.strategy == WriteStrategy::Fallback => {
+                    self.strategy = WriteStrategy::Single;
+                }
+                res => {
+                    res?;
+                    for (reg, val) in vals {
+                        self.acknowledged(reg.addr, AckValue::Holding(val));
+                    }
+                    return Ok(());
+                }
+            }
+        }
+
+        for (reg, val) in vals {
+            self.write_f32(Context::new(Operation::WriteHolding, reg), reg, val)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::VirtualClock;
+    use crate::mock::{frame, MockSerial};
+
+    fn pid_response(p: f32, i: f32, d: f32) -> Vec<u8> {
+        let mut resp = vec![1, 0x03, 12];
+        for val in [p, i, d] {
+            resp.extend_from_slice(&val.to_be_bytes());
+        }
+        resp
+    }
+
+    fn pid_write(reg: regs::Reg, val: f32) -> Vec<u8> {
+        let [a0, a1] = reg.addr.to_be_bytes();
+        let mut req = vec![1, 0x10, a0, a1, 0, 2, 4];
+        req.extend_from_slice(&val.to_be_bytes());
+        frame(&req)
+    }
+
+    #[test]
+    fn writes_the_at_coil() {
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x05, 0x00, 0x00, 0xFF, 0x00]);
+        port.respond(&[1, 0x05, 0x00, 0x00, 0x00, 0x00]);
+        let mut pid = Syl2381::new(1, port);
+
+        pid.start_autotune().unwrap();
+        pid.abort_autotune().unwrap();
+        assert_eq!(pid.port.tx[..8], frame(&[1, 0x05, 0x00, 0x00, 0xFF, 0x00]));
+        assert_eq!(pid.port.tx[8..], frame(&[1, 0x05, 0x00, 0x00, 0x00, 0x00]));
+    }
+
+    #[test]
+    fn waits_for_tuning_to_finish() {
+        let clock = VirtualClock::new();
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x01, 1, 0x01]);
+        port.respond(&[1, 0x01, 1, 0x01]);
+        port.respond(&[1, 0x01, 1, 0x00]);
+        port.respond(&pid_response(3.5, 120.0, 30.0));
+        let mut pid = Syl2381::new(1, port);
+
+        let poll = Duration::from_secs(5);
+        let tuned = pid
+            .wait_autotune_complete(&clock, poll, Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(tuned.to_string(), "P 3.5, I 120s, D 30s");
+        assert_eq!(clock.now(), 2 * poll);
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn gives_up_after_the_timeout() {
+        let clock = VirtualClock::new();
+        let mut port = MockSerial::new();
+        for _ in 0..3 {
+            port.respond(&[1, 0x01, 1, 0x01]);
+        }
+        let mut pid = Syl2381::new(1, port);
+
+        let poll = Duration::from_secs(5);
+        assert!(matches!(
+            pid.wait_autotune_complete(&clock, poll, 2 * poll),
+            Err(Error::Timeout(ctx)) if ctx.op == Operation::ReadCoils
+        ));
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn moves_pid_in_one_request() {
+        let mut port = MockSerial::new();
+        port.respond(&pid_response(3.5, 120.0, 30.0));
+        port.respond(&[1, 0x10, 0x10, 0x00, 0, 6]);
+        let mut pid = Syl2381::new(1, port);
+
+        let gains = pid.get_pid().unwrap();
+        assert_eq!(gains.to_string(), "P 3.5, I 120s, D 30s");
+        assert_eq!(pid.port.tx[..8], frame(&[1, 0x03, 0x10, 0x00, 0x00, 0x06]));
+
+        let gains = Pid {
+            p: 4.0,
+            i: Seconds(90),
+            d: Seconds(20),
+        };
+        assert!(pid.set_pid(gains).is_ok());
+        let mut req = vec![1, 0x10, 0x10, 0x00, 0, 6, 12];
+        for val in [4f32, 90.0, 20.0] {
+            req.extend_from_slice(&val.to_be_bytes());
+        }
+        assert_eq!(pid.port.tx[8..], frame(&req));
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn writes_only_changed_gains() {
+        let mut port = MockSerial::new();
+        port.respond(&pid_response(3.5, 120.0, 30.0));
+        port.ack_f32(1, regs::I.addr);
+        port.ack_f32(1, regs::P.addr);
+        let mut pid = Syl2381::new(1, port).with_clock(VirtualClock::new());
+        pid.set_write_guard(Some(crate::WriteGuard {
+            min_interval: Duration::from_secs(60),
+        }));
+
+        let gains = pid.get_pid().unwrap();
+        assert!(pid
+            .set_pid(Pid {
+                i: Seconds(90),
+                ..gains
+            })
+            .is_ok());
+        assert_eq!(pid.port.tx[8..21], pid_write(regs::I, 90.0));
+
+        // P wasn't written above, so it isn't throttled.
+        let gains = Pid {
+            p: 4.0,
+            i: Seconds(90),
+            ..gains
+        };
+        assert!(pid.set_pid(gains).is_ok());
+        assert_eq!(pid.port.tx[21..], pid_write(regs::P, 4.0));
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn rejects_fractional_times() {
+        let mut port = MockSerial::new();
+        port.respond(&pid_response(3.5, 120.5, 30.0));
+        let mut pid = Syl2381::new(1, port);
+
+        assert!(matches!(pid.get_pid(), Err(Error::UnexpectedValue(v)) if v == 120.5));
+    }
+
+    #[test]
+    fn checks_all_gains_before_writing() {
+        let mut pid = Syl2381::new(1, MockSerial::new());
+
+        let gains = Pid {
+            p: 4.0,
+            i: Seconds(90),
+            d: Seconds(1000),
+        };
+        assert!(matches!(pid.set_pid(gains), Err(Error::UnexpectedValue(v)) if v == 1000.0));
+        assert!(pid.port.tx.is_empty());
+    }
+}
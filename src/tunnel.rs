@@ -0,0 +1,240 @@
+//! An encrypted tunnel to a remote serial bridge.
+//!
+//! Exposing raw Modbus on the internet lets anyone who finds the port change
+//! setpoints. A [`Tunnel`] runs the driver over any byte stream (usually a
+//! `TcpStream`) through a `Noise_KK_25519_ChaChaPoly_BLAKE2s` session: both
+//! ends know each other's static public key in advance, so each side
+//! authenticates the other and nobody else can read or inject frames.
+//!
+//! On the wire every Noise message carries a two byte big-endian length
+//! prefix. Requests go out one frame per message; the bridge may split
+//! responses across messages however it likes. See the `tunnel_bridge`
+//! example for the other end.
+
+use std::io::{self, Read, Write};
+
+use snow::params::NoiseParams;
+
+use crate::transport::{IoTransport, Transport};
+
+const PATTERN: &str = "Noise_KK_25519_ChaChaPoly_BLAKE2s";
+const MAX_MSG: usize = 65535;
+
+/// A static X25519 key pair.
+#[derive(Clone)]
+pub struct Keypair {
+    pub private: [u8; 32],
+    pub public: [u8; 32],
+}
+
+impl Keypair {
+    pub fn generate() -> io::Result<Self> {
+        let pair = builder().generate_keypair().map_err(noise_error)?;
+        let mut keys = Keypair {
+            private: [0; 32],
+            public: [0; 32],
+        };
+        keys.private.copy_from_slice(&pair.private);
+        keys.public.copy_from_slice(&pair.public);
+        Ok(keys)
+    }
+}
+
+pub struct Tunnel<S> {
+    stream: S,
+    noise: snow::TransportState,
+    /// Decrypted bytes not yet read.
+    rx: Vec<u8>,
+    pos: usize,
+    msg: Vec<u8>,
+}
+
+impl<S> Tunnel<S>
+where
+    S: Read + Write,
+{
+    /// Open a tunnel to the bridge whose public key is `peer`.
+    pub fn connect(mut stream: S, private: &[u8; 32], peer: &[u8; 32]) -> io::Result<Self> {
+        let mut hs = builder()
+            .local_private_key(private)
+            .remote_public_key(peer)
+            .build_initiator()
+            .map_err(noise_error)?;
+        let mut msg = vec![0; MAX_MSG];
+
+        let n = hs.write_message(&[], &mut msg).map_err(noise_error)?;
+        send(&mut stream, &msg[..n])?;
+        let n = recv(&mut stream, &mut msg)?;
+        hs.read_message(&msg[..n], &mut []).map_err(noise_error)?;
+
+        Self::finish(stream, hs, msg)
+    }
+
+    /// Accept a tunnel from the client whose public key is `peer`; this is
+    /// the bridge's end.
+    pub fn accept(mut stream: S, private: &[u8; 32], peer: &[u8; 32]) -> io::Result<Self> {
+        let mut hs = builder()
+            .local_private_key(private)
+            .remote_public_key(peer)
+            .build_responder()
+            .map_err(noise_error)?;
+        let mut msg = vec![0; MAX_MSG];
+
+        let n = recv(&mut stream, &mut msg)?;
+        hs.read_message(&msg[..n], &mut []).map_err(noise_error)?;
+        let n = hs.write_message(&[], &mut msg).map_err(noise_error)?;
+        send(&mut stream, &msg[..n])?;
+
+        Self::finish(stream, hs, msg)
+    }
+
+    fn finish(stream: S, hs: snow::HandshakeState, msg: Vec<u8>) -> io::Result<Self> {
+        Ok(Tunnel {
+            stream,
+            noise: hs.into_transport_mode().map_err(noise_error)?,
+            rx: Vec::new(),
+            pos: 0,
+            msg,
+        })
+    }
+
+    /// Encrypt and send `payload` as one message.
+    pub fn send(&mut self, payload: &[u8]) -> io::Result<()> {
+        let n = self
+            .noise
+            .write_message(payload, &mut self.msg)
+            .map_err(noise_error)?;
+        send(&mut self.stream, &self.msg[..n])?;
+        self.stream.flush()
+    }
+
+    /// Receive and decrypt the next message.
+    pub fn recv(&mut self) -> io::Result<Vec<u8>> {
+        let n = recv(&mut self.stream, &mut self.msg)?;
+        let mut payload = vec![0; n];
+        let len = self
+            .noise
+            .read_message(&self.msg[..n], &mut payload)
+            .map_err(noise_error)?;
+        payload.truncate(len);
+        Ok(payload)
+    }
+
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+/// A timeout part way through a message leaves the tunnel out of step; open
+/// a new one after [`Error::Timeout`](crate::Error::Timeout).
+impl<S> Transport for Tunnel<S>
+where
+    S: Read + Write,
+{
+    type Error = io::Error;
+
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        // Anything left over belongs to an earlier, abandoned response.
+        self.rx.clear();
+        self.pos = 0;
+        self.send(frame)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Self::Error> {
+        while self.rx.len() - self.pos < buf.len() {
+            let payload = self.recv()?;
+            self.rx.drain(..self.pos);
+            self.pos = 0;
+            self.rx.extend_from_slice(&payload);
+        }
+        buf.copy_from_slice(&self.rx[self.pos..self.pos + buf.len()]);
+        self.pos += buf.len();
+        Ok(())
+    }
+
+    fn is_timeout(err: &Self::Error) -> bool {
+        <IoTransport<S> as Transport>::is_timeout(err)
+    }
+}
+
+fn builder() -> snow::Builder<'static> {
+    let params: NoiseParams = PATTERN.parse().expect("valid noise pattern");
+    snow::Builder::new(params)
+}
+
+fn send(stream: &mut impl Write, msg: &[u8]) -> io::Result<()> {
+    stream.write_all(&(msg.len() as u16).to_be_bytes())?;
+    stream.write_all(msg)
+}
+
+fn recv(stream: &mut impl Read, msg: &mut [u8]) -> io::Result<usize> {
+    let mut len = [0; 2];
+    stream.read_exact(&mut len)?;
+    let len = u16::from_be_bytes(len) as usize;
+    stream.read_exact(&mut msg[..len])?;
+    Ok(len)
+}
+
+fn noise_error(err: snow::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::frame;
+    use crate::{Syl2381, TempRaw};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    #[test]
+    fn carries_frames_both_ways() {
+        let client = Keypair::generate().unwrap();
+        let bridge = Keypair::generate().unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client_pub = client.public;
+        let bridge_keys = bridge.clone();
+        let remote = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut tunnel = Tunnel::accept(stream, &bridge_keys.private, &client_pub).unwrap();
+            let request = tunnel.recv().unwrap();
+            let response = frame(&[0x01, 0x03, 0x04, 0x42, 0x48, 0x00, 0x00]);
+            tunnel.send(&response[..2]).unwrap();
+            tunnel.send(&response[2..]).unwrap();
+            request
+        });
+
+        let stream = TcpStream::connect(addr).unwrap();
+        let tunnel = Tunnel::connect(stream, &client.private, &bridge.public).unwrap();
+        let mut pid = Syl2381::new(1, tunnel);
+        assert!(matches!(pid.get_sv(), Ok(TempRaw(v)) if v == 50.0));
+
+        let request = remote.join().unwrap();
+        assert_eq!(request, frame(&[0x01, 0x03, 0x00, 0x00, 0x00, 0x02]));
+    }
+
+    #[test]
+    fn rejects_unknown_peers() {
+        let client = Keypair::generate().unwrap();
+        let bridge = Keypair::generate().unwrap();
+        let stranger = Keypair::generate().unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let (client_pub, bridge_pub) = (client.public, bridge.public);
+        let remote = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            Tunnel::accept(stream, &bridge.private, &client_pub).is_err()
+        });
+
+        let stream = TcpStream::connect(addr).unwrap();
+        let _ = Tunnel::connect(stream, &stranger.private, &bridge_pub);
+        assert!(remote.join().unwrap());
+    }
+}
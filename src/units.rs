@@ -0,0 +1,136 @@
+//! Unit-carrying wrappers for register values.
+//!
+//! The SYL-2381 stores every holding parameter as a bare `f32`, regardless of
+//! whether it holds a temperature, a duration or a ratio. These newtypes make
+//! the unit part of the signature, so a control cycle can't be passed where an
+//! integral time or a setpoint is expected.
+
+use core::fmt;
+
+/// A duration in whole seconds (I, D, OT).
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Seconds(pub u16);
+
+/// A fraction of full scale, as stored by the controller (OUT, SouF).
+///
+/// The wrapped value runs from 0.0 to 1.0, not 0 to 100: `Percent(0.5)` is
+/// half power. [`Percent::from_percent`] takes the 0-100 form.
+#[derive(Clone, Copy, fmt::Debug, PartialEq, PartialOrd, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Percent(pub f32);
+
+/// A temperature (or temperature difference) in the controller's configured
+/// display unit (PV, SV, AH1, AL1, BB, Hy, PSb).
+///
+/// The value is "raw" in the sense that it's not tied to Celsius or
+/// Fahrenheit; its meaning depends on the controller's CorF setting.
+#[derive(Clone, Copy, fmt::Debug, PartialEq, PartialOrd, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TempRaw(pub f32);
+
+macro_rules! impl_unit {
+    ($name:ident, $inner:ty, $suffix:expr) => {
+        impl_unit!($name, $inner);
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)?;
+                f.write_str($suffix)
+            }
+        }
+    };
+    ($name:ident, $inner:ty) => {
+        impl From<$inner> for $name {
+            fn from(value: $inner) -> Self {
+                $name(value)
+            }
+        }
+
+        impl From<$name> for $inner {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+    };
+}
+
+impl_unit!(Seconds, u16, " s");
+impl_unit!(Percent, f32);
+impl_unit!(TempRaw, f32, "°");
+
+/// Whole seconds only, as read back from I, D or OT.
+impl TryFrom<f32> for Seconds {
+    type Error = ();
+    fn try_from(value: f32) -> core::result::Result<Self, Self::Error> {
+        // `as` saturates (and takes NaN to 0), so anything that isn't a whole
+        // number of seconds in range won't make the round trip.
+        let val = value as u16;
+        if val as f32 != value {
+            return Err(());
+        }
+
+        Ok(Seconds(val))
+    }
+}
+
+impl Percent {
+    pub const ZERO: Percent = Percent(0.0);
+    pub const FULL: Percent = Percent(1.0);
+
+    /// From a percentage, 0 to 100.
+    pub fn from_percent(val: f32) -> Self {
+        Percent(val / 100.0)
+    }
+
+    /// As a percentage, 0 to 100.
+    pub fn percent(self) -> f32 {
+        self.0 * 100.0
+    }
+
+    /// Whether this is something the controller accepts: 0% to 100%.
+    pub fn is_valid(self) -> bool {
+        (0.0..=1.0).contains(&self.0)
+    }
+}
+
+/// `50%`, from `Percent(0.5)`.
+impl fmt::Display for Percent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.percent(), f)?;
+        f.write_str("%")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_includes_unit() {
+        assert_eq!(format!("{}", Seconds(120)), "120 s");
+        assert_eq!(format!("{}", TempRaw(62.5)), "62.5°");
+        assert_eq!(format!("{:.1}", TempRaw(-40.0)), "-40.0°");
+        assert_eq!(format!("{}", Percent(0.25)), "25%");
+    }
+
+    #[test]
+    fn percent_is_a_fraction() {
+        assert_eq!(Percent::from_percent(50.0), Percent(0.5));
+        assert_eq!(Percent(0.75).percent(), 75.0);
+        assert!(Percent::FULL.is_valid());
+        assert!(!Percent(50.0).is_valid());
+    }
+
+    #[test]
+    fn seconds_from_whole_values_only() {
+        assert_eq!(Seconds::try_from(120.0), Ok(Seconds(120)));
+        assert_eq!(Seconds::try_from(1.5), Err(()));
+        assert_eq!(Seconds::try_from(-1.0), Err(()));
+        assert_eq!(Seconds::try_from(70000.0), Err(()));
+        assert_eq!(Seconds::try_from(f32::NAN), Err(()));
+    }
+}
@@ -0,0 +1,111 @@
+//! Read-back verification of writes.
+//!
+//! A write the controller acknowledges has usually taken, but not always: a
+//! value can be clamped, or silently dropped while the front panel menu is
+//! open. With [`Syl2381::set_verify_writes`] on, every write is followed by a
+//! read of the same register, and a mismatch is reported as
+//! [`Error::VerificationFailed`].
+
+use crate::clock::DriverClock;
+use crate::regs;
+use crate::transport::Transport;
+use crate::{Error, Syl2381};
+
+impl<UART, CLOCK> Syl2381<UART, CLOCK>
+where
+    UART: Transport,
+    CLOCK: DriverClock,
+{
+    pub fn verify_writes(&self) -> bool {
+        self.verify
+    }
+
+    /// Read back every register after writing it.
+    ///
+    /// Id and bAud are never read back, since the controller may stop
+    /// answering at the old settings; see [`Syl2381::change_unit_id`] and
+    /// [`Syl2381::change_baud_rate`] instead. Values are compared exactly, as
+    /// stored.
+    pub fn set_verify_writes(&mut self, verify: bool) {
+        self.verify = verify;
+    }
+
+    /// Check a holding write of `expected` took, if verification is on.
+    pub(crate) fn verify_written(
+        &mut self,
+        reg: regs::Reg,
+        expected: f32,
+    ) -> crate::Result<(), UART> {
+        if !self.verify || reg.addr == regs::ID.addr || reg.addr == regs::BAUD.addr {
+            return Ok(());
+        }
+        let actual = self.get_holding_raw(reg)?;
+        if actual != expected {
+            return Err(Error::VerificationFailed { expected, actual });
+        }
+        Ok(())
+    }
+
+    /// Check a coil write of `expected` took, if verification is on.
+    pub(crate) fn verify_coil(
+        &mut self,
+        reg: regs::Reg,
+        expected: bool,
+    ) -> crate::Result<(), UART> {
+        if !self.verify {
+            return Ok(());
+        }
+        let actual = self.get_coil(reg)?;
+        if actual != expected {
+            return Err(Error::VerificationFailed {
+                expected: expected as u8 as f32,
+                actual: actual as u8 as f32,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockSerial;
+    use crate::TempRaw;
+
+    #[test]
+    fn reads_back_each_write() {
+        let mut port = MockSerial::new();
+        port.ack_f32(1, regs::SV.addr);
+        port.respond_f32(1, 65.0);
+        port.ack_f32(1, regs::SV.addr);
+        port.respond_f32(1, 65.0);
+        port.respond(&[1, 0x05, 0x00, 0x01, 0xFF, 0x00]);
+        port.respond(&[1, 0x01, 1, 0x00]);
+        let mut pid = Syl2381::new(1, port);
+        pid.live
+            .assume_sensor(crate::InputType::K, crate::DisplayUnit::Celsius);
+        pid.set_verify_writes(true);
+
+        assert!(pid.set_sv(TempRaw(65.0)).is_ok());
+        assert!(matches!(
+            pid.set_sv(TempRaw(70.0)),
+            Err(Error::VerificationFailed { expected, actual }) if expected == 70.0 && actual == 65.0
+        ));
+        assert!(matches!(
+            pid.set_manual_mode(true),
+            Err(Error::VerificationFailed { expected, actual }) if expected == 1.0 && actual == 0.0
+        ));
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn skips_communication_settings() {
+        let mut port = MockSerial::new();
+        port.ack_f32(1, regs::ID.addr);
+        let mut pid = Syl2381::new(1, port);
+        pid.set_verify_writes(true);
+
+        assert!(pid.set_unit_id(7).is_ok());
+        assert!(pid.port.rx.is_empty());
+    }
+}
@@ -0,0 +1,166 @@
+//! Status change events.
+//!
+//! Monitoring code rarely cares that the alarm is on, only that it just came
+//! on. A [`StatusWatcher`] remembers the last status flags it saw and turns
+//! each poll into the list of flags that changed, optionally calling back for
+//! each one.
+
+use core::fmt;
+
+use crate::clock::DriverClock;
+use crate::transport::Transport;
+use crate::{Labels, Status, Syl2381};
+
+/// A status flag that changed between two polls.
+#[derive(Clone, Copy, fmt::Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StatusEvent {
+    AutotuneStarted,
+    AutotuneFinished,
+    ManualEntered,
+    ManualExited,
+    CoolingStarted,
+    CoolingStopped,
+    SettingEntered,
+    SettingExited,
+    AnomalyDetected,
+    AnomalyCleared,
+    AlarmAsserted,
+    AlarmCleared,
+}
+
+impl fmt::Display for StatusEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <Self as fmt::Debug>::fmt(self, f)
+    }
+}
+
+/// Events from one poll, at most one per flag.
+pub type Events = heapless::Vec<StatusEvent, 6>;
+
+/// Each flag's bit, with the events for it going on and off.
+const FLAGS: [(u8, StatusEvent, StatusEvent); 6] = [
+    (
+        0,
+        StatusEvent::AutotuneStarted,
+        StatusEvent::AutotuneFinished,
+    ),
+    (1, StatusEvent::ManualEntered, StatusEvent::ManualExited),
+    (2, StatusEvent::CoolingStarted, StatusEvent::CoolingStopped),
+    (3, StatusEvent::SettingEntered, StatusEvent::SettingExited),
+    (4, StatusEvent::AnomalyDetected, StatusEvent::AnomalyCleared),
+    (5, StatusEvent::AlarmAsserted, StatusEvent::AlarmCleared),
+];
+
+#[derive(Default)]
+pub struct StatusWatcher {
+    last: Option<Status>,
+    on_event: Option<fn(&Labels, StatusEvent)>,
+}
+
+impl StatusWatcher {
+    pub fn new() -> Self {
+        StatusWatcher::default()
+    }
+
+    /// Call `f` with the controller's labels for each event [`Self::poll`]
+    /// finds.
+    pub fn on_event(&mut self, f: fn(&Labels, StatusEvent)) {
+        self.on_event = Some(f);
+    }
+
+    /// The status flags last seen.
+    pub fn last(&self) -> Option<Status> {
+        self.last
+    }
+
+    /// Forget the last status, so the next one is taken as a baseline.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// Compare `status` with the last one seen.
+    ///
+    /// The first status only sets the baseline and yields no events.
+    pub fn update(&mut self, status: Status) -> Events {
+        let mut events = Events::new();
+        if let Some(Status(last)) = self.last.replace(status) {
+            let changed = last ^ status.0;
+            for (bit, on, off) in FLAGS {
+                if changed & (1 << bit) != 0 {
+                    let event = if status.0 & (1 << bit) != 0 { on } else { off };
+                    // One event per flag, so this can't overflow.
+                    let _ = events.push(event);
+                }
+            }
+        }
+        events
+    }
+
+    /// Read the status flags (AT through AL1_STA, one request) and report
+    /// what changed since the last poll.
+    pub fn poll<UART, CLOCK>(
+        &mut self,
+        pid: &mut Syl2381<UART, CLOCK>,
+    ) -> crate::Result<Events, UART>
+    where
+        UART: Transport,
+        CLOCK: DriverClock,
+    {
+        let events = self.update(pid.get_status()?);
+        if let Some(f) = self.on_event {
+            for &event in &events {
+                f(pid.labels(), event);
+            }
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockSerial;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn reports_only_changes() {
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x01, 1, 0b00_0001]);
+        port.respond(&[1, 0x01, 1, 0b00_0001]);
+        port.respond(&[1, 0x01, 1, 0b10_0010]);
+        let mut pid = Syl2381::new(1, port);
+        let mut watcher = StatusWatcher::new();
+
+        assert!(watcher.poll(&mut pid).unwrap().is_empty());
+        assert!(watcher.poll(&mut pid).unwrap().is_empty());
+        assert_eq!(
+            watcher.poll(&mut pid).unwrap(),
+            [
+                StatusEvent::AutotuneFinished,
+                StatusEvent::ManualEntered,
+                StatusEvent::AlarmAsserted,
+            ]
+        );
+        assert!(pid.port.rx.is_empty());
+    }
+
+    #[test]
+    fn calls_back_for_each_event() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        let mut watcher = StatusWatcher::new();
+        watcher.on_event(|_, _| {
+            CALLS.fetch_add(1, Ordering::Relaxed);
+        });
+        let mut port = MockSerial::new();
+        port.respond(&[1, 0x01, 1, 0b00_0000]);
+        port.respond(&[1, 0x01, 1, 0b01_1000]);
+        let mut pid = Syl2381::new(1, port);
+
+        watcher.poll(&mut pid).unwrap();
+        watcher.poll(&mut pid).unwrap();
+        assert_eq!(CALLS.load(Ordering::Relaxed), 2);
+    }
+}
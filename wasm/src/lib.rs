@@ -0,0 +1,87 @@
+//! Links the async driver into a `wasm32-unknown-unknown` module.
+//!
+//! A real commissioning page would back [`Page`] with WebSerial or a
+//! WebSocket through `wasm-bindgen`; here the bytes come from buffers the
+//! host fills in, which is enough to prove nothing needs a UART, threads or
+//! a clock.
+
+#![no_std]
+
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+
+use embedded_io_async::{ErrorKind, ErrorType, Read, Write};
+use syl2381::exchange::{Exchange, Op};
+use syl2381::{regs, Filter, Param, Seconds, Syl2381Async, TempRaw};
+
+/// A transport the page feeds from JavaScript.
+struct Page {
+    rx: [u8; 256],
+    rx_len: usize,
+    rx_pos: usize,
+}
+
+static mut RX: [u8; 256] = [0; 256];
+
+impl ErrorType for Page {
+    type Error = ErrorKind;
+}
+
+impl Read for Page {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = buf.len().min(self.rx_len - self.rx_pos);
+        buf[..n].copy_from_slice(&self.rx[self.rx_pos..self.rx_pos + n]);
+        self.rx_pos += n;
+        Ok(n)
+    }
+}
+
+impl Write for Page {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(buf.len())
+    }
+}
+
+/// Where the host writes the response bytes before calling [`read_pv`].
+#[no_mangle]
+pub extern "C" fn rx_buffer() -> *mut u8 {
+    core::ptr::addr_of_mut!(RX).cast()
+}
+
+/// Run every async operation against `len` bytes of canned response.
+#[no_mangle]
+pub extern "C" fn read_pv(len: usize) -> f32 {
+    let page = Page {
+        rx: unsafe { RX },
+        rx_len: len.min(256),
+        rx_pos: 0,
+    };
+    let mut pid = Syl2381Async::new(1, page);
+    let ops = async {
+        let _ = pid.get_status().await;
+        let _ = pid.set_sv(TempRaw(65.0)).await;
+        let _ = pid.set_i(Seconds(120)).await;
+        let _ = pid.set_filter(Filter::Weak).await;
+        let _ = pid.set_manual_mode(false).await;
+        let _ = pid.get_input_sensor_type().await;
+        let _ = pid.get_display_unit().await;
+        pid.get_pv().await
+    };
+    let mut cx = Context::from_waker(Waker::noop());
+    let pv = match pin!(ops).poll(&mut cx) {
+        Poll::Ready(Ok(TempRaw(pv))) => pv,
+        _ => f32::NAN,
+    };
+
+    if let Ok(exchange) = Exchange::new(1, Op::Get(regs::SV)) {
+        let _ = exchange.request();
+    }
+    let _ = Param::Sv.meta();
+    pv
+}
+
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    loop {}
+}